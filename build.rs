@@ -1,3 +1,13 @@
+use std::path::PathBuf;
+use std::{env, fs};
+
 fn main() {
+    // This crate's memory.x rather than ch32-hal's, which would hand the
+    // settings page to the image
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::copy("memory.x", out.join("memory.x")).unwrap();
+    println!("cargo:rustc-link-search={}", out.display());
+    println!("cargo:rerun-if-changed=memory.x");
+
     println!("cargo:rustc-link-arg-bins=-Tlink.x");
 }
@@ -0,0 +1,14 @@
+/* CH32V203K8T6, less the last 4 KiB page of flash: src/flash.rs keeps the
+   settings there, so the image must never grow into it. */
+MEMORY
+{
+    FLASH : ORIGIN = 0x00000000, LENGTH = 60K
+    RAM : ORIGIN = 0x20000000, LENGTH = 20K
+}
+
+REGION_ALIAS("REGION_TEXT", FLASH);
+REGION_ALIAS("REGION_RODATA", FLASH);
+REGION_ALIAS("REGION_DATA", RAM);
+REGION_ALIAS("REGION_BSS", RAM);
+REGION_ALIAS("REGION_HEAP", RAM);
+REGION_ALIAS("REGION_STACK", RAM);
@@ -0,0 +1,62 @@
+//! ADC1, converting one channel at a time on request: the die temperature,
+//! and the button ladder where there is one. A conversion spins for its
+//! 20 µs with nothing awaited in between, so no two ever overlap.
+
+use ch32_hal::pac;
+
+/// Channel of the internal temperature sensor.
+pub const TEMPERATURE: u8 = 16;
+
+/// Setting the longest sample time, 239.5 ADC clocks, which the sensor and
+/// the ladder's resistors both want.
+const SAMPLE_TIME: u8 = 0b111;
+
+/// Brings the ADC up with the temperature sensor powered, and calibrates
+/// it. Once, before any `sample`.
+pub fn init() {
+    pac::RCC.apb2pcenr().modify(|w| w.set_adc1en(true));
+
+    // Converted on SWSTART
+    pac::ADC1.ctlr2().modify(|w| {
+        w.set_extsel(0b111);
+        w.set_exttrig(true);
+        w.set_tsvrefe(true);
+        w.set_adon(true);
+    });
+
+    pac::ADC1.ctlr2().modify(|w| w.set_rstcal(true));
+    while pac::ADC1.ctlr2().read().rstcal() {}
+    pac::ADC1.ctlr2().modify(|w| w.set_cal(true));
+    while pac::ADC1.ctlr2().read().cal() {}
+
+    set_sample_time(TEMPERATURE);
+}
+
+/// Gives `channel` the longest sample time.
+fn set_sample_time(channel: u8) {
+    match channel {
+        0..=9 => pac::ADC1
+            .samptr2()
+            .modify(|w| w.set_smp(channel as usize, SAMPLE_TIME)),
+        _ => pac::ADC1
+            .samptr1()
+            .modify(|w| w.set_smp(channel as usize - 10, SAMPLE_TIME)),
+    }
+}
+
+/// Readies PA`channel`, channels 0-7 being PA0-PA7, as an analog input.
+pub fn init_pin(channel: u8) {
+    pac::GPIOA.cfglr().modify(|w| {
+        w.set_mode(channel as usize, 0b00);
+        w.set_cnf(channel as usize, 0b00);
+    });
+    set_sample_time(channel);
+}
+
+/// One 12-bit conversion of `channel`.
+pub fn sample(channel: u8) -> u16 {
+    pac::ADC1.rsqr3().modify(|w| w.set_sq(0, channel));
+    pac::ADC1.ctlr2().modify(|w| w.set_swstart(true));
+    while !pac::ADC1.statr().read().eoc() {}
+    pac::ADC1.rdatar().read().data()
+}
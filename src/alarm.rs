@@ -0,0 +1,84 @@
+//! Wake-up alarm on the buzzer. The alarm rings once per matching minute
+//! of the time shown, in the display zone, while that time is radio-synced
+//! or set by hand. A short press snoozes it for `SNOOZE_MS`; a long one
+//! stops it. The state machine is `alarm::machine` of the lib.
+
+use core::cell::Cell;
+
+pub use ch32_jjy_clock::alarm::machine::{Alarm, Phase, Setting};
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_time::Timer;
+
+use crate::{buzzer, log, monotonic, settings, syslog, timebase};
+
+static ALARM: Mutex<CriticalSectionRawMutex, Cell<Alarm>> = Mutex::new(Cell::new(Alarm::new()));
+
+pub fn setting() -> Setting {
+    settings::get().alarm
+}
+
+pub fn set(setting: Setting) {
+    settings::update(|s| s.alarm = setting);
+}
+
+fn with_alarm<R>(f: impl FnOnce(&mut Alarm) -> R) -> R {
+    ALARM.lock(|a| {
+        let mut alarm = a.get();
+        let result = f(&mut alarm);
+        a.set(alarm);
+        result
+    })
+}
+
+/// Whether the alarm has the buzzer; other sounds wait for it.
+pub fn is_ringing() -> bool {
+    ALARM.lock(|a| matches!(a.get().phase(), Phase::Ringing { .. }))
+}
+
+/// `Alarm::snooze` on the alarm; a press it answers does nothing else.
+pub fn snooze() -> bool {
+    with_alarm(|a| a.snooze(monotonic::now_ms()))
+}
+
+/// `Alarm::stop` on the alarm; a press it answers does nothing else.
+pub fn stop() -> bool {
+    with_alarm(|a| a.stop())
+}
+
+/// Beep, pause, beep, long pause, in ms, for as long as it rings.
+const PATTERN_MS: [u64; 4] = [100, 100, 100, 700];
+
+/// How often an idle or snoozed alarm looks at the time.
+const POLL_MS: u64 = 500;
+
+#[embassy_executor::task]
+pub async fn alarm_task() {
+    let mut ringing = false;
+
+    loop {
+        let now = monotonic::now_ms();
+        let clock = timebase::load()
+            .filter(|b| b.synced || b.manual)
+            .map(|b| b.wall_clock(now, settings::zone()));
+        let phase = with_alarm(|a| a.update(setting(), clock, now));
+
+        let Phase::Ringing { .. } = phase else {
+            ringing = false;
+            Timer::after_millis(POLL_MS).await;
+            continue;
+        };
+
+        if !ringing {
+            log::info!("Alarm ringing");
+            syslog::emit(syslog::Event::Alarm("wake-up"));
+            ringing = true;
+        }
+
+        for (i, ms) in PATTERN_MS.iter().enumerate() {
+            buzzer::set(i % 2 == 0);
+            Timer::after_millis(*ms).await;
+        }
+        buzzer::set(false);
+    }
+}
@@ -0,0 +1,217 @@
+//! When the alarm rings, apart from the buzzer and the buttons.
+
+use crate::timebase::WallClock;
+
+/// The alarm time as shown, and whether it is armed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Setting {
+    pub hour: u8,
+    pub minute: u8,
+    pub enabled: bool,
+}
+
+impl Setting {
+    pub const DEFAULT: Setting = Setting {
+        hour: 7,
+        minute: 0,
+        enabled: false,
+    };
+}
+
+const SNOOZE_MS: u64 = 5 * 60 * 1000;
+
+/// An alarm nobody answers gives up after this long.
+const RING_MS: u64 = 10 * 60 * 1000;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Phase {
+    Idle,
+    Ringing { since: u64 },
+    Snoozed { until: u64 },
+}
+
+/// The minute an alarm last went off, so it fires once however often the
+/// clock is read within it.
+type Minute = (u16, u8, u8, u8, u8);
+
+fn minute_of(clock: &WallClock) -> Minute {
+    (clock.year, clock.month, clock.day, clock.hour, clock.minute)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Alarm {
+    phase: Phase,
+    fired: Option<Minute>,
+}
+
+impl Alarm {
+    pub const fn new() -> Self {
+        Alarm {
+            phase: Phase::Idle,
+            fired: None,
+        }
+    }
+
+    /// Moves on to `now`, with `clock` the time shown if it is one to ring
+    /// by, and returns the phase.
+    pub fn update(&mut self, setting: Setting, clock: Option<WallClock>, now: u64) -> Phase {
+        self.phase = match self.phase {
+            _ if !setting.enabled => Phase::Idle,
+            Phase::Ringing { since } if now - since >= RING_MS => Phase::Idle,
+            Phase::Snoozed { until } if now >= until => Phase::Ringing { since: now },
+            phase => phase,
+        };
+
+        if let Some(clock) = clock
+            && setting.enabled
+            && (clock.hour, clock.minute) == (setting.hour, setting.minute)
+            && self.fired != Some(minute_of(&clock))
+        {
+            self.fired = Some(minute_of(&clock));
+            self.phase = Phase::Ringing { since: now };
+        }
+
+        self.phase
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Snoozes a ringing alarm, and returns whether it was ringing.
+    pub fn snooze(&mut self, now: u64) -> bool {
+        let Phase::Ringing { .. } = self.phase else {
+            return false;
+        };
+
+        self.phase = Phase::Snoozed {
+            until: now + SNOOZE_MS,
+        };
+        true
+    }
+
+    /// Stops a ringing or snoozed alarm, and returns whether there was one.
+    pub fn stop(&mut self) -> bool {
+        core::mem::replace(&mut self.phase, Phase::Idle) != Phase::Idle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARMED: Setting = Setting {
+        hour: 6,
+        minute: 30,
+        enabled: true,
+    };
+
+    /// 2025-05-`day` at `hour:minute`.
+    fn at(day: u8, hour: u8, minute: u8) -> Option<WallClock> {
+        Some(WallClock {
+            year: 2025,
+            month: 5,
+            day,
+            weekday: None,
+            hour,
+            minute,
+            second: 0,
+            millisecond: 0,
+        })
+    }
+
+    /// An alarm that started ringing at 1000.
+    fn ringing() -> Alarm {
+        let mut alarm = Alarm::new();
+        assert_eq!(
+            alarm.update(ARMED, at(3, 6, 30), 1000),
+            Phase::Ringing { since: 1000 }
+        );
+        alarm
+    }
+
+    #[test]
+    fn rings_once_at_the_set_minute() {
+        let mut alarm = Alarm::new();
+        assert_eq!(alarm.update(ARMED, at(3, 6, 29), 0), Phase::Idle);
+
+        let mut alarm = ringing();
+        assert!(alarm.stop());
+
+        // Still the same minute: stopped stays stopped
+        assert_eq!(alarm.update(ARMED, at(3, 6, 30), 30_000), Phase::Idle);
+
+        // The next day it rings again
+        assert_eq!(
+            alarm.update(ARMED, at(4, 6, 30), 90_000),
+            Phase::Ringing { since: 90_000 }
+        );
+    }
+
+    #[test]
+    fn disarmed_or_unset_time_never_rings() {
+        let disarmed = Setting {
+            enabled: false,
+            ..ARMED
+        };
+        let mut alarm = Alarm::new();
+        assert_eq!(alarm.update(disarmed, at(3, 6, 30), 0), Phase::Idle);
+        assert_eq!(alarm.update(ARMED, None, 0), Phase::Idle);
+
+        // Disarming silences a ringing alarm
+        let mut alarm = ringing();
+        assert_eq!(alarm.update(disarmed, at(3, 6, 30), 2000), Phase::Idle);
+    }
+
+    #[test]
+    fn snooze_rings_again_later() {
+        let mut alarm = ringing();
+        assert!(alarm.snooze(2000));
+        assert_eq!(
+            alarm.phase(),
+            Phase::Snoozed {
+                until: 2000 + SNOOZE_MS
+            }
+        );
+
+        // Past the alarm minute, so only the snooze brings it back
+        assert_eq!(
+            alarm.update(ARMED, at(3, 6, 31), 1999 + SNOOZE_MS),
+            Phase::Snoozed {
+                until: 2000 + SNOOZE_MS
+            }
+        );
+        assert_eq!(
+            alarm.update(ARMED, at(3, 6, 35), 2000 + SNOOZE_MS),
+            Phase::Ringing {
+                since: 2000 + SNOOZE_MS
+            }
+        );
+    }
+
+    #[test]
+    fn presses_only_answer_an_alarm() {
+        let mut alarm = Alarm::new();
+        assert!(!alarm.snooze(0));
+        assert!(!alarm.stop());
+
+        let mut alarm = ringing();
+        assert!(alarm.snooze(2000));
+        assert!(!alarm.snooze(3000));
+        assert!(alarm.stop());
+        assert_eq!(alarm.phase(), Phase::Idle);
+    }
+
+    #[test]
+    fn unanswered_alarm_gives_up() {
+        let mut alarm = ringing();
+        assert_eq!(
+            alarm.update(ARMED, at(3, 6, 39), 999 + RING_MS),
+            Phase::Ringing { since: 1000 }
+        );
+        assert_eq!(
+            alarm.update(ARMED, at(3, 6, 40), 1000 + RING_MS),
+            Phase::Idle
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! LCD backlight on PB1, PWM from TIM3 CH4 through a transistor, dimmed
+//! overnight by the decoded time.
+
+use core::cell::Cell;
+use core::cmp::Ordering;
+
+use ch32_hal::peripherals::TIM3;
+use ch32_hal::timer::Channel;
+use ch32_hal::timer::simple_pwm::SimplePwm;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_time::Timer;
+
+use crate::{monotonic, power, settings, state, timebase};
+
+const CHANNEL: Channel = Channel::Ch4;
+
+/// Brightness in percent of full duty.
+const FULL: u8 = 100;
+const NIGHT_LEVEL: u8 = 10;
+
+/// Local hours of the dim period.
+const NIGHT_START_HOUR: u8 = 22;
+const NIGHT_END_HOUR: u8 = 7;
+
+/// A button press at night brings full brightness back for this long.
+const BOOST_MS: u64 = 10_000;
+
+/// The level moves 1% per step, so a full-range change takes a second.
+const STEP_MS: u64 = 10;
+
+/// Once at its target, the level is only checked this often.
+const SETTLED_MS: u64 = 250;
+
+/// Until when a button press holds full brightness.
+static BOOST_UNTIL: Mutex<CriticalSectionRawMutex, Cell<u64>> = Mutex::new(Cell::new(0));
+
+/// Level by the schedule: off in standby and during night blanking, dim at
+/// night once the time is known, full otherwise.
+fn scheduled(now: u64) -> u8 {
+    let current = state::snapshot();
+    let base = timebase::load();
+    if current.standby || power::is_blanked(&current, base, now) {
+        return 0;
+    }
+
+    match base {
+        Some(base) => {
+            let hour = base.wall_clock(now, settings::zone()).hour;
+            match hour >= NIGHT_START_HOUR || hour < NIGHT_END_HOUR {
+                true => NIGHT_LEVEL,
+                false => FULL,
+            }
+        }
+        None => FULL,
+    }
+}
+
+fn target(now: u64) -> u8 {
+    match now < BOOST_UNTIL.lock(|b| b.get()) {
+        true => FULL,
+        false => scheduled(now),
+    }
+}
+
+/// Brings full brightness back for `BOOST_MS` if the backlight is dimmed,
+/// and returns whether it was.
+pub fn boost() -> bool {
+    let now = monotonic::now_ms();
+    if !(1..FULL).contains(&target(now)) {
+        return false;
+    }
+
+    BOOST_UNTIL.lock(|b| b.set(now + BOOST_MS));
+    true
+}
+
+fn set_level(pwm: &mut SimplePwm<'static, TIM3>, level: u8) {
+    pwm.set_duty(CHANNEL, pwm.get_max_duty() * level as u32 / FULL as u32);
+}
+
+/// Eases the backlight toward its target level.
+#[embassy_executor::task]
+pub async fn backlight_task(mut pwm: SimplePwm<'static, TIM3>) {
+    let mut level = FULL;
+    set_level(&mut pwm, level);
+    pwm.enable(CHANNEL);
+
+    loop {
+        match level.cmp(&target(monotonic::now_ms())) {
+            Ordering::Less => level += 1,
+            Ordering::Greater => level -= 1,
+            Ordering::Equal => {
+                Timer::after_millis(SETTLED_MS).await;
+                continue;
+            }
+        }
+
+        set_level(&mut pwm, level);
+        Timer::after_millis(STEP_MS).await;
+    }
+}
@@ -0,0 +1,19 @@
+use ch32_hal::pac;
+
+/// BKP data registers survive a reset, and a power loss too when VBAT is
+/// fitted. They must be unlocked once per boot before writing.
+pub fn init() {
+    pac::RCC.apb1pcenr().modify(|w| {
+        w.set_pwren(true);
+        w.set_bkpen(true);
+    });
+    pac::PWR.ctlr().modify(|w| w.set_dbp(true));
+}
+
+pub fn read(index: usize) -> u16 {
+    pac::BKP.datar(index).read().d()
+}
+
+pub fn write(index: usize, value: u16) {
+    pac::BKP.datar(index).write(|w| w.set_d(value));
+}
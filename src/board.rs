@@ -0,0 +1,206 @@
+//! What is wired to which pin. Exactly one `board-*` feature picks the
+//! board, whose `take` splits the peripherals into the bundles the tasks
+//! need, so `main` only hands them out. Inputs and outputs a board lacks
+//! are `None`; a feature needing pins the board lacks fails to build.
+//!
+//! `board-auto` builds both and picks one at boot by the strap divider on
+//! PA1, read before anything else touches the pins, so one image runs on
+//! either. It builds only what both boards have room for.
+
+#[cfg(not(any(
+    feature = "board-standard",
+    feature = "board-minimal",
+    feature = "board-auto"
+)))]
+compile_error!("pick a board: `board-standard`, `board-minimal` or `board-auto`");
+#[cfg(any(
+    all(feature = "board-standard", feature = "board-minimal"),
+    all(feature = "board-standard", feature = "board-auto"),
+    all(feature = "board-minimal", feature = "board-auto")
+))]
+compile_error!("pick one of `board-standard`, `board-minimal` and `board-auto`");
+
+#[cfg(any(feature = "i2c-display", feature = "button-ladder"))]
+use ch32_hal::Peri;
+use ch32_hal::exti::ExtiInput;
+use ch32_hal::gpio::Output;
+use ch32_hal::mode::Blocking;
+#[cfg(feature = "button-ladder")]
+use ch32_hal::peripherals::PA0;
+#[cfg(feature = "backlight")]
+use ch32_hal::peripherals::TIM3;
+#[cfg(any(feature = "nmea", feature = "console"))]
+use ch32_hal::peripherals::USART2;
+#[cfg(feature = "i2c-display")]
+use ch32_hal::peripherals::{I2C1, PB6, PB7};
+#[cfg(feature = "backlight")]
+use ch32_hal::timer::simple_pwm::SimplePwm;
+#[cfg(feature = "console")]
+use ch32_hal::usart::UartRx;
+use ch32_hal::usart::UartTx;
+
+#[cfg(all(
+    not(feature = "monitor"),
+    not(feature = "seven-segment"),
+    not(feature = "i2c-display")
+))]
+use crate::display::DisplayPins;
+#[cfg(not(feature = "replay"))]
+use crate::receiver::JjyInput;
+#[cfg(feature = "seven-segment")]
+use crate::segments::ShiftRegisters;
+use crate::syslog;
+
+#[cfg(feature = "board-auto")]
+use ch32_hal::pac;
+#[cfg(feature = "board-auto")]
+use ch32_jjy_clock::board::strap;
+
+#[cfg(feature = "board-auto")]
+use crate::{adc, log};
+
+#[cfg(any(feature = "board-minimal", feature = "board-auto"))]
+mod minimal;
+#[cfg(any(feature = "board-standard", feature = "board-auto"))]
+mod standard;
+
+/// ADC channel of the strap divider, on PA1.
+#[cfg(feature = "board-auto")]
+const STRAP_CHANNEL: u8 = 1;
+/// The strap reading of each board: the standard board's 10k over 10k,
+/// and the minimal board's 20k over 10k, from 3.3 V.
+#[cfg(feature = "board-auto")]
+const STRAP_NOMINAL: [u16; 2] = [2048, 1365];
+
+/// The bus an I2C display is on.
+#[cfg(feature = "i2c-display")]
+pub struct I2cBus {
+    pub i2c: Peri<'static, I2C1>,
+    pub scl: Peri<'static, PB6>,
+    pub sda: Peri<'static, PB7>,
+}
+
+/// What a button on the ladder does.
+#[cfg(feature = "button-ladder")]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Button {
+    /// As the power button: standby, or the zone while held.
+    Power,
+    /// As the page button: pages, or setting the time while held.
+    Page,
+    /// Snoozes the alarm, or stops it when held.
+    Snooze,
+    /// Steps the display zone east.
+    Zone,
+}
+
+/// Buttons on a resistor ladder into PA0, ADC channel 0, in place of the
+/// power and page buttons. `below` has the reading each button ends below,
+/// ascending, as `button::ladder` takes them.
+#[cfg(feature = "button-ladder")]
+pub struct ButtonLadder {
+    /// Held so nothing else drives the pin.
+    pub pin: Peri<'static, PA0>,
+    pub below: &'static [u16],
+    pub buttons: &'static [Button],
+}
+
+#[cfg(feature = "button-ladder")]
+impl ButtonLadder {
+    pub const CHANNEL: u8 = 0;
+    /// Buttons a ladder can have; past six the readings crowd together
+    /// too closely to tell apart.
+    pub const MAX_BUTTONS: usize = 6;
+}
+
+pub struct Board {
+    #[cfg(all(
+        not(feature = "monitor"),
+        not(feature = "seven-segment"),
+        not(feature = "i2c-display")
+    ))]
+    pub display: DisplayPins,
+    #[cfg(all(
+        not(feature = "monitor"),
+        not(feature = "seven-segment"),
+        feature = "i2c-display"
+    ))]
+    pub display: I2cBus,
+    #[cfg(feature = "seven-segment")]
+    pub segments: ShiftRegisters,
+
+    /// Edges of the receiver output; `replay` makes its own.
+    #[cfg(not(feature = "replay"))]
+    pub receiver: JjyInput,
+    /// The receiver output again, timed by its wake-ups to check the
+    /// capture against.
+    #[cfg(feature = "cross-check")]
+    pub cross_check: ExtiInput<'static>,
+    /// Receiver power, at `RECEIVER_ON` from the start.
+    pub pon: Output<'static>,
+
+    /// Held at boot to choose the station; standby and zone otherwise.
+    #[cfg(not(feature = "button-ladder"))]
+    pub power_button: ExtiInput<'static>,
+    #[cfg(not(feature = "button-ladder"))]
+    pub page_button: ExtiInput<'static>,
+    #[cfg(feature = "button-ladder")]
+    pub ladder: ButtonLadder,
+    pub buzzer: Output<'static>,
+    pub fault_led: Output<'static>,
+    #[cfg(feature = "status-led")]
+    pub status_led: Output<'static>,
+    #[cfg(feature = "backlight")]
+    pub backlight: SimplePwm<'static, TIM3>,
+
+    pub syslog: UartTx<'static, syslog::Port, Blocking>,
+    #[cfg(feature = "nmea")]
+    pub nmea: UartTx<'static, USART2, Blocking>,
+    #[cfg(feature = "console")]
+    pub console: (
+        UartTx<'static, USART2, Blocking>,
+        UartRx<'static, USART2, Blocking>,
+    ),
+
+    /// Logged with the time of each edge.
+    pub trigger: Option<ExtiInput<'static>>,
+    /// Mains zero crossings, for the grid frequency.
+    pub zero_cross: Option<ExtiInput<'static>>,
+    pub pps: Option<Output<'static>>,
+}
+
+impl Board {
+    #[cfg(feature = "board-standard")]
+    pub fn take(p: ch32_hal::Peripherals) -> Board {
+        standard::take(p)
+    }
+
+    #[cfg(feature = "board-minimal")]
+    pub fn take(p: ch32_hal::Peripherals) -> Board {
+        minimal::take(p)
+    }
+
+    /// Reads the strap and takes that board. One not recognised is taken
+    /// as the minimal board, which drives the fewest pins.
+    #[cfg(feature = "board-auto")]
+    pub fn take(p: ch32_hal::Peripherals) -> Board {
+        pac::RCC.apb2pcenr().modify(|w| w.set_iopaen(true));
+        adc::init_pin(STRAP_CHANNEL);
+        let raw = adc::sample(STRAP_CHANNEL);
+
+        match strap::revision(raw, &STRAP_NOMINAL) {
+            Some(0) => {
+                log::info!("Strap {}: standard board", raw);
+                standard::take(p)
+            }
+            Some(_) => {
+                log::info!("Strap {}: minimal board", raw);
+                minimal::take(p)
+            }
+            None => {
+                log::error!("Strap {} is of no board; taking the minimal one", raw);
+                minimal::take(p)
+            }
+        }
+    }
+}
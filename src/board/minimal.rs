@@ -0,0 +1,85 @@
+//! The fewest wires that make a clock: a CH32V203K8T6 with the LCD on a
+//! 4-bit bus with RW grounded, the receiver, both buttons, the buzzer and
+//! the fault LED, on the same pins as the standard board. Nothing else is
+//! wired: no trigger, mains or PPS, and no second UART.
+
+#[cfg(any(feature = "seven-segment", feature = "i2c-display"))]
+compile_error!("the minimal board only has the parallel LCD");
+#[cfg(feature = "backlight")]
+compile_error!("the minimal board has no backlight on PB1");
+#[cfg(any(feature = "nmea", feature = "console"))]
+compile_error!("the minimal board has no USART2 on PA2/PA3");
+#[cfg(feature = "status-led")]
+compile_error!("the minimal board has no status LED on PA1");
+
+use ch32_hal::Peripherals;
+#[cfg(any(not(feature = "button-ladder"), feature = "exti-timing"))]
+use ch32_hal::exti::ExtiInput;
+#[cfg(any(not(feature = "button-ladder"), feature = "exti-timing"))]
+use ch32_hal::gpio::Pull;
+use ch32_hal::gpio::{Level, Output};
+use ch32_hal::usart::UartTx;
+
+use super::Board;
+#[cfg(feature = "button-ladder")]
+use super::{Button, ButtonLadder};
+#[cfg(not(feature = "monitor"))]
+use crate::display;
+#[cfg(not(feature = "replay"))]
+use crate::receiver::JjyInput;
+
+/// The button ladder, as on the standard board: 10k from PA0 to 3.3 V,
+/// and each button from PA0 to ground through 0, 2.2k, 6.8k and 22k.
+#[cfg(feature = "button-ladder")]
+const LADDER_BELOW: [u16; 4] = [369, 1197, 2236, 3455];
+#[cfg(feature = "button-ladder")]
+const LADDER_BUTTONS: [Button; 4] = [Button::Power, Button::Page, Button::Snooze, Button::Zone];
+
+pub fn take(p: Peripherals) -> Board {
+    #[cfg(not(feature = "monitor"))]
+    let display = display::DisplayPins::new(
+        p.PB0.into(), // rs
+        p.PA8.into(), // enable
+        display::DataBus::four([
+            p.PA4.into(), // d4
+            p.PA5.into(), // d5
+            p.PA6.into(), // d6
+            p.PA7.into(), // d7
+        ]),
+    );
+
+    #[cfg(all(feature = "exti-timing", not(feature = "replay")))]
+    let receiver = JjyInput::new(ExtiInput::new(p.PA9, p.EXTI9, Pull::None));
+    #[cfg(not(any(feature = "exti-timing", feature = "replay", feature = "cross-check")))]
+    let receiver = JjyInput::new(p.PA9, p.TIM1);
+    #[cfg(feature = "cross-check")]
+    let (receiver, cross_check) = JjyInput::new(p.PA9, p.TIM1, p.EXTI9);
+
+    Board {
+        #[cfg(not(feature = "monitor"))]
+        display,
+        #[cfg(not(feature = "replay"))]
+        receiver,
+        #[cfg(feature = "cross-check")]
+        cross_check,
+        pon: Output::new(p.PB3, crate::RECEIVER_ON, Default::default()),
+        #[cfg(not(feature = "button-ladder"))]
+        power_button: ExtiInput::new(p.PB5, p.EXTI5, Pull::Up),
+        #[cfg(not(feature = "button-ladder"))]
+        page_button: ExtiInput::new(p.PA11, p.EXTI11, Pull::Up),
+        // PA0, free on the 4-bit LCD bus
+        #[cfg(feature = "button-ladder")]
+        ladder: ButtonLadder {
+            pin: p.PA0,
+            below: &LADDER_BELOW,
+            buttons: &LADDER_BUTTONS,
+        },
+        buzzer: Output::new(p.PB7, Level::Low, Default::default()),
+        fault_led: Output::new(p.PB4, Level::Low, Default::default()),
+        // USART1 remapped to PB6, PA9 being the receiver input
+        syslog: UartTx::new_blocking(p.USART1, p.PB6, Default::default()).unwrap(),
+        trigger: None,
+        zero_cross: None,
+        pps: None,
+    }
+}
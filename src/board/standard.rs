@@ -0,0 +1,200 @@
+//! The board the firmware grew up on: a CH32V203K8T6 with the LCD on
+//! PA0-PA8 and PB0/PB1, the receiver on PA9/PB3, and every optional input
+//! and output wired.
+
+use ch32_hal::Peripherals;
+use ch32_hal::exti::ExtiInput;
+use ch32_hal::gpio::{Level, Output, Pull};
+use ch32_hal::usart::UartTx;
+
+use super::Board;
+#[cfg(feature = "i2c-display")]
+use super::I2cBus;
+#[cfg(feature = "button-ladder")]
+use super::{Button, ButtonLadder};
+#[cfg(all(
+    not(feature = "monitor"),
+    not(feature = "seven-segment"),
+    not(feature = "i2c-display")
+))]
+use crate::display;
+#[cfg(not(feature = "replay"))]
+use crate::receiver::JjyInput;
+#[cfg(feature = "seven-segment")]
+use crate::segments::ShiftRegisters;
+
+/// The button ladder: 10k from PA0 to 3.3 V, and each button from PA0 to
+/// ground through 0, 2.2k, 6.8k and 22k. Each reading ends halfway to the
+/// next button's, of 0, 738, 1657 and 2815, or 4095 with none down.
+#[cfg(feature = "button-ladder")]
+const LADDER_BELOW: [u16; 4] = [369, 1197, 2236, 3455];
+#[cfg(feature = "button-ladder")]
+const LADDER_BUTTONS: [Button; 4] = [Button::Power, Button::Page, Button::Snooze, Button::Zone];
+
+pub fn take(p: Peripherals) -> Board {
+    #[cfg(all(
+        not(feature = "monitor"),
+        not(feature = "seven-segment"),
+        not(feature = "i2c-display"),
+        not(feature = "lcd-4bit")
+    ))]
+    let data = display::DataBus::eight([
+        p.PA0.into(), // d0
+        p.PA1.into(), // d1
+        p.PA2.into(), // d2
+        p.PA3.into(), // d3
+        p.PA4.into(), // d4
+        p.PA5.into(), // d5
+        p.PA6.into(), // d6
+        p.PA7.into(), // d7
+    ]);
+    // PA0-PA3 stay free
+    #[cfg(all(
+        not(feature = "monitor"),
+        not(feature = "seven-segment"),
+        not(feature = "i2c-display"),
+        feature = "lcd-4bit"
+    ))]
+    let data = display::DataBus::four([
+        p.PA4.into(), // d4
+        p.PA5.into(), // d5
+        p.PA6.into(), // d6
+        p.PA7.into(), // d7
+    ]);
+
+    #[cfg(all(
+        not(feature = "monitor"),
+        not(feature = "seven-segment"),
+        not(feature = "i2c-display"),
+        not(feature = "lcd-rw-grounded")
+    ))]
+    let display = display::DisplayPins::new(
+        p.PB0.into(), // rs
+        p.PB1.into(), // rw
+        p.PA8.into(), // enable
+        data,
+    );
+    // PB1 stays free
+    #[cfg(all(
+        not(feature = "monitor"),
+        not(feature = "seven-segment"),
+        not(feature = "i2c-display"),
+        feature = "lcd-rw-grounded"
+    ))]
+    let display = display::DisplayPins::new(
+        p.PB0.into(), // rs
+        p.PA8.into(), // enable
+        data,
+    );
+
+    // PCF8574 backpack or SSD1306 on I2C1, SCL PB6 and SDA PB7
+    #[cfg(all(
+        not(feature = "monitor"),
+        not(feature = "seven-segment"),
+        feature = "i2c-display"
+    ))]
+    let display = I2cBus {
+        i2c: p.I2C1,
+        scl: p.PB6,
+        sda: p.PB7,
+    };
+
+    // 74HC595 chain on the freed LCD pins
+    #[cfg(feature = "seven-segment")]
+    let segments = ShiftRegisters::new(
+        Output::new(p.PA7, Level::Low, Default::default()), // data
+        Output::new(p.PA5, Level::Low, Default::default()), // shift clock
+        Output::new(p.PA4, Level::Low, Default::default()), // latch
+    );
+
+    // 1 kHz PWM on PB1, free with RW grounded
+    #[cfg(feature = "backlight")]
+    let backlight = {
+        use ch32_hal::time::Hertz;
+        use ch32_hal::timer::simple_pwm::{PwmPin, SimplePwm};
+
+        let pin = PwmPin::new_ch4::<0>(p.PB1);
+        SimplePwm::new(
+            p.TIM3,
+            None,
+            None,
+            None,
+            Some(pin),
+            Hertz::khz(1),
+            Default::default(),
+        )
+    };
+
+    // 外部割り込みを使用する場合のタスク
+    // ExtiInputを作成するために、ペリフェラル、EXTIライン、プル設定が必要
+    #[cfg(all(feature = "exti-timing", not(feature = "replay")))]
+    let receiver = JjyInput::new(ExtiInput::new(p.PA9, p.EXTI9, Pull::None));
+    #[cfg(not(any(feature = "exti-timing", feature = "replay", feature = "cross-check")))]
+    let receiver = JjyInput::new(p.PA9, p.TIM1);
+    #[cfg(feature = "cross-check")]
+    let (receiver, cross_check) = JjyInput::new(p.PA9, p.TIM1, p.EXTI9);
+
+    // USART1 remapped to PB6, PA9 being the receiver input
+    #[cfg(not(feature = "i2c-display"))]
+    let syslog = UartTx::new_blocking(p.USART1, p.PB6, Default::default()).unwrap();
+    // PB6 is SCL of the display
+    #[cfg(feature = "i2c-display")]
+    let syslog = UartTx::new_blocking(p.USART2, p.PA2, Default::default()).unwrap();
+
+    // Time sentences on USART2 TX, PA2, which the 4-bit LCD bus leaves
+    // free
+    #[cfg(feature = "nmea")]
+    let nmea = UartTx::new_blocking(p.USART2, p.PA2, Default::default()).unwrap();
+
+    // Console on USART2, PA2 out and PA3 in, which the 4-bit LCD bus
+    // leaves free
+    #[cfg(feature = "console")]
+    let console = ch32_hal::usart::Uart::new_blocking(p.USART2, p.PA3, p.PA2, Default::default())
+        .unwrap()
+        .split();
+
+    // PB7 is SDA of the display
+    #[cfg(not(feature = "i2c-display"))]
+    let buzzer = Output::new(p.PB7, Level::Low, Default::default());
+    #[cfg(feature = "i2c-display")]
+    let buzzer = Output::new(p.PA0, Level::Low, Default::default());
+
+    Board {
+        #[cfg(not(any(feature = "monitor", feature = "seven-segment")))]
+        display,
+        #[cfg(feature = "seven-segment")]
+        segments,
+        #[cfg(not(feature = "replay"))]
+        receiver,
+        #[cfg(feature = "cross-check")]
+        cross_check,
+        pon: Output::new(p.PB3, crate::RECEIVER_ON, Default::default()),
+        #[cfg(not(feature = "button-ladder"))]
+        power_button: ExtiInput::new(p.PB5, p.EXTI5, Pull::Up),
+        #[cfg(not(feature = "button-ladder"))]
+        page_button: ExtiInput::new(p.PA11, p.EXTI11, Pull::Up),
+        // PA0, free on the 4-bit LCD bus
+        #[cfg(feature = "button-ladder")]
+        ladder: ButtonLadder {
+            pin: p.PA0,
+            below: &LADDER_BELOW,
+            buttons: &LADDER_BUTTONS,
+        },
+        buzzer,
+        fault_led: Output::new(p.PB4, Level::Low, Default::default()),
+        // PA1, free on the 4-bit LCD bus
+        #[cfg(feature = "status-led")]
+        status_led: Output::new(p.PA1, Level::Low, Default::default()),
+        #[cfg(feature = "backlight")]
+        backlight,
+        syslog,
+        #[cfg(feature = "nmea")]
+        nmea,
+        #[cfg(feature = "console")]
+        console,
+        trigger: Some(ExtiInput::new(p.PA10, p.EXTI10, Pull::Up)),
+        zero_cross: Some(ExtiInput::new(p.PA15, p.EXTI15, Pull::None)),
+        // PA12 is USB D+, unused otherwise
+        pps: Some(Output::new(p.PA12, Level::Low, Default::default())),
+    }
+}
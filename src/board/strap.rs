@@ -0,0 +1,45 @@
+//! Which board the firmware woke up on, from the reading of a strap
+//! divider that each board revision fits with resistors of its own.
+//! Hardware-free.
+
+/// How far a reading may stray from its board's and still count: the
+/// resistors' 1% both ways, and noise, with room to spare between boards.
+pub const TOLERANCE: u16 = 300;
+
+/// The board whose reading in `nominal` `raw` is within `TOLERANCE` of, by
+/// its index, or `None` where it is near none of them.
+pub fn revision(raw: u16, nominal: &[u16]) -> Option<usize> {
+    nominal
+        .iter()
+        .position(|&nominal| raw.abs_diff(nominal) <= TOLERANCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10k over 10k, and 20k over 10k.
+    const NOMINAL: [u16; 2] = [2048, 1365];
+
+    #[test]
+    fn readings_near_a_board_pick_it() {
+        for (raw, board) in [
+            (2048, Some(0)),
+            (1748, Some(0)),
+            (2348, Some(0)),
+            (1365, Some(1)),
+            (1065, Some(1)),
+            (1665, Some(1)),
+        ] {
+            assert_eq!(revision(raw, &NOMINAL), board, "{raw}");
+        }
+    }
+
+    #[test]
+    fn readings_near_no_board_pick_none() {
+        // A missing strap floats, or reads the rails
+        for raw in [0, 700, 2400, 4095] {
+            assert_eq!(revision(raw, &NOMINAL), None, "{raw}");
+        }
+    }
+}
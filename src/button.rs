@@ -0,0 +1,152 @@
+//! Short and long presses, and auto-repeat while held, from a debounced
+//! button level. Hardware-free.
+
+pub mod ladder;
+
+/// Held this long, a press is long rather than short.
+const LONG_PRESS_MS: u64 = 1000;
+
+/// Held on past a long press, a repeat comes this often.
+const REPEAT_MS: u64 = 200;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Press {
+    /// Released before it became long.
+    Short,
+    /// Held for `LONG_PRESS_MS`, reported without waiting for the release.
+    Long,
+    /// Still held after a long press, every `REPEAT_MS`.
+    Repeat,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    Released,
+    Held { next_at: u64, long: bool },
+}
+
+pub struct Presses {
+    state: State,
+}
+
+impl Presses {
+    pub const fn new() -> Self {
+        Presses {
+            state: State::Released,
+        }
+    }
+
+    /// Takes the button level at `now`, after each edge and at `deadline`.
+    pub fn update(&mut self, pressed: bool, now: u64) -> Option<Press> {
+        match (self.state, pressed) {
+            (State::Released, false) => None,
+            (State::Released, true) => {
+                self.state = State::Held {
+                    next_at: now + LONG_PRESS_MS,
+                    long: false,
+                };
+                None
+            }
+            (State::Held { long, .. }, false) => {
+                self.state = State::Released;
+                (!long).then_some(Press::Short)
+            }
+            (State::Held { next_at, .. }, true) if now < next_at => None,
+            (State::Held { long, .. }, true) => {
+                self.state = State::Held {
+                    next_at: now + REPEAT_MS,
+                    long: true,
+                };
+                Some(if long { Press::Repeat } else { Press::Long })
+            }
+        }
+    }
+
+    /// When a held button next needs an `update` without an edge, if it
+    /// is held.
+    pub fn deadline(&self) -> Option<u64> {
+        match self.state {
+            State::Released => None,
+            State::Held { next_at, .. } => Some(next_at),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Presses reported for button levels at the given times.
+    fn presses(levels: &[(bool, u64)]) -> Vec<(Press, u64)> {
+        let mut presses = Presses::new();
+        levels
+            .iter()
+            .filter_map(|(pressed, now)| presses.update(*pressed, *now).map(|p| (p, *now)))
+            .collect()
+    }
+
+    #[test]
+    fn short_press_reports_on_release() {
+        assert_eq!(presses(&[(true, 0), (false, 80)]), [(Press::Short, 80)]);
+        assert_eq!(
+            presses(&[(true, 0), (false, LONG_PRESS_MS - 1)]),
+            [(Press::Short, LONG_PRESS_MS - 1)]
+        );
+    }
+
+    #[test]
+    fn long_press_reports_while_held_and_not_on_release() {
+        let levels = [
+            (true, 0),
+            (true, LONG_PRESS_MS),
+            (false, LONG_PRESS_MS + 50),
+        ];
+        assert_eq!(presses(&levels), [(Press::Long, LONG_PRESS_MS)]);
+    }
+
+    #[test]
+    fn holding_on_repeats() {
+        let mut presses = Presses::new();
+        assert_eq!(presses.deadline(), None);
+
+        assert_eq!(presses.update(true, 0), None);
+        assert_eq!(presses.deadline(), Some(LONG_PRESS_MS));
+
+        assert_eq!(presses.update(true, LONG_PRESS_MS), Some(Press::Long));
+        let mut at = LONG_PRESS_MS;
+        for _ in 0..3 {
+            at += REPEAT_MS;
+            assert_eq!(presses.deadline(), Some(at));
+            assert_eq!(presses.update(true, at - 1), None);
+            assert_eq!(presses.update(true, at), Some(Press::Repeat));
+        }
+
+        assert_eq!(presses.update(false, at + 10), None);
+        assert_eq!(presses.deadline(), None);
+    }
+
+    #[test]
+    fn bounces_settling_on_the_same_level_change_nothing() {
+        // Edges that settle back to where they were pass the debounce as
+        // the same level again, and must not restart the long press
+        let levels = [
+            (false, 0),
+            (true, 100),
+            (true, 130),
+            (true, 600),
+            (true, 100 + LONG_PRESS_MS),
+            (false, 1200),
+            (false, 1230),
+        ];
+        assert_eq!(presses(&levels), [(Press::Long, 100 + LONG_PRESS_MS)]);
+
+        let levels = [
+            (false, 0),
+            (false, 30),
+            (true, 60),
+            (true, 90),
+            (false, 200),
+        ];
+        assert_eq!(presses(&levels), [(Press::Short, 200)]);
+    }
+}
@@ -0,0 +1,94 @@
+//! Which of several buttons on a resistor ladder is down, from readings
+//! of the one ADC input they share. Each button pulls the input to a
+//! voltage of its own, and only one is read at a time. Hardware-free.
+
+/// Readings in a row that must agree on the button before it counts. A
+/// press passes through the voltages in between as the contact settles.
+const SETTLE_READINGS: u8 = 2;
+
+pub struct Ladder {
+    /// The reading each button ends below, ascending; above the last, none
+    /// is down.
+    below: &'static [u16],
+    reading: Option<usize>,
+    agreeing: u8,
+    held: Option<usize>,
+}
+
+impl Ladder {
+    pub const fn new(below: &'static [u16]) -> Self {
+        Ladder {
+            below,
+            reading: None,
+            agreeing: 0,
+            held: None,
+        }
+    }
+
+    /// The button a single reading `raw` is of, by its index.
+    pub fn classify(&self, raw: u16) -> Option<usize> {
+        self.below.iter().position(|&below| raw < below)
+    }
+
+    /// Takes a reading and returns the button held, which changes once
+    /// `SETTLE_READINGS` in a row agree on another.
+    pub fn update(&mut self, raw: u16) -> Option<usize> {
+        let reading = self.classify(raw);
+        if reading == self.reading {
+            self.agreeing = self.agreeing.saturating_add(1);
+        } else {
+            self.reading = reading;
+            self.agreeing = 1;
+        }
+
+        if self.agreeing >= SETTLE_READINGS {
+            self.held = reading;
+        }
+        self.held
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10k to the supply, and 0, 2.2k, 6.8k or 22k to ground.
+    const BELOW: [u16; 4] = [369, 1197, 2236, 3455];
+
+    #[test]
+    fn readings_fall_between_the_thresholds() {
+        let ladder = Ladder::new(&BELOW);
+        for (raw, button) in [
+            (0, Some(0)),
+            (368, Some(0)),
+            (369, Some(1)),
+            (738, Some(1)),
+            (1657, Some(2)),
+            (2815, Some(3)),
+            (3454, Some(3)),
+            (3455, None),
+            (4095, None),
+        ] {
+            assert_eq!(ladder.classify(raw), button, "{raw}");
+        }
+    }
+
+    #[test]
+    fn a_button_counts_once_the_readings_agree() {
+        let mut ladder = Ladder::new(&BELOW);
+        assert_eq!(ladder.update(4095), None);
+
+        // Settling through button 2's voltage on the way to button 1's
+        assert_eq!(ladder.update(1700), None);
+        assert_eq!(ladder.update(740), None);
+        assert_eq!(ladder.update(735), Some(1));
+        assert_eq!(ladder.update(741), Some(1));
+
+        // A single stray reading holds on to it
+        assert_eq!(ladder.update(4095), Some(1));
+        assert_eq!(ladder.update(738), Some(1));
+
+        assert_eq!(ladder.update(4090), Some(1));
+        assert_eq!(ladder.update(4095), None);
+    }
+}
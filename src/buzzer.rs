@@ -0,0 +1,33 @@
+use core::cell::RefCell;
+
+use ch32_hal::gpio::{Level, Output};
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_time::Timer;
+
+/// Gate the buzzer with the received carrier, to hear the pulses while
+/// positioning the antenna. Alarms still chime on top.
+pub const AUDIO_MONITOR: bool = false;
+
+/// Active buzzer on PB7 (PA0 with an I2C display): driving it high sounds its
+/// built-in ~1 kHz tone.
+static BUZZER: Mutex<CriticalSectionRawMutex, RefCell<Option<Output<'static>>>> =
+    Mutex::new(RefCell::new(None));
+
+pub fn init(output: Output<'static>) {
+    BUZZER.lock(|b| b.replace(Some(output)));
+}
+
+pub fn set(on: bool) {
+    BUZZER.lock(|b| {
+        if let Some(output) = b.borrow_mut().as_mut() {
+            output.set_level(if on { Level::High } else { Level::Low });
+        }
+    });
+}
+
+pub async fn chime(ms: u64) {
+    set(true);
+    Timer::after_millis(ms).await;
+    set(false);
+}
@@ -0,0 +1,419 @@
+//! Command console on USART2, PA2 out and PA3 in, for poking at the
+//! running decoder. One command per line:
+//!
+//! - `status`: last sync, its age, signal quality, drift and the
+//!   reception counters
+//! - `stats reset`: start the reception counters over
+//! - `dump`: the symbols of the frame in progress
+//! - `frames`: the last few frames that ended, newest first, each with its
+//!   age, station and outcome, its symbols, and a digit per second of how
+//!   far inside its window the pulse was, 9 on the center
+//! - `tol <percent>`: classification tolerance, up to the station's
+//!   weak-reception one, until the reception profile next changes
+//! - `resync`: drop the frame in progress and wait for a fresh start
+//! - `tz <+hh[:mm]>`: zone the time is shown in
+//! - `alarm <hh:mm>` or `alarm off`: arm the alarm for a time shown, or
+//!   disarm it
+//! - `pips <off|hourly|nhk>`: the hourly time signal
+//! - `inject <yyyy-mm-dd> <hh:mm[:ss]>`: run from a time in the zone shown,
+//!   as if set by hand, to try alarms, zone changes and midnight without
+//!   waiting for them; the next decode replaces it
+//! - `config dump`: the settings record, magic, version and CRC included,
+//!   in hex
+//! - `config load <hex>`: take a record `config dump` printed, on this
+//!   clock or another, if its checks pass
+//! - `line <template>` or `line off`: lay out the time page's bottom line
+//!   by a template, such as `%Y-%m-%d %a`, or go back to the built-in one;
+//!   the tokens are listed in `display::template` of the lib
+//!
+//! Anything else prints the list. The line editing and parsing are in
+//! `console::parse` of the lib.
+
+use core::cell::{Cell, RefCell};
+use core::fmt::{self, Write};
+
+use ch32_hal::mode::Blocking;
+use ch32_hal::pac;
+use ch32_hal::peripherals::USART2;
+use ch32_hal::usart::{UartRx, UartTx};
+use ch32_jjy_clock::console::parse::{Command, LineBuffer, parse};
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+use embassy_time::Timer;
+
+use crate::decoder::{Abort, BitWidth, Event};
+use crate::stats::Stats;
+use crate::temperature::Celsius;
+use crate::timebase::{LeapSecond, Slew, TimeBase};
+use crate::{StatusUpdate, alarm, monotonic, pips, quality, settings, state, timebase};
+
+/// What the decoder task is asked to do; it picks requests up between
+/// pulses.
+#[derive(Clone, Copy, Debug)]
+pub enum Request {
+    TolerancePermille(u32),
+    Resync,
+    ResetStats,
+}
+
+pub static REQUESTS: Channel<CriticalSectionRawMutex, Request, 2> = Channel::new();
+
+// The frame in progress, as last published by the decoder task
+static FRAME: Mutex<CriticalSectionRawMutex, Cell<([BitWidth; 60], usize)>> =
+    Mutex::new(Cell::new(([BitWidth::Unknown; 60], 0)));
+
+/// Makes `received` the frame `dump` shows.
+pub fn publish_frame(received: &[BitWidth]) {
+    let mut frame = [BitWidth::Unknown; 60];
+    frame[..received.len()].copy_from_slice(received);
+    FRAME.lock(|f| f.set((frame, received.len())));
+}
+
+/// Frames `frames` shows.
+const FRAMES: usize = 4;
+
+#[derive(Clone, Copy)]
+struct EndedFrame {
+    ended_at: u64,
+    station: &'static str,
+    outcome: &'static str,
+    symbols: [BitWidth; 60],
+    confidence: [u8; 60],
+    len: usize,
+}
+
+// Newest first
+static HISTORY: Mutex<CriticalSectionRawMutex, RefCell<[Option<EndedFrame>; FRAMES]>> =
+    Mutex::new(RefCell::new([None; FRAMES]));
+
+/// What became of a frame that ended with `event`.
+fn outcome(event: Event) -> &'static str {
+    match event {
+        Event::Aborted {
+            reason: Abort::UnknownWidth,
+            ..
+        } => "unknown-width",
+        Event::Aborted {
+            reason: Abort::Layout(reason),
+            ..
+        } => reason,
+        Event::Rejected(e) => e.as_str(),
+        Event::Unconfirmed(_) => "unconfirmed",
+        Event::Decoded(_) => "ok",
+        Event::None | Event::FrameStarted => "",
+    }
+}
+
+/// Keeps a frame that ended at `at`, with the `event` that ended it, for
+/// `frames`, in place of the oldest.
+pub fn record_frame(
+    station: &'static str,
+    event: Event,
+    symbols: &[BitWidth],
+    confidence: &[u8],
+    at: u64,
+) {
+    let mut frame = EndedFrame {
+        ended_at: at,
+        station,
+        outcome: outcome(event),
+        symbols: [BitWidth::Unknown; 60],
+        confidence: [0; 60],
+        len: symbols.len(),
+    };
+    frame.symbols[..symbols.len()].copy_from_slice(symbols);
+    frame.confidence[..confidence.len()].copy_from_slice(confidence);
+
+    HISTORY.lock(|h| {
+        let mut history = h.borrow_mut();
+        history.rotate_right(1);
+        history[0] = Some(frame);
+    });
+}
+
+fn symbol(bit: BitWidth) -> char {
+    match bit {
+        BitWidth::Marker => 'M',
+        BitWidth::Short => 'S',
+        BitWidth::Long => 'L',
+        BitWidth::Medium => 'm',
+        BitWidth::Double => 'D',
+        BitWidth::Unknown => '?',
+    }
+}
+
+const HELP: &str = "status | stats reset | dump | frames | tol <percent> | resync\r\n\
+                    tz <+hh[:mm]> | alarm <hh:mm|off> | pips <off|hourly|nhk>\r\n\
+                    inject <yyyy-mm-dd> <hh:mm[:ss]>\r\n\
+                    config dump | config load <hex> | line <template|off>\r\n";
+
+/// Pulse counts are listed in this order.
+const WIDTHS: [BitWidth; 6] = [
+    BitWidth::Marker,
+    BitWidth::Short,
+    BitWidth::Long,
+    BitWidth::Medium,
+    BitWidth::Double,
+    BitWidth::Unknown,
+];
+
+struct Out<'a>(&'a mut UartTx<'static, USART2, Blocking>);
+
+impl Write for Out<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.blocking_write(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+fn status(out: &mut Out) -> fmt::Result {
+    let current = state::snapshot();
+    let now = monotonic::now_ms();
+
+    match (timebase::load(), current.last_sync_at()) {
+        (Some(base), Some(at)) => {
+            let c = base.wall_clock(at, settings::zone());
+            write!(
+                out,
+                "last sync {:0>2}:{:0>2}:{:0>2}, {} min ago\r\n",
+                c.hour,
+                c.minute,
+                c.second,
+                (now - at) / (60 * 1000)
+            )?;
+        }
+        _ => out.write_str("not synced\r\n")?,
+    }
+
+    write!(
+        out,
+        "quality {}/{}\r\n",
+        current.signal_quality,
+        quality::MAX_SCORE
+    )?;
+
+    match timebase::load().and_then(|b| b.drift_ppm) {
+        Some(ppm) => write!(out, "drift {ppm:+} ppm\r\n")?,
+        None => out.write_str("drift -\r\n")?,
+    }
+
+    match current.temperature {
+        Some(tenths) => write!(out, "temperature {} C\r\n", Celsius(tenths))?,
+        None => out.write_str("temperature -\r\n")?,
+    }
+
+    stats(out, &current.stats, now)
+}
+
+fn stats(out: &mut Out, stats: &Stats, now: u64) -> fmt::Result {
+    write!(out, "pulses {}", stats.total_pulses())?;
+    for bit in WIDTHS {
+        write!(out, " {}{}", symbol(bit), stats.pulses[bit as usize])?;
+    }
+
+    write!(
+        out,
+        "\r\nframes {} started, {} completed\r\n",
+        stats.frames_started, stats.frames_completed
+    )?;
+    write!(
+        out,
+        "errors parity {} range {} marker {}\r\n",
+        stats.parity_errors, stats.range_errors, stats.marker_errors
+    )?;
+    write!(out, "recovered {} seconds from parity\r\n", stats.recovered)?;
+
+    write!(out, "signal lost {} times\r\n", stats.signal_losses)?;
+    write!(out, "decodes {}", stats.decodes)?;
+    match stats.last_decode_at {
+        Some(at) => write!(out, ", last {} min ago\r\n", (now - at) / (60 * 1000)),
+        None => out.write_str("\r\n"),
+    }
+}
+
+fn dump(out: &mut Out) -> fmt::Result {
+    let (frame, len) = FRAME.lock(|f| f.get());
+
+    write!(out, "{len}/60 ")?;
+    for bit in &frame[..len] {
+        out.write_char(symbol(*bit))?;
+    }
+    out.write_str("\r\n")
+}
+
+fn frames(out: &mut Out) -> fmt::Result {
+    // Copied out, so the critical section doesn't last the whole write
+    let history = HISTORY.lock(|h| *h.borrow());
+    let now = monotonic::now_ms();
+
+    if history[0].is_none() {
+        return out.write_str("no frames\r\n");
+    }
+
+    for frame in history.iter().flatten() {
+        write!(
+            out,
+            "{} s ago {} {}\r\n",
+            (now - frame.ended_at) / 1000,
+            frame.station,
+            frame.outcome
+        )?;
+        for bit in &frame.symbols[..frame.len] {
+            out.write_char(symbol(*bit))?;
+        }
+        out.write_str("\r\n")?;
+        for confidence in &frame.confidence[..frame.len] {
+            write!(out, "{confidence}")?;
+        }
+        out.write_str("\r\n")?;
+    }
+    Ok(())
+}
+
+/// Runs the clock from `second` of `day` of `year` in the zone shown,
+/// starting now.
+fn inject(out: &mut Out, year: u16, day: u16, second: u32) -> fmt::Result {
+    let base = TimeBase {
+        system_time: monotonic::now_ms(),
+        clock: second,
+        year,
+        day,
+        weekday: Some(timebase::weekday_of(year, day)),
+        leap_second: LeapSecond::None,
+        drift_ppm: None,
+        synced: false,
+        manual: true,
+        slew: Slew::NONE,
+    }
+    .in_utc(settings::zone());
+    state::post(StatusUpdate::TimeBaseUpdate(base));
+
+    write!(
+        out,
+        "injected {:0>2}:{:0>2}:{:0>2}\r\n",
+        second / 3600,
+        second / 60 % 60,
+        second % 60
+    )
+}
+
+fn config_dump(out: &mut Out) -> fmt::Result {
+    out.write_str("config ")?;
+    for word in settings::record() {
+        write!(out, "{word:04X}")?;
+    }
+    out.write_str("\r\n")
+}
+
+fn config_load(out: &mut Out, words: &[u16; settings::WORDS]) -> fmt::Result {
+    let zone = settings::zone();
+    let Some(loaded) = settings::load_record(words) else {
+        return out.write_str("config refused: bad magic, version or CRC\r\n");
+    };
+
+    // Shows the new zone, as `tz` does
+    if loaded.zone_minutes != zone {
+        state::post(StatusUpdate::Zone(loaded.zone_minutes));
+    }
+    out.write_str("config loaded\r\n")
+}
+
+fn run(out: &mut Out, command: Option<Command>) -> fmt::Result {
+    match command {
+        Some(Command::Status) => status(out),
+        Some(Command::ResetStats) => {
+            let _ = REQUESTS.try_send(Request::ResetStats);
+            out.write_str("stats reset\r\n")
+        }
+        Some(Command::Dump) => dump(out),
+        Some(Command::Frames) => frames(out),
+        Some(Command::Tolerance(percent)) => {
+            let _ = REQUESTS.try_send(Request::TolerancePermille(percent * 10));
+            write!(out, "tolerance {percent}%\r\n")
+        }
+        Some(Command::Resync) => {
+            let _ = REQUESTS.try_send(Request::Resync);
+            out.write_str("resync\r\n")
+        }
+        Some(Command::Zone(minutes)) => {
+            state::post(StatusUpdate::Zone(minutes));
+            let (sign, hours, minutes) = timebase::zone_parts(minutes);
+            write!(out, "UTC{}{hours:0>2}:{minutes:0>2}\r\n", sign as char)
+        }
+        Some(Command::Alarm(time)) => {
+            let setting = match time {
+                Some((hour, minute)) => alarm::Setting {
+                    hour,
+                    minute,
+                    enabled: true,
+                },
+                None => alarm::Setting {
+                    enabled: false,
+                    ..alarm::setting()
+                },
+            };
+            alarm::set(setting);
+
+            let state = if setting.enabled { "on" } else { "off" };
+            write!(
+                out,
+                "alarm {:0>2}:{:0>2} {state}\r\n",
+                setting.hour, setting.minute
+            )
+        }
+        Some(Command::Pips(mode)) => {
+            pips::set_mode(mode);
+            write!(out, "pips {}\r\n", mode.as_str())
+        }
+        Some(Command::Inject { year, day, second }) => inject(out, year, day, second),
+        Some(Command::ConfigDump) => config_dump(out),
+        Some(Command::ConfigLoad(words)) => config_load(out, &words),
+        Some(Command::StatusLine(template)) => {
+            settings::update(|s| s.status_line = template);
+            match template.is_empty() {
+                true => out.write_str("line off\r\n"),
+                false => write!(out, "line {}\r\n", template.as_str()),
+            }
+        }
+        None => out.write_str(HELP),
+    }
+}
+
+/// Bytes arrive one register at a time; at this interval, typing is never
+/// too fast, though a pasted line may lose characters.
+const POLL_MS: u64 = 10;
+
+fn byte_ready() -> bool {
+    pac::USART2.statr().read().rxne()
+}
+
+#[embassy_executor::task]
+pub async fn console_task(
+    mut tx: UartTx<'static, USART2, Blocking>,
+    mut rx: UartRx<'static, USART2, Blocking>,
+) {
+    let mut line = LineBuffer::new();
+
+    loop {
+        Timer::after_millis(POLL_MS).await;
+
+        while byte_ready() {
+            let mut byte = [0];
+            if rx.blocking_read(&mut byte).is_err() {
+                continue;
+            }
+
+            let mut out = Out(&mut tx);
+            // Echo, so the terminal shows what is typed
+            let _ = out.write_str(match byte[0] {
+                b'\r' | b'\n' => "\r\n",
+                0x08 | 0x7F => "\x08 \x08",
+                _ => core::str::from_utf8(&byte).unwrap_or(""),
+            });
+
+            if let Some(text) = line.push(byte[0]) {
+                let _ = run(&mut out, parse(text));
+            }
+        }
+    }
+}
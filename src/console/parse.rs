@@ -0,0 +1,412 @@
+//! Line editing and command parsing, apart from the UART.
+
+use crate::display::template::Template;
+use crate::pips::mode::Mode;
+use crate::timebase;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Command {
+    Status,
+    ResetStats,
+    Dump,
+    Frames,
+    /// Percent either side of the nominal widths.
+    Tolerance(u32),
+    Resync,
+    /// Minutes east of UTC.
+    Zone(i32),
+    /// Hour and minute to arm the alarm for, or `None` to disarm it.
+    Alarm(Option<(u8, u8)>),
+    Pips(Mode),
+    /// A date and time in the zone shown, to run the clock from as if set
+    /// by hand: the day of year and the second of that day.
+    Inject {
+        year: u16,
+        day: u16,
+        second: u32,
+    },
+    ConfigDump,
+    /// The settings record to load, as `ConfigDump` printed it; checked
+    /// by the settings before any of it is taken.
+    ConfigLoad([u16; CONFIG_WORDS]),
+    /// The template for the time page's bottom line, or an empty one for
+    /// the built-in layout.
+    StatusLine(Template),
+}
+
+/// Half-words in the settings record, which `config` prints and takes as
+/// four hex digits each, most significant first: five of settings, ten of
+/// the bottom line template and the CRC.
+pub const CONFIG_WORDS: usize = 16;
+
+/// The decoder task further limits a tolerance to the station's own
+/// weak-reception one, the widest its windows stay apart at.
+const TOLERANCE_PERCENT: core::ops::RangeInclusive<u32> = 1..=50;
+
+const ZONE_MINUTES: core::ops::RangeInclusive<i32> = -12 * 60..=14 * 60;
+
+/// `+hh`, `-hh`, `+hh:mm` or `-hh:mm`, in minutes.
+fn parse_zone(zone: &str) -> Option<i32> {
+    let sign = match zone.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+
+    let (hours, minutes) = zone[1..].split_once(':').unwrap_or((&zone[1..], "0"));
+    let hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+
+    Some(sign * (hours as i32 * 60 + minutes as i32)).filter(|m| ZONE_MINUTES.contains(m))
+}
+
+/// `hh:mm`, as hour and minute.
+fn parse_time(time: &str) -> Option<(u8, u8)> {
+    let (hour, minute) = time.split_once(':')?;
+    let hour: u8 = hour.parse().ok()?;
+    let minute: u8 = minute.parse().ok()?;
+
+    (hour < 24 && minute < 60).then_some((hour, minute))
+}
+
+/// Years a date can be in; `timebase` counts from 2000.
+const YEARS: core::ops::RangeInclusive<u16> = 2000..=2099;
+
+/// `yyyy-mm-dd`, as year and day of year.
+fn parse_date(date: &str) -> Option<(u16, u16)> {
+    let mut fields = date.splitn(3, '-');
+    let year: u16 = fields.next()?.parse().ok()?;
+    let month: u8 = fields.next()?.parse().ok()?;
+    let day: u8 = fields.next()?.parse().ok()?;
+
+    if !YEARS.contains(&year) {
+        return None;
+    }
+    Some((year, timebase::day_of_year(year, month, day)?))
+}
+
+/// `hh:mm` or `hh:mm:ss`, as the second of the day.
+fn parse_second(time: &str) -> Option<u32> {
+    let (time, second) = match time.len() {
+        8 => time.rsplit_once(':')?,
+        _ => (time, "0"),
+    };
+    let (hour, minute) = parse_time(time)?;
+    let second: u8 = second.parse().ok()?;
+
+    (second < 60).then_some(hour as u32 * 3600 + minute as u32 * 60 + second as u32)
+}
+
+/// `CONFIG_WORDS` half-words in hex, four digits each.
+fn parse_record(hex: &str) -> Option<[u16; CONFIG_WORDS]> {
+    // `from_str_radix` would take a sign as well
+    if hex.len() != CONFIG_WORDS * 4 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut words = [0; CONFIG_WORDS];
+    for (word, digits) in words.iter_mut().zip(hex.as_bytes().chunks(4)) {
+        *word = u16::from_str_radix(core::str::from_utf8(digits).ok()?, 16).ok()?;
+    }
+    Some(words)
+}
+
+/// The command on `line`, or `None` if it isn't one.
+pub fn parse(line: &str) -> Option<Command> {
+    // A template has spaces of its own, so takes the rest of the line
+    if let Some(("line", template)) = line.trim().split_once(|c: char| c.is_ascii_whitespace()) {
+        return match template.trim_start() {
+            "off" => Some(Command::StatusLine(Template::NONE)),
+            template => Template::new(template).map(Command::StatusLine),
+        };
+    }
+
+    let mut words = line.split_ascii_whitespace();
+    let command = match (words.next()?, words.next()) {
+        ("status", None) => Command::Status,
+        ("stats", Some("reset")) => Command::ResetStats,
+        ("dump", None) => Command::Dump,
+        ("frames", None) => Command::Frames,
+        ("resync", None) => Command::Resync,
+        ("tol", Some(percent)) => {
+            let percent = percent.parse().ok()?;
+            if !TOLERANCE_PERCENT.contains(&percent) {
+                return None;
+            }
+            Command::Tolerance(percent)
+        }
+        ("tz", Some(zone)) => Command::Zone(parse_zone(zone)?),
+        ("alarm", Some("off")) => Command::Alarm(None),
+        ("alarm", Some(time)) => Command::Alarm(Some(parse_time(time)?)),
+        ("pips", Some("off")) => Command::Pips(Mode::Off),
+        ("pips", Some("hourly")) => Command::Pips(Mode::Hourly),
+        ("pips", Some("nhk")) => Command::Pips(Mode::Nhk),
+        ("inject", Some(date)) => {
+            let (year, day) = parse_date(date)?;
+            let second = parse_second(words.next()?)?;
+            Command::Inject { year, day, second }
+        }
+        ("config", Some("dump")) => Command::ConfigDump,
+        ("config", Some("load")) => Command::ConfigLoad(parse_record(words.next()?)?),
+        _ => return None,
+    };
+
+    // No trailing words
+    words.next().is_none().then_some(command)
+}
+
+/// Room for `config load` and its record.
+const LINE_LENGTH: usize = 80;
+
+/// Collects typed characters into lines.
+pub struct LineBuffer {
+    bytes: [u8; LINE_LENGTH],
+    len: usize,
+    overflowed: bool,
+}
+
+impl LineBuffer {
+    pub const fn new() -> Self {
+        LineBuffer {
+            bytes: [0; LINE_LENGTH],
+            len: 0,
+            overflowed: false,
+        }
+    }
+
+    /// Takes one received byte, and returns the line it ends, if any. A
+    /// line too long for the buffer comes back empty, so it is answered
+    /// with the command list.
+    pub fn push(&mut self, byte: u8) -> Option<&str> {
+        match byte {
+            b'\r' | b'\n' => {
+                let len = core::mem::take(&mut self.len);
+                if core::mem::take(&mut self.overflowed) {
+                    return Some("");
+                }
+                // Ignores the LF of a CR LF line end
+                if len == 0 {
+                    return None;
+                }
+                core::str::from_utf8(&self.bytes[..len]).ok().or(Some(""))
+            }
+            // Backspace or delete
+            0x08 | 0x7F => {
+                self.len = self.len.saturating_sub(1);
+                None
+            }
+            _ if self.len == LINE_LENGTH => {
+                self.overflowed = true;
+                None
+            }
+            _ => {
+                self.bytes[self.len] = byte;
+                self.len += 1;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The ten half-words after the first six of a record, all zero.
+    const RECORD_REST: &str = "0000000000000000000000000000000000000000";
+
+    fn template(text: &str) -> Command {
+        Command::StatusLine(Template::new(text).unwrap())
+    }
+
+    /// Lines returned for each byte of `input`.
+    fn lines(input: &[u8]) -> Vec<String> {
+        let mut buffer = LineBuffer::new();
+        input
+            .iter()
+            .filter_map(|byte| buffer.push(*byte).map(String::from))
+            .collect()
+    }
+
+    #[test]
+    fn commands_parse() {
+        for (line, command) in [
+            ("status", Command::Status),
+            ("stats reset", Command::ResetStats),
+            ("dump", Command::Dump),
+            ("frames", Command::Frames),
+            ("resync", Command::Resync),
+            ("tol 1", Command::Tolerance(1)),
+            ("tol 50", Command::Tolerance(50)),
+            ("tz +9", Command::Zone(9 * 60)),
+            ("tz -05:00", Command::Zone(-5 * 60)),
+            ("tz +5:30", Command::Zone(5 * 60 + 30)),
+            ("tz +14", Command::Zone(14 * 60)),
+            ("tz -12", Command::Zone(-12 * 60)),
+            ("alarm 06:30", Command::Alarm(Some((6, 30)))),
+            ("alarm 23:59", Command::Alarm(Some((23, 59)))),
+            ("alarm off", Command::Alarm(None)),
+            ("pips off", Command::Pips(Mode::Off)),
+            ("pips hourly", Command::Pips(Mode::Hourly)),
+            ("pips nhk", Command::Pips(Mode::Nhk)),
+            ("  status  ", Command::Status),
+            ("tz\t+9", Command::Zone(9 * 60)),
+            (
+                "inject 2025-05-03 12:34",
+                Command::Inject {
+                    year: 2025,
+                    day: 123,
+                    second: 12 * 3600 + 34 * 60,
+                },
+            ),
+            (
+                "inject 2024-12-31 23:59:30",
+                Command::Inject {
+                    year: 2024,
+                    day: 366,
+                    second: 86370,
+                },
+            ),
+            (
+                "inject 2000-01-01 00:00:00",
+                Command::Inject {
+                    year: 2000,
+                    day: 1,
+                    second: 0,
+                },
+            ),
+            ("config dump", Command::ConfigDump),
+            ("line %Y-%m-%d %a", template("%Y-%m-%d %a")),
+            ("line   q%q  %s ", template("q%q  %s")),
+            ("line\t%H:%M", template("%H:%M")),
+            (
+                "line 12345678901234567890",
+                template("12345678901234567890"),
+            ),
+            ("line off", Command::StatusLine(Template::NONE)),
+        ] {
+            assert_eq!(parse(line), Some(command), "{line}");
+        }
+    }
+
+    #[test]
+    fn malformed_commands_are_refused() {
+        for line in [
+            "",
+            "   ",
+            "Status",
+            "stats",
+            "stats clear",
+            "status now",
+            "frames 3",
+            "tol",
+            "tol 0",
+            "tol 51",
+            "tol -1",
+            "tol 20%",
+            "tz 9",
+            "tz +",
+            "tz +9:60",
+            "tz +15",
+            "tz -13",
+            "tz +9:",
+            "tz +a",
+            "alarm",
+            "alarm 24:00",
+            "alarm 12:60",
+            "alarm 1230",
+            "alarm 12:30 off",
+            "pips",
+            "pips loud",
+            "inject",
+            "inject 2025-05-03",
+            "inject 12:34 2025-05-03",
+            "inject 2025-02-29 12:34",
+            "inject 2025-13-01 12:34",
+            "inject 2025-05-00 12:34",
+            "inject 1999-12-31 23:59",
+            "inject 2100-01-01 00:00",
+            "inject 2025-05-03 24:00",
+            "inject 2025-05-03 12:34:60",
+            "inject 2025-05-03 12:34:5",
+            "inject 2025-05-03-01 12:34",
+            "inject 2025-05-03 12:34 now",
+            "config",
+            "config save",
+            "config load",
+            "config load 5E770003021C071E00050000",
+            "config dump all",
+            "line",
+            "line  ",
+            "line 123456789012345678901",
+            "line tab\there",
+        ] {
+            assert_eq!(parse(line), None, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn config_records_parse() {
+        let mut words = [0; CONFIG_WORDS];
+        words[..6].copy_from_slice(&[0x5E77, 0x0003, 0x021C, 0x071E, 0x0005, 0x2541]);
+        for record in ["5E770003021C071E00052541", "5e770003021c071e00052541"] {
+            let line = format!("config load {record}{RECORD_REST}");
+            assert_eq!(parse(&line), Some(Command::ConfigLoad(words)), "{line}");
+        }
+
+        // One digit short or over, not hex, signed, or followed by more
+        for record in [
+            "5E770003021C071E0005254",
+            "5E770003021C071E000525410",
+            "5E770003021C071E0005254G",
+            "+E770003021C071E00052541",
+        ] {
+            let line = format!("config load {record}{RECORD_REST}");
+            assert_eq!(parse(&line), None, "{line}");
+        }
+        let line = format!("config load 5E770003021C071E00052541{RECORD_REST} now");
+        assert_eq!(parse(&line), None);
+    }
+
+    #[test]
+    fn lines_end_at_cr_or_lf_once() {
+        assert_eq!(
+            lines(b"status\r\ndump\nresync\r"),
+            ["status", "dump", "resync"]
+        );
+        assert_eq!(lines(b"\r\n\n\r"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn backspace_edits_the_line() {
+        assert_eq!(lines(b"stauts\x08\x08\x08tus\r"), ["status"]);
+        assert_eq!(lines(b"\x7f\x7fdump\x7f\x7fmp\r"), ["dump"]);
+    }
+
+    #[test]
+    fn a_config_record_fits_on_a_line() {
+        let line = format!("config load 5E770003021C071E00050000{RECORD_REST}");
+        assert_eq!(line.len(), "config load ".len() + CONFIG_WORDS * 4);
+        assert_eq!(lines(format!("{line}\r").as_bytes()), [line]);
+    }
+
+    #[test]
+    fn overlong_lines_come_back_empty() {
+        let mut input = vec![b'a'; LINE_LENGTH];
+        input.push(b'\r');
+        assert_eq!(lines(&input)[0].len(), LINE_LENGTH);
+
+        // Past the buffer, and the next line is read afresh
+        let mut input = vec![b'a'; LINE_LENGTH + 10];
+        input.extend_from_slice(b"\rstatus\r");
+        assert_eq!(lines(&input), ["", "status"]);
+    }
+
+    #[test]
+    fn lines_that_are_not_utf8_come_back_empty() {
+        assert_eq!(lines(b"tz \xff\r"), [""]);
+    }
+}
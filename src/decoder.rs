@@ -0,0 +1,1426 @@
+use core::marker::PhantomData;
+
+use crate::timebase::{self, LeapSecond};
+
+mod dcf77;
+mod jjy;
+mod msf;
+mod wwvb;
+
+#[cfg(any(test, feature = "replay"))]
+pub use jjy::encode as encode_jjy;
+
+/// Width class of a pulse. What each class means is up to the protocol.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BitWidth {
+    Unknown,
+    Marker,
+    Short,
+    Long,
+    // Only MSF has a fourth width and a second pulse per second
+    Medium,
+    /// Two short pulses within one second.
+    Double,
+}
+
+impl BitWidth {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BitWidth::Unknown => "Unknown",
+            BitWidth::Marker => "Marker",
+            BitWidth::Short => "Short",
+            BitWidth::Long => "Long",
+            BitWidth::Medium => "Medium",
+            BitWidth::Double => "Double",
+        }
+    }
+}
+
+/// What differs between time signals: the pulse widths and their meaning,
+/// how a minute is found, and the frame layout.
+pub trait Protocol {
+    const NAME: &'static str;
+
+    /// Nominal pulse widths in ms, shortest first, which is also the order
+    /// they are tried in. At most four.
+    const WIDTHS: &'static [(BitWidth, u32)];
+
+    /// Accepted deviation from the nominal widths in per-mille, under strong
+    /// and under weak reception.
+    const TOLERANCE_PERMILLE: (u32, u32);
+
+    /// Standard time of the zone decoded frames are in, in minutes east of
+    /// UTC. Summer time is an hour ahead of it.
+    const UTC_OFFSET_MINUTES: i32;
+
+    /// Data symbols for a one and a zero.
+    const ONE: BitWidth;
+    const ZERO: BitWidth;
+
+    /// Second whose pulse completes the frame once it has passed the layout
+    /// check, or `None` if the frame completes on the first pulse of the
+    /// next one, which is the second 0 it encodes.
+    const COMMIT_SECOND: Option<u8>;
+
+    /// Pulses in a complete frame.
+    const FRAME_PULSES: u8 = 60;
+
+    /// Inclusive ranges slow-moving enough to be voted across frames.
+    const VOTED_FIELDS: &'static [(usize, usize)];
+
+    /// Fields with a parity bit of their own, as their bits and the second
+    /// of the bit that makes their count of ones even. A single unreadable
+    /// data second in one of them is solved for instead of dropping the
+    /// frame.
+    const PARITY_FIELDS: &'static [(&'static [(usize, u32)], usize)] = &[];
+
+    /// Whether `bit` opens second 0 of a frame, given the previous pulse
+    /// and the time since it started (`None` if there was none).
+    fn starts_frame(previous: BitWidth, bit: BitWidth, period_ms: Option<u32>) -> bool;
+
+    /// Checks `bit` against what the frame layout allows at `second`.
+    fn check_slot(second: u8, bit: BitWidth) -> Result<(), &'static str>;
+
+    /// Whether `second` carries data rather than a marker.
+    fn is_data(second: u8) -> bool;
+
+    /// Decodes the time and date fields of a complete frame.
+    fn decode(buf: &[BitWidth; 60]) -> Result<Frame, FrameError>;
+
+    /// The bit a second carries, or its first bit where there are two.
+    fn value(bit: BitWidth) -> Option<bool> {
+        match bit {
+            b if b == Self::ONE => Some(true),
+            b if b == Self::ZERO => Some(false),
+            _ => None,
+        }
+    }
+
+    /// The second bit of a second, for protocols that send two.
+    fn second_value(_bit: BitWidth) -> Option<bool> {
+        None
+    }
+
+    /// The symbol `previous` becomes if `bit`, `period_ms` after it, is
+    /// part of the same second rather than a second of its own.
+    fn merge(_previous: BitWidth, _bit: BitWidth, _period_ms: Option<u32>) -> Option<BitWidth> {
+        None
+    }
+}
+
+/// What a protocol transmits at each second of the frame.
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Slot {
+    Marker,
+    /// Fixed zero in every frame.
+    Zero,
+    /// Fixed one in every frame.
+    One,
+    Data,
+}
+
+/// Layout with markers, fixed zeros and fixed ones at the given seconds,
+/// data everywhere else.
+const fn frame_layout(markers: &[usize], zeros: &[usize], ones: &[usize]) -> [Slot; 60] {
+    let mut layout = [Slot::Data; 60];
+
+    let mut i = 0;
+    while i < markers.len() {
+        layout[markers[i]] = Slot::Marker;
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < zeros.len() {
+        layout[zeros[i]] = Slot::Zero;
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < ones.len() {
+        layout[ones[i]] = Slot::One;
+        i += 1;
+    }
+
+    layout
+}
+
+/// Checks `bit` against what `layout` allows at `second`.
+fn check_layout<P: Protocol>(
+    layout: &[Slot; 60],
+    second: u8,
+    bit: BitWidth,
+) -> Result<(), &'static str> {
+    match (layout[second as usize], bit) {
+        (Slot::Marker, BitWidth::Marker) => Ok(()),
+        (Slot::Marker, _) => Err("Marker missing"),
+        (_, BitWidth::Marker) => Err("Unexpected marker"),
+        (Slot::Zero, b) if P::value(b) == Some(true) => Err("Fixed zero is one"),
+        (Slot::One, b) if P::value(b) == Some(false) => Err("Fixed one is zero"),
+        _ => Ok(()),
+    }
+}
+
+fn bit<P: Protocol>(buf: &[BitWidth], index: usize) -> Result<bool, FrameError> {
+    P::value(buf[index]).ok_or(FrameError::Unreadable(index))
+}
+
+fn second_bit<P: Protocol>(buf: &[BitWidth], index: usize) -> Result<bool, FrameError> {
+    P::second_value(buf[index]).ok_or(FrameError::Unreadable(index))
+}
+
+/// Sums a binary-coded field given as the second and weight of each bit,
+/// and tells whether it holds an odd number of ones.
+fn field<P: Protocol>(buf: &[BitWidth], bits: &[(usize, u32)]) -> Result<(u32, bool), FrameError> {
+    let mut value = 0;
+    let mut parity = false;
+
+    for (index, weight) in bits.iter().copied() {
+        if bit::<P>(buf, index)? {
+            value += weight;
+            parity = !parity;
+        }
+    }
+
+    Ok((value, parity))
+}
+
+/// Decoded fields of one frame, all range checked.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Frame {
+    pub minute: u32,
+    pub hour: u32,
+    /// Day of year, 1-based.
+    pub day: u16,
+    pub year: u16,
+    /// 0 = Sunday .. 6 = Saturday, `None` if it could not be read.
+    pub weekday: Option<u8>,
+    pub leap_second: LeapSecond,
+    /// The transmitted time is daylight saving time.
+    pub summer_time: bool,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FrameError {
+    /// A data position carried a marker, or a pulse of no known width.
+    Unreadable(usize),
+    /// Parity failed over the named field.
+    Parity(&'static str),
+    OutOfRange(&'static str),
+}
+
+impl FrameError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FrameError::Unreadable(_) => "unreadable",
+            FrameError::Parity(_) => "parity",
+            FrameError::OutOfRange(_) => "out-of-range",
+        }
+    }
+}
+
+/// The kind and where it is, e.g. `parity-hour` or `unreadable-12`.
+impl core::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            FrameError::Unreadable(second) => write!(f, "{}-{second}", self.as_str()),
+            FrameError::Parity(field) | FrameError::OutOfRange(field) => {
+                write!(f, "{}-{field}", self.as_str())
+            }
+        }
+    }
+}
+
+/// Exclusive (min, max) bounds of a `nominal` ms pulse at ±`permille`.
+const fn window(nominal: u32, permille: u32) -> (u32, u32) {
+    (
+        nominal * (1000 - permille) / 1000,
+        nominal * (1000 + permille) / 1000,
+    )
+}
+
+/// Whether no pulse width can match two symbols of `P` at ±`permille`. For
+/// JJY this holds up to 230 ‰, where Short and Long meet.
+const fn windows_disjoint<P: Protocol>(permille: u32) -> bool {
+    let mut i = 1;
+    while i < P::WIDTHS.len() {
+        let (_, previous_max) = window(P::WIDTHS[i - 1].1, permille);
+        let (min, _) = window(P::WIDTHS[i].1, permille);
+        if previous_max > min {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+// A pulse must never classify as two symbols, even at the weak tolerance
+const _: () = {
+    assert!(windows_disjoint::<jjy::Jjy>(jjy::Jjy::TOLERANCE_PERMILLE.1));
+    assert!(windows_disjoint::<dcf77::Dcf77>(
+        dcf77::Dcf77::TOLERANCE_PERMILLE.1
+    ));
+    assert!(windows_disjoint::<wwvb::Wwvb>(
+        wwvb::Wwvb::TOLERANCE_PERMILLE.1
+    ));
+    assert!(windows_disjoint::<msf::Msf>(msf::Msf::TOLERANCE_PERMILLE.1));
+};
+
+/// Classifies a pulse width against the nominal widths at ±`permille`.
+pub fn classify<P: Protocol>(width_ms: u32, permille: u32) -> BitWidth {
+    classify_calibrated::<P>(width_ms, permille, &Calibration::new()).0
+}
+
+/// The most a pulse's confidence can be, for one right on its center.
+pub const MAX_CONFIDENCE: u8 = 9;
+
+/// The symbol, and how far inside its window the pulse is, from 0 at the
+/// edge to `MAX_CONFIDENCE` on the center. Unknown pulses have 0.
+fn classify_calibrated<P: Protocol>(
+    width_ms: u32,
+    permille: u32,
+    calibration: &Calibration,
+) -> (BitWidth, u8) {
+    for (i, (bit, _)) in P::WIDTHS.iter().copied().enumerate() {
+        let center = center::<P>(i, permille, calibration);
+        let (min, max) = window(center, permille);
+        if min < width_ms && width_ms < max {
+            let half = if width_ms < center {
+                center - min
+            } else {
+                max - center
+            };
+            let inside = half - width_ms.abs_diff(center);
+            return (bit, (MAX_CONFIDENCE as u32 * inside / half) as u8);
+        }
+    }
+
+    (BitWidth::Unknown, 0)
+}
+
+/// Middle of the gap between the nominal windows of the `i`th and the
+/// next symbol at ±`permille`.
+fn split<P: Protocol>(i: usize, permille: u32) -> u32 {
+    let (_, max) = window(P::WIDTHS[i].1, permille);
+    let (min, _) = window(P::WIDTHS[i + 1].1, permille);
+    (max + min) / 2
+}
+
+/// Nominal width of the `i`th symbol moved by its learned offset, but no
+/// further than keeps its window at ±`permille` on its side of the splits
+/// with its neighbours. However the receiver skews the symbols, their
+/// windows stay as disjoint as the nominal ones.
+fn center<P: Protocol>(i: usize, permille: u32, calibration: &Calibration) -> u32 {
+    let nominal = P::WIDTHS[i].1 as i32;
+    let center = (nominal + calibration.offset_ms(i).unwrap_or(0)).max(0) as u32;
+
+    let lowest = match i {
+        0 => 0,
+        _ => (split::<P>(i - 1, permille) * 1000).div_ceil(1000 - permille),
+    };
+    let highest = match i + 1 < P::WIDTHS.len() {
+        true => split::<P>(i, permille) * 1000 / (1000 + permille),
+        false => u32::MAX,
+    };
+
+    // At a tolerance the nominal windows already overlap at, the bounds
+    // cross; the lower one wins
+    center.min(highest).max(lowest)
+}
+
+/// Pulses per symbol before its learned offset replaces the static window.
+const CALIBRATION_SAMPLES: u16 = 16;
+
+/// Learned offsets are clamped to this share of the nominal width, and
+/// tighter still by `center` where a neighbour is closer.
+const CALIBRATION_LIMIT_PERMILLE: i32 = 300;
+
+/// Systematic stretch or shrink of each symbol by the receiver, learned
+/// from pulses that passed the frame layout check.
+#[derive(Clone, Copy, Debug)]
+pub struct Calibration {
+    // Moving average of measured minus nominal width, in 1/16 ms
+    offsets: [i32; 4],
+    samples: [u16; 4],
+}
+
+impl Calibration {
+    const fn new() -> Self {
+        Calibration {
+            offsets: [0; 4],
+            samples: [0; 4],
+        }
+    }
+
+    fn learn<P: Protocol>(&mut self, bit: BitWidth, width_ms: u32) {
+        let Some(i) = P::WIDTHS.iter().position(|(b, _)| *b == bit) else {
+            return;
+        };
+
+        let nominal = P::WIDTHS[i].1 as i32;
+        let limit = nominal * CALIBRATION_LIMIT_PERMILLE / 1000 * 16;
+        let error = (width_ms as i32 - nominal) * 16;
+
+        self.offsets[i] += (error - self.offsets[i]) / 8;
+        self.offsets[i] = self.offsets[i].clamp(-limit, limit);
+        self.samples[i] = self.samples[i].saturating_add(1);
+    }
+
+    /// Learned offset of the `i`th symbol of the protocol's `WIDTHS` in ms,
+    /// once enough pulses are in.
+    pub fn offset_ms(&self, i: usize) -> Option<i32> {
+        (self.samples[i] >= CALIBRATION_SAMPLES).then_some(self.offsets[i] / 16)
+    }
+}
+
+/// Commit the very first decode without waiting for a second frame to
+/// confirm it: faster sync, less robust against noisy frames.
+const ACCEPT_FIRST_DECODE: bool = false;
+
+/// Fill seconds lost to noise in the slow-moving fields with the value
+/// they held in recent frames, instead of dropping the minute. Parity and
+/// the one-minute confirmation still have to pass.
+const ROBUST_MODE: bool = false;
+
+/// Frames of agreement a vote saturates at, so a changed field takes over
+/// after as many frames.
+const VOTE_DEPTH: i8 = 3;
+
+/// A pending candidate older than this many pulses can no longer be
+/// confirmed.
+const CANDIDATE_TIMEOUT_PULSES: u32 = 2 * 60;
+
+/// A confirmed frame, committed during second `second` of the minute it
+/// encodes.
+#[derive(Clone, Copy, Debug)]
+pub struct DecodedTime {
+    pub frame: Frame,
+    pub second: u8,
+}
+
+impl DecodedTime {
+    pub fn second_of_day(&self) -> u32 {
+        self.frame.hour * 3600 + self.frame.minute * 60 + self.second as u32
+    }
+}
+
+/// Why the frame in progress was dropped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Abort {
+    UnknownWidth,
+    Layout(&'static str),
+}
+
+/// What the last pulse did, beyond being classified.
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    None,
+    FrameStarted,
+    Aborted {
+        reason: Abort,
+        second: u8,
+    },
+    Rejected(FrameError),
+    /// Valid, but waiting for the next frame to confirm it.
+    Unconfirmed(Frame),
+    Decoded(DecodedTime),
+}
+
+/// Pulse widths in, confirmed times out. Hardware-free, so it can be fed
+/// from recorded pulse sequences as well as from the receiver.
+pub struct Decoder<P: Protocol> {
+    tolerance_permille: u32,
+    calibration: Calibration,
+    buffer: [BitWidth; 60],
+    // The last frame handed to `decode`, as received
+    completed: [BitWidth; 60],
+    // How sure each second of `buffer` and of `completed` is, as
+    // `classify_calibrated` has it
+    confidence: [u8; 60],
+    completed_confidence: [u8; 60],
+    cursor: u8,
+    recording: bool,
+    previous: BitWidth,
+    // Second of the last marker or frame start passed, and pulses since
+    marker_phase: (u8, u8),
+    last_bit: BitWidth,
+    last_event: Event,
+    confirmed_once: bool,
+    // Minute stamp of the last decode and pulses seen since
+    candidate: Option<(u32, u32)>,
+    // Per second, positive when recent frames carried a one
+    votes: [i8; 60],
+    // Hour, day and year the votes were collected under
+    voted_fields: Option<(u32, u16, u16)>,
+    // Seconds solved from parity in the last frame handed to `decode`
+    recovered: u8,
+    protocol: PhantomData<P>,
+}
+
+impl<P: Protocol> Decoder<P> {
+    pub const fn new(tolerance_permille: u32) -> Self {
+        Decoder {
+            tolerance_permille,
+            calibration: Calibration::new(),
+            buffer: [BitWidth::Unknown; 60],
+            completed: [BitWidth::Unknown; 60],
+            confidence: [0; 60],
+            completed_confidence: [0; 60],
+            cursor: 0,
+            recording: false,
+            previous: BitWidth::Unknown,
+            marker_phase: (0, 0),
+            last_bit: BitWidth::Unknown,
+            last_event: Event::None,
+            confirmed_once: false,
+            candidate: None,
+            votes: [0; 60],
+            voted_fields: None,
+            recovered: 0,
+            protocol: PhantomData,
+        }
+    }
+
+    pub fn set_tolerance(&mut self, tolerance_permille: u32) {
+        self.tolerance_permille = tolerance_permille;
+    }
+
+    /// Drops the frame in progress and waits for a fresh frame start.
+    pub fn reset(&mut self) {
+        self.cursor = 0;
+        self.recording = false;
+        self.previous = BitWidth::Unknown;
+    }
+
+    /// Forgets the learned receiver bias, e.g. after a long signal loss.
+    pub fn reset_calibration(&mut self) {
+        self.calibration = Calibration::new();
+    }
+
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    /// Position within the frame of the next pulse.
+    pub fn cursor(&self) -> u8 {
+        self.cursor
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Symbols of the frame in progress so far.
+    pub fn received(&self) -> &[BitWidth] {
+        &self.buffer[..self.cursor as usize]
+    }
+
+    pub fn last_bit(&self) -> BitWidth {
+        self.last_bit
+    }
+
+    pub fn last_event(&self) -> Event {
+        self.last_event
+    }
+
+    /// Unreadable seconds the parity filled in, in the frame the last pulse
+    /// ended if it ended one.
+    pub fn recovered(&self) -> u8 {
+        match self.last_event {
+            Event::Rejected(_) | Event::Unconfirmed(_) | Event::Decoded(_) => self.recovered,
+            _ => 0,
+        }
+    }
+
+    /// Symbols of the frame the last pulse ended, if it ended one: the
+    /// whole frame, closing pulse included, once complete, or what was in
+    /// when it was aborted.
+    pub fn ended_frame(&self) -> Option<&[BitWidth]> {
+        match self.last_event {
+            Event::Aborted { second: 0, .. } => None,
+            Event::Aborted { second, .. } => Some(&self.buffer[..second as usize]),
+            Event::Rejected(_) | Event::Unconfirmed(_) | Event::Decoded(_) => {
+                Some(&self.completed[..P::FRAME_PULSES as usize])
+            }
+            Event::None | Event::FrameStarted => None,
+        }
+    }
+
+    /// The confidence of each second of `ended_frame`.
+    pub fn ended_confidence(&self) -> Option<&[u8]> {
+        match self.last_event {
+            Event::Aborted { second: 0, .. } => None,
+            Event::Aborted { second, .. } => Some(&self.confidence[..second as usize]),
+            Event::Rejected(_) | Event::Unconfirmed(_) | Event::Decoded(_) => {
+                Some(&self.completed_confidence[..P::FRAME_PULSES as usize])
+            }
+            Event::None | Event::FrameStarted => None,
+        }
+    }
+
+    fn abort(&mut self, reason: Abort) {
+        self.last_event = Event::Aborted {
+            reason,
+            second: self.cursor,
+        };
+        self.cursor = 0;
+        self.recording = false;
+    }
+
+    /// Feeds one pulse, `period_ms` after the previous one started (`None`
+    /// if there was none, e.g. after a silence).
+    pub fn push_pulse(&mut self, width_ms: u32, period_ms: Option<u32>) -> Option<DecodedTime> {
+        let (bit, confidence) =
+            classify_calibrated::<P>(width_ms, self.tolerance_permille, &self.calibration);
+        self.last_bit = bit;
+        self.last_event = Event::None;
+
+        // A second pulse within the second it started refines that second
+        if self.recording
+            && self.cursor > 0
+            && let Some(merged) = P::merge(self.previous, bit, period_ms)
+        {
+            let second = self.cursor - 1;
+            if let Err(reason) = P::check_slot(second, merged) {
+                self.abort(Abort::Layout(reason));
+                return None;
+            }
+
+            self.buffer[second as usize] = merged;
+            // As sure as the less sure of the two
+            let merged_confidence = &mut self.confidence[second as usize];
+            *merged_confidence = (*merged_confidence).min(confidence);
+            self.previous = merged;
+            self.last_bit = merged;
+            return None;
+        }
+
+        if let Some((_, pulses)) = &mut self.candidate {
+            *pulses += 1;
+        }
+
+        // An unreadable second is an erasure, not the end of the frame, if
+        // its field's parity can solve for it, or under robust mode if it
+        // is data for the vote to fill
+        let erasure = self.recording
+            && self.cursor < P::FRAME_PULSES
+            && P::is_data(self.cursor)
+            && (ROBUST_MODE || self.solvable(self.cursor));
+
+        if bit == BitWidth::Unknown && !erasure {
+            self.abort(Abort::UnknownWidth);
+            return None;
+        }
+
+        let starts = P::starts_frame(self.previous, bit, period_ms);
+        self.previous = bit;
+
+        // Without a closing marker, a frame is complete once every second
+        // before the next frame start is in
+        let closed = (starts
+            && P::COMMIT_SECOND.is_none()
+            && self.recording
+            && self.cursor == P::FRAME_PULSES)
+            .then_some((self.buffer, self.confidence));
+
+        if starts {
+            self.last_event = Event::FrameStarted;
+            self.recording = true;
+            self.cursor = 0;
+
+            // Nothing of the previous minute may leak into this one
+            self.buffer = [BitWidth::Unknown; 60];
+            self.confidence = [0; 60];
+        }
+
+        if !self.recording {
+            return None;
+        }
+
+        if self.cursor == P::FRAME_PULSES {
+            self.abort(Abort::Layout("Frame too long"));
+            return None;
+        }
+
+        if let Err(reason) = P::check_slot(self.cursor, bit) {
+            self.abort(Abort::Layout(reason));
+            return None;
+        }
+
+        self.calibration.learn::<P>(bit, width_ms);
+
+        // The layout check has just placed this pulse at `cursor`
+        self.marker_phase = if bit == BitWidth::Marker || starts {
+            (self.cursor, 0)
+        } else {
+            (self.marker_phase.0, self.marker_phase.1 + 1)
+        };
+
+        let complete = match P::COMMIT_SECOND {
+            Some(second) => (self.cursor == second).then_some((self.buffer, self.confidence)),
+            None => closed,
+        };
+
+        let mut decoded = None;
+
+        if let Some((mut buffer, buffer_confidence)) = complete {
+            self.completed = buffer;
+            self.completed_confidence = buffer_confidence;
+            if P::COMMIT_SECOND.is_some() {
+                self.completed[self.cursor as usize] = bit;
+                self.completed_confidence[self.cursor as usize] = confidence;
+            }
+
+            if ROBUST_MODE {
+                self.fill_erasures(&mut buffer);
+            }
+            self.recovered = Self::solve_erasures(&mut buffer);
+
+            match P::decode(&buffer) {
+                Ok(frame) => {
+                    // Only what was received votes: a filled or solved
+                    // second voting for itself would confirm a wrong guess
+                    if ROBUST_MODE {
+                        let received = self.completed;
+                        self.vote(&received, &frame);
+                    }
+                    decoded = self.confirm(frame);
+                }
+                Err(e) => {
+                    self.last_event = Event::Rejected(e);
+
+                    // The pulse that closed the frame still opens the next
+                    if P::COMMIT_SECOND.is_some() {
+                        self.cursor = 0;
+                        self.recording = false;
+                        return None;
+                    }
+                }
+            }
+        }
+
+        self.buffer[self.cursor as usize] = bit;
+        self.confidence[self.cursor as usize] = confidence;
+
+        // Frames closed by the next frame start wait for it at the end
+        self.cursor += 1;
+        if P::COMMIT_SECOND.is_some() {
+            self.cursor %= 60;
+        }
+
+        decoded
+    }
+
+    /// Second of the minute the current pulse started, counted from the
+    /// last marker or frame start rather than from wherever the commit
+    /// happens.
+    fn second_of_minute(&self) -> u8 {
+        let (marker, pulses) = self.marker_phase;
+        (marker + pulses) % 60
+    }
+
+    /// Whether an unreadable `second` is the first of its parity field.
+    fn solvable(&self, second: u8) -> bool {
+        P::PARITY_FIELDS.iter().any(|(bits, _)| {
+            bits.iter().any(|(i, _)| *i == second as usize)
+                && bits
+                    .iter()
+                    .all(|(i, _)| *i >= second as usize || self.buffer[*i] != BitWidth::Unknown)
+        })
+    }
+
+    /// Sets the one Unknown data second of each parity field to whatever
+    /// makes the parity hold, and returns how many it set. Fields with more
+    /// than one, or an unreadable parity bit, stay as they are and fail the
+    /// decode.
+    fn solve_erasures(buffer: &mut [BitWidth; 60]) -> u8 {
+        let mut solved = 0;
+
+        for (bits, parity) in P::PARITY_FIELDS {
+            let mut erased = bits
+                .iter()
+                .map(|(i, _)| *i)
+                .filter(|i| buffer[*i] == BitWidth::Unknown);
+            let (Some(erased), None) = (erased.next(), erased.next()) else {
+                continue;
+            };
+            let Some(parity) = P::value(buffer[*parity]) else {
+                continue;
+            };
+
+            let one = bits
+                .iter()
+                .filter_map(|(i, _)| P::value(buffer[*i]))
+                .fold(parity, |one, bit| one ^ bit);
+            buffer[erased] = if one { P::ONE } else { P::ZERO };
+            solved += 1;
+        }
+
+        solved
+    }
+
+    fn is_voted(i: usize) -> bool {
+        P::VOTED_FIELDS
+            .iter()
+            .any(|(first, last)| (*first..=*last).contains(&i))
+    }
+
+    /// Replaces Unknown seconds in the voted fields with the majority of
+    /// recent frames. Ties and minute bits stay Unknown and fail the decode.
+    fn fill_erasures(&self, buffer: &mut [BitWidth; 60]) {
+        for (i, bit) in buffer.iter_mut().enumerate() {
+            if *bit != BitWidth::Unknown || !Self::is_voted(i) {
+                continue;
+            }
+
+            *bit = match self.votes[i] {
+                v if v > 0 => P::ONE,
+                v if v < 0 => P::ZERO,
+                _ => BitWidth::Unknown,
+            };
+        }
+    }
+
+    /// Adds a decoded frame to the votes, from `received`, the frame as it
+    /// came in before any erasure was filled. A change of hour, day or year
+    /// restarts them, so a rollover doesn't vote yesterday's date back in.
+    fn vote(&mut self, received: &[BitWidth; 60], frame: &Frame) {
+        let fields = (frame.hour, frame.day, frame.year);
+        if self.voted_fields != Some(fields) {
+            self.votes = [0; 60];
+            self.voted_fields = Some(fields);
+        }
+
+        for (i, bit) in received.iter().enumerate() {
+            if !Self::is_voted(i) {
+                continue;
+            }
+
+            let vote = match P::value(*bit) {
+                Some(true) => 1,
+                Some(false) => -1,
+                None => continue,
+            };
+            self.votes[i] = (self.votes[i] + vote).clamp(-VOTE_DEPTH, VOTE_DEPTH);
+        }
+    }
+
+    /// Parity is weak, so a decode only counts once the next frame lands
+    /// exactly one minute later.
+    fn confirm(&mut self, frame: Frame) -> Option<DecodedTime> {
+        let stamp = timebase::minute_stamp(frame.year, frame.day, frame.hour, frame.minute);
+        let confirmed = self.candidate.take().is_some_and(|(previous, pulses)| {
+            stamp == previous + 1 && pulses <= CANDIDATE_TIMEOUT_PULSES
+        });
+        self.candidate = Some((stamp, 0));
+
+        let accepted = confirmed || (ACCEPT_FIRST_DECODE && !self.confirmed_once);
+        if !accepted {
+            self.last_event = Event::Unconfirmed(frame);
+            return None;
+        }
+
+        self.confirmed_once = true;
+
+        let decoded = DecodedTime {
+            frame,
+            second: self.second_of_minute(),
+        };
+        self.last_event = Event::Decoded(decoded);
+        Some(decoded)
+    }
+}
+
+/// A time signal the clock can be set to receive.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Station {
+    /// JJY from Mount Otakadoya, 40 kHz.
+    Jjy40,
+    /// JJY from Mount Hagane, 60 kHz. Same code as 40 kHz; only the
+    /// receiver is tuned differently.
+    Jjy60,
+    Dcf77,
+    Wwvb,
+    Msf,
+}
+
+/// Runs `$body` with `$p` naming the protocol type of `$station`.
+macro_rules! with_protocol {
+    ($station:expr, $p:ident => $body:expr) => {
+        match $station {
+            Station::Jjy40 | Station::Jjy60 => {
+                type $p = jjy::Jjy;
+                $body
+            }
+            Station::Dcf77 => {
+                type $p = dcf77::Dcf77;
+                $body
+            }
+            Station::Wwvb => {
+                type $p = wwvb::Wwvb;
+                $body
+            }
+            Station::Msf => {
+                type $p = msf::Msf;
+                $body
+            }
+        }
+    };
+}
+
+impl Station {
+    pub const ALL: [Station; 5] = [
+        Station::Jjy40,
+        Station::Jjy60,
+        Station::Dcf77,
+        Station::Wwvb,
+        Station::Msf,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Station::Jjy40 => "JJY40",
+            Station::Jjy60 => "JJY60",
+            _ => with_protocol!(self, P => P::NAME),
+        }
+    }
+
+    /// `Protocol::TOLERANCE_PERMILLE` of the station's protocol.
+    pub fn tolerance_permille(self) -> (u32, u32) {
+        with_protocol!(self, P => P::TOLERANCE_PERMILLE)
+    }
+
+    /// Minutes east of UTC of the station's decoded time, in summer time
+    /// or not.
+    pub fn utc_offset_minutes(self, summer_time: bool) -> i32 {
+        let standard = with_protocol!(self, P => P::UTC_OFFSET_MINUTES);
+        standard + if summer_time { 60 } else { 0 }
+    }
+
+    /// `Protocol::WIDTHS` of the station's protocol.
+    pub fn widths(self) -> &'static [(BitWidth, u32)] {
+        with_protocol!(self, P => P::WIDTHS)
+    }
+
+    /// `Protocol::value` of the station's protocol.
+    pub fn value(self, bit: BitWidth) -> Option<bool> {
+        with_protocol!(self, P => P::value(bit))
+    }
+
+    /// `bit` as one character of a frame log: its value, `M` for a marker,
+    /// `?` for anything else.
+    pub fn symbol(self, bit: BitWidth) -> u8 {
+        match (self.value(bit), bit) {
+            (Some(true), _) => b'1',
+            (Some(false), _) => b'0',
+            (None, BitWidth::Marker) => b'M',
+            (None, _) => b'?',
+        }
+    }
+
+    /// `classify` against the station's protocol.
+    pub fn classify(self, width_ms: u32, permille: u32) -> BitWidth {
+        with_protocol!(self, P => classify::<P>(width_ms, permille))
+    }
+}
+
+/// The station setting: one station, or whichever decodes first.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Selection {
+    Fixed(Station),
+    Auto,
+}
+
+impl Selection {
+    /// Every setting, in the order they are cycled through.
+    pub const ALL: [Selection; 6] = [
+        Selection::Fixed(Station::Jjy40),
+        Selection::Fixed(Station::Jjy60),
+        Selection::Fixed(Station::Dcf77),
+        Selection::Fixed(Station::Wwvb),
+        Selection::Fixed(Station::Msf),
+        Selection::Auto,
+    ];
+
+    pub fn next(self) -> Selection {
+        let i = Selection::ALL.iter().position(|s| *s == self).unwrap();
+        Selection::ALL[(i + 1) % Selection::ALL.len()]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Selection::Fixed(station) => station.as_str(),
+            Selection::Auto => "Auto",
+        }
+    }
+}
+
+/// A decoder for a station chosen at runtime. Switching station means
+/// building a new one, so nothing learned under one protocol carries over
+/// to another.
+pub enum AnyDecoder {
+    Jjy(Decoder<jjy::Jjy>),
+    Dcf77(Decoder<dcf77::Dcf77>),
+    Wwvb(Decoder<wwvb::Wwvb>),
+    Msf(Decoder<msf::Msf>),
+}
+
+/// Runs `$body` with `$d` bound to the inner decoder of `$decoder`.
+macro_rules! each {
+    ($decoder:expr, $d:ident => $body:expr) => {
+        match $decoder {
+            AnyDecoder::Jjy($d) => $body,
+            AnyDecoder::Dcf77($d) => $body,
+            AnyDecoder::Wwvb($d) => $body,
+            AnyDecoder::Msf($d) => $body,
+        }
+    };
+}
+
+impl AnyDecoder {
+    pub const fn new(station: Station, tolerance_permille: u32) -> Self {
+        match station {
+            Station::Jjy40 | Station::Jjy60 => AnyDecoder::Jjy(Decoder::new(tolerance_permille)),
+            Station::Dcf77 => AnyDecoder::Dcf77(Decoder::new(tolerance_permille)),
+            Station::Wwvb => AnyDecoder::Wwvb(Decoder::new(tolerance_permille)),
+            Station::Msf => AnyDecoder::Msf(Decoder::new(tolerance_permille)),
+        }
+    }
+
+    pub fn set_tolerance(&mut self, tolerance_permille: u32) {
+        each!(self, d => d.set_tolerance(tolerance_permille))
+    }
+
+    pub fn reset(&mut self) {
+        each!(self, d => d.reset())
+    }
+
+    pub fn reset_calibration(&mut self) {
+        each!(self, d => d.reset_calibration())
+    }
+
+    pub fn calibration(&self) -> Calibration {
+        each!(self, d => d.calibration())
+    }
+
+    pub fn cursor(&self) -> u8 {
+        each!(self, d => d.cursor())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        each!(self, d => d.is_recording())
+    }
+
+    pub fn received(&self) -> &[BitWidth] {
+        each!(self, d => d.received())
+    }
+
+    pub fn last_bit(&self) -> BitWidth {
+        each!(self, d => d.last_bit())
+    }
+
+    pub fn last_event(&self) -> Event {
+        each!(self, d => d.last_event())
+    }
+
+    pub fn ended_frame(&self) -> Option<&[BitWidth]> {
+        each!(self, d => d.ended_frame())
+    }
+
+    pub fn ended_confidence(&self) -> Option<&[u8]> {
+        each!(self, d => d.ended_confidence())
+    }
+
+    pub fn recovered(&self) -> u8 {
+        each!(self, d => d.recovered())
+    }
+
+    pub fn push_pulse(&mut self, width_ms: u32, period_ms: Option<u32>) -> Option<DecodedTime> {
+        each!(self, d => d.push_pulse(width_ms, period_ms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jjy::Jjy;
+
+    /// Minute `n` from 2025-05-03 12:34 JST, as JJY sends it.
+    fn minute(n: u32) -> [u16; 60] {
+        encode_jjy(&Frame {
+            minute: 34 + n,
+            hour: 12,
+            day: 123,
+            year: 2025,
+            weekday: Some(6),
+            leap_second: LeapSecond::None,
+            summer_time: false,
+        })
+    }
+
+    /// A decoder that has just seen the P0 closing the minute before.
+    fn decoder() -> Decoder<Jjy> {
+        let mut decoder = Decoder::new(Jjy::TOLERANCE_PERMILLE.0);
+        decoder.push_pulse(200, None);
+        decoder
+    }
+
+    /// Feeds `widths` a second apart, up to the first that ends the frame
+    /// or throws it away, and returns what that pulse decoded.
+    fn replay(decoder: &mut Decoder<Jjy>, widths: &[u16]) -> Option<DecodedTime> {
+        for width in widths {
+            let decoded = decoder.push_pulse(*width as u32, Some(1000));
+            if !matches!(decoder.last_event(), Event::None | Event::FrameStarted) {
+                return decoded;
+            }
+        }
+        None
+    }
+
+    /// Every symbol of `P` learned as skewed by `skews[i]`, in ms.
+    fn skewed<P: Protocol>(skews: &[i32]) -> Calibration {
+        let mut calibration = Calibration::new();
+        for (i, skew) in skews.iter().enumerate() {
+            let (bit, nominal) = P::WIDTHS[i];
+            for _ in 0..64 {
+                calibration.learn::<P>(bit, (nominal as i32 + skew) as u32);
+            }
+        }
+        calibration
+    }
+
+    /// Whether the windows of `P` at ±`permille` under `calibration` are
+    /// disjoint.
+    fn disjoint<P: Protocol>(permille: u32, calibration: &Calibration) -> bool {
+        (1..P::WIDTHS.len()).all(|i| {
+            let (_, previous_max) = window(center::<P>(i - 1, permille, calibration), permille);
+            let (min, _) = window(center::<P>(i, permille, calibration), permille);
+            previous_max <= min
+        })
+    }
+
+    /// Neighbours learned as skewed towards each other, or all one way, as
+    /// far as the calibration limit lets them.
+    fn clamp_keeps_windows_disjoint<P: Protocol>() {
+        let limit = |i: usize| P::WIDTHS[i].1 as i32 * CALIBRATION_LIMIT_PERMILLE / 1000;
+        let towards: Vec<i32> = (0..P::WIDTHS.len())
+            .map(|i| if i % 2 == 0 { limit(i) } else { -limit(i) })
+            .collect();
+        let away: Vec<i32> = towards.iter().map(|skew| -skew).collect();
+        let longer: Vec<i32> = (0..P::WIDTHS.len()).map(limit).collect();
+        let shorter: Vec<i32> = longer.iter().map(|skew| -skew).collect();
+
+        for skews in [towards, away, longer, shorter] {
+            let calibration = skewed::<P>(&skews);
+            for permille in [P::TOLERANCE_PERMILLE.0, P::TOLERANCE_PERMILLE.1] {
+                assert!(
+                    disjoint::<P>(permille, &calibration),
+                    "{skews:?} at {permille}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn windows_exclude_their_bounds() {
+        let (strong, weak) = Jjy::TOLERANCE_PERMILLE;
+        for (permille, bounds) in [
+            (strong, [(176, 224), (440, 560), (704, 896)]),
+            (weak, [(156, 244), (390, 610), (624, 976)]),
+        ] {
+            for ((bit, _), (min, max)) in Jjy::WIDTHS.iter().zip(bounds) {
+                assert_eq!(
+                    classify::<Jjy>(min, permille),
+                    BitWidth::Unknown,
+                    "{permille} {min}"
+                );
+                assert_eq!(classify::<Jjy>(min + 1, permille), *bit, "{permille} {min}");
+                assert_eq!(classify::<Jjy>(max - 1, permille), *bit, "{permille} {max}");
+                assert_eq!(
+                    classify::<Jjy>(max, permille),
+                    BitWidth::Unknown,
+                    "{permille} {max}"
+                );
+            }
+        }
+
+        assert_eq!(classify::<Jjy>(0, strong), BitWidth::Unknown);
+        assert_eq!(classify::<Jjy>(u32::MAX, weak), BitWidth::Unknown);
+    }
+
+    #[test]
+    fn learned_offsets_keep_windows_disjoint() {
+        clamp_keeps_windows_disjoint::<Jjy>();
+        clamp_keeps_windows_disjoint::<dcf77::Dcf77>();
+        clamp_keeps_windows_disjoint::<wwvb::Wwvb>();
+        clamp_keeps_windows_disjoint::<msf::Msf>();
+    }
+
+    #[test]
+    fn small_offsets_are_not_clamped() {
+        let calibration = skewed::<Jjy>(&[20, -30, 25]);
+        let permille = Jjy::TOLERANCE_PERMILLE.1;
+        for (i, (_, nominal)) in Jjy::WIDTHS.iter().enumerate() {
+            let offset = calibration.offset_ms(i).unwrap();
+            assert!(offset != 0);
+            let learned = (*nominal as i32 + offset) as u32;
+            assert_eq!(center::<Jjy>(i, permille, &calibration), learned);
+        }
+    }
+
+    #[test]
+    fn clamped_offset_stops_at_the_split() {
+        // A 650 ms one would reach 793 ms at ±220 ‰, into the zero window
+        let calibration = skewed::<Jjy>(&[0, 150, 0]);
+        let permille = Jjy::TOLERANCE_PERMILLE.1;
+        let center = center::<Jjy>(1, permille, &calibration);
+        assert!(center < 650);
+        assert!(window(center, permille).1 <= window(800, permille).0);
+        assert_eq!(
+            classify_calibrated::<Jjy>(640, permille, &calibration).0,
+            BitWidth::Long
+        );
+    }
+
+    #[test]
+    fn good_frames_decode_once_confirmed() {
+        let mut decoder = decoder();
+
+        assert!(replay(&mut decoder, &minute(0)).is_none());
+        assert!(matches!(decoder.last_event(), Event::Unconfirmed(f) if f.minute == 34));
+
+        let decoded = replay(&mut decoder, &minute(1)).unwrap();
+        let frame = decoded.frame;
+        assert_eq!((frame.hour, frame.minute, decoded.second), (12, 35, 59));
+        assert_eq!((frame.year, frame.day, frame.weekday), (2025, 123, Some(6)));
+        assert_eq!(frame.leap_second, LeapSecond::None);
+    }
+
+    #[test]
+    fn confidence_falls_towards_the_window_edges() {
+        let (strong, _) = Jjy::TOLERANCE_PERMILLE;
+        let calibration = Calibration::new();
+        for (width, classified) in [
+            (200, (BitWidth::Marker, MAX_CONFIDENCE)),
+            (212, (BitWidth::Marker, 4)),
+            (188, (BitWidth::Marker, 4)),
+            (223, (BitWidth::Marker, 0)),
+            (530, (Jjy::ONE, 4)),
+            (350, (BitWidth::Unknown, 0)),
+        ] {
+            assert_eq!(
+                classify_calibrated::<Jjy>(width, strong, &calibration),
+                classified,
+                "{width}"
+            );
+        }
+    }
+
+    #[test]
+    fn ended_frames_carry_their_confidence() {
+        let mut decoder = decoder();
+
+        // A one 30 ms long, halfway to the edge of its window
+        let mut widths = minute(0);
+        let second = widths.iter().position(|w| *w == 500).unwrap();
+        widths[second] = 530;
+        replay(&mut decoder, &widths);
+
+        let confidence = decoder.ended_confidence().unwrap();
+        assert_eq!(confidence.len(), decoder.ended_frame().unwrap().len());
+        for (i, confidence) in confidence.iter().enumerate() {
+            let expected = if i == second { 4 } else { MAX_CONFIDENCE };
+            assert_eq!(*confidence, expected, "{i}");
+        }
+
+        // An aborted frame has what was in
+        let mut widths = minute(1);
+        widths[22] = 200;
+        replay(&mut decoder, &widths);
+        assert_eq!(decoder.ended_confidence(), Some(&[MAX_CONFIDENCE; 22][..]));
+    }
+
+    /// JJY committed at second `S` rather than at the closing P0.
+    struct CommitAt<const S: u8>;
+
+    impl<const S: u8> Protocol for CommitAt<S> {
+        const NAME: &'static str = Jjy::NAME;
+        const WIDTHS: &'static [(BitWidth, u32)] = Jjy::WIDTHS;
+        const TOLERANCE_PERMILLE: (u32, u32) = Jjy::TOLERANCE_PERMILLE;
+        const UTC_OFFSET_MINUTES: i32 = Jjy::UTC_OFFSET_MINUTES;
+        const ONE: BitWidth = Jjy::ONE;
+        const ZERO: BitWidth = Jjy::ZERO;
+        const COMMIT_SECOND: Option<u8> = Some(S);
+        const VOTED_FIELDS: &'static [(usize, usize)] = Jjy::VOTED_FIELDS;
+        const PARITY_FIELDS: &'static [(&'static [(usize, u32)], usize)] = Jjy::PARITY_FIELDS;
+
+        fn starts_frame(previous: BitWidth, bit: BitWidth, period_ms: Option<u32>) -> bool {
+            Jjy::starts_frame(previous, bit, period_ms)
+        }
+
+        fn check_slot(second: u8, bit: BitWidth) -> Result<(), &'static str> {
+            Jjy::check_slot(second, bit)
+        }
+
+        fn is_data(second: u8) -> bool {
+            Jjy::is_data(second)
+        }
+
+        fn decode(buf: &[BitWidth; 60]) -> Result<Frame, FrameError> {
+            Jjy::decode(buf)
+        }
+    }
+
+    /// Minute and second of each time decoded from three minutes committed
+    /// at second `S`.
+    fn commits<const S: u8>() -> Vec<(u32, u8, u32)> {
+        let mut decoder = Decoder::<CommitAt<S>>::new(Jjy::TOLERANCE_PERMILLE.0);
+        decoder.push_pulse(200, None);
+
+        [minute(0), minute(1), minute(2)]
+            .iter()
+            .flatten()
+            .filter_map(|width| decoder.push_pulse(*width as u32, Some(1000)))
+            .map(|time| (time.frame.minute, time.second, time.second_of_day()))
+            .collect()
+    }
+
+    #[test]
+    fn commit_second_follows_the_cursor() {
+        // The weekday and leap second bits are the last a JJY decode reads
+        assert_eq!(
+            commits::<55>(),
+            [
+                (35, 55, 12 * 3600 + 35 * 60 + 55),
+                (36, 55, 12 * 3600 + 36 * 60 + 55)
+            ]
+        );
+        assert_eq!(
+            commits::<57>(),
+            [
+                (35, 57, 12 * 3600 + 35 * 60 + 57),
+                (36, 57, 12 * 3600 + 36 * 60 + 57)
+            ]
+        );
+        assert_eq!(
+            commits::<59>(),
+            [
+                (35, 59, 12 * 3600 + 35 * 60 + 59),
+                (36, 59, 12 * 3600 + 36 * 60 + 59)
+            ]
+        );
+    }
+
+    #[test]
+    fn parity_error_rejects_the_frame() {
+        let mut decoder = decoder();
+        replay(&mut decoder, &minute(0));
+
+        // PA2 flipped
+        let mut widths = minute(1);
+        widths[37] = if widths[37] == 500 { 800 } else { 500 };
+        assert!(replay(&mut decoder, &widths).is_none());
+        assert!(matches!(
+            decoder.last_event(),
+            Event::Rejected(FrameError::Parity("minute"))
+        ));
+
+        // Two minutes after the last good frame, nothing confirms
+        assert!(replay(&mut decoder, &minute(2)).is_none());
+        assert!(matches!(decoder.last_event(), Event::Unconfirmed(_)));
+        assert!(replay(&mut decoder, &minute(3)).is_some());
+    }
+
+    #[test]
+    fn misplaced_marker_aborts_the_frame() {
+        let mut decoder = decoder();
+        replay(&mut decoder, &minute(0));
+
+        let mut widths = minute(1);
+        widths[22] = 200;
+        assert!(replay(&mut decoder, &widths).is_none());
+        assert!(matches!(
+            decoder.last_event(),
+            Event::Aborted {
+                reason: Abort::Layout("Unexpected marker"),
+                second: 22,
+            }
+        ));
+        assert!(!decoder.is_recording());
+
+        // The closing P0 and the next M start over
+        let rest = &widths[23..];
+        assert!(replay(&mut decoder, rest).is_none());
+        assert!(replay(&mut decoder, &minute(2)).is_none());
+        assert!(matches!(decoder.last_event(), Event::Unconfirmed(_)));
+        assert!(replay(&mut decoder, &minute(3)).is_some());
+    }
+
+    /// A good minute, then the next with `erased` seconds unreadable;
+    /// returns what the second decoded and how many seconds were solved.
+    fn replay_erased(erased: &[usize]) -> (Option<DecodedTime>, u8) {
+        let mut decoder = decoder();
+        replay(&mut decoder, &minute(0));
+
+        let mut widths = minute(1);
+        for second in erased {
+            widths[*second] = 350;
+        }
+        let decoded = replay(&mut decoder, &widths);
+        (decoded, decoder.recovered())
+    }
+
+    #[test]
+    fn single_erasure_is_solved_at_every_minute_and_hour_bit() {
+        // PA2 over the minute, PA1 over the hour
+        let seconds = Jjy::PARITY_FIELDS.iter().flat_map(|(bits, _)| bits.iter());
+        assert_eq!(seconds.clone().count(), 13);
+        for (second, _) in seconds {
+            let (decoded, recovered) = replay_erased(&[*second]);
+            let frame = decoded.unwrap_or_else(|| panic!("second {second}")).frame;
+            assert_eq!((frame.hour, frame.minute), (12, 35), "second {second}");
+            assert_eq!(recovered, 1);
+        }
+    }
+
+    #[test]
+    fn one_erasure_in_each_field_is_solved() {
+        let (decoded, recovered) = replay_erased(&[3, 16]);
+        assert_eq!(decoded.unwrap().frame.minute, 35);
+        assert_eq!(recovered, 2);
+    }
+
+    #[test]
+    fn more_erasures_than_parity_can_solve_drop_the_frame() {
+        // Two in one field, a parity bit itself, or a field without parity
+        for erased in [&[1, 5][..], &[12, 18], &[3, 6, 16], &[36], &[37], &[22]] {
+            let (decoded, _) = replay_erased(erased);
+            assert!(decoded.is_none(), "{erased:?}");
+        }
+
+        let (decoded, recovered) = replay_erased(&[]);
+        assert!(decoded.is_some());
+        assert_eq!(recovered, 0);
+    }
+
+    #[test]
+    fn unknown_width_aborts_the_frame() {
+        let mut decoder = decoder();
+        replay(&mut decoder, &minute(0));
+
+        // Between a marker and a one; the day has no parity to solve it
+        let mut widths = minute(1);
+        widths[25] = 350;
+        assert!(replay(&mut decoder, &widths).is_none());
+        assert_eq!(decoder.last_bit(), BitWidth::Unknown);
+        assert!(matches!(
+            decoder.last_event(),
+            Event::Aborted {
+                reason: Abort::UnknownWidth,
+                second: 25,
+            }
+        ));
+        assert!(!decoder.is_recording());
+    }
+}
@@ -0,0 +1,190 @@
+//! DCF77, Germany: 100 ms = 0, 200 ms = 1, and no pulse at all in second
+//! 59, so the minute mark is the gap before second 0.
+
+use super::{BitWidth, Frame, FrameError, Protocol, Slot, bit, check_layout, field, frame_layout};
+use crate::timebase::{self, LeapSecond};
+
+pub struct Dcf77;
+
+/// Interval between pulse starts that spans the missing second 59. Shorter
+/// is a regular second, longer is a dropout.
+const MINUTE_GAP_MS: core::ops::RangeInclusive<u32> = 1500..=2500;
+
+impl Protocol for Dcf77 {
+    const NAME: &'static str = "DCF77";
+
+    const WIDTHS: &'static [(BitWidth, u32)] = &[(BitWidth::Short, 100), (BitWidth::Long, 200)];
+
+    const TOLERANCE_PERMILLE: (u32, u32) = (120, 220);
+
+    const UTC_OFFSET_MINUTES: i32 = 60;
+
+    const ONE: BitWidth = BitWidth::Long;
+    const ZERO: BitWidth = BitWidth::Short;
+
+    /// Seconds 0-58 are all data, and the frame gives the time at the next
+    /// minute mark: it is only complete, and only true, once the pulse after
+    /// the gap arrives.
+    const COMMIT_SECOND: Option<u8> = None;
+
+    /// Second 59 has no pulse. In a leap second minute it does, and the
+    /// frame is dropped as too long.
+    const FRAME_PULSES: u8 = 59;
+
+    /// CET/CEST flags, hour, and the whole date with its parity.
+    const VOTED_FIELDS: &'static [(usize, usize)] = &[(17, 18), (29, 35), (36, 58)];
+
+    /// P1 over the minute, P2 over the hour. P3 spans the whole date, too
+    /// long for one bit of parity to vouch for a guess.
+    const PARITY_FIELDS: &'static [(&'static [(usize, u32)], usize)] =
+        &[(&MINUTE, 28), (&HOUR, 35)];
+
+    fn starts_frame(_previous: BitWidth, _bit: BitWidth, period_ms: Option<u32>) -> bool {
+        period_ms.is_some_and(|period| MINUTE_GAP_MS.contains(&period))
+    }
+
+    fn check_slot(second: u8, bit: BitWidth) -> Result<(), &'static str> {
+        check_layout::<Dcf77>(&FRAME_LAYOUT, second, bit)
+    }
+
+    fn is_data(second: u8) -> bool {
+        second < 59
+    }
+
+    fn decode(buf: &[BitWidth; 60]) -> Result<Frame, FrameError> {
+        decode(buf)
+    }
+}
+
+/// Second 0 is always zero and second 20, the start of the time, always
+/// one.
+const FRAME_LAYOUT: [Slot; 60] = frame_layout(&[], &[0], &[20]);
+
+const MINUTE: [(usize, u32); 7] = [
+    (21, 1),
+    (22, 2),
+    (23, 4),
+    (24, 8),
+    (25, 10),
+    (26, 20),
+    (27, 40),
+];
+const HOUR: [(usize, u32); 6] = [(29, 1), (30, 2), (31, 4), (32, 8), (33, 10), (34, 20)];
+const DAY: [(usize, u32); 6] = [(36, 1), (37, 2), (38, 4), (39, 8), (40, 10), (41, 20)];
+const WEEKDAY: [(usize, u32); 3] = [(42, 1), (43, 2), (44, 4)];
+const MONTH: [(usize, u32); 5] = [(45, 1), (46, 2), (47, 4), (48, 8), (49, 10)];
+const YEAR: [(usize, u32); 8] = [
+    (50, 1),
+    (51, 2),
+    (52, 4),
+    (53, 8),
+    (54, 10),
+    (55, 20),
+    (56, 40),
+    (57, 80),
+];
+
+fn decode(buf: &[BitWidth]) -> Result<Frame, FrameError> {
+    let (minute, minute_parity) = field::<Dcf77>(buf, &MINUTE)?;
+    let (hour, hour_parity) = field::<Dcf77>(buf, &HOUR)?;
+    let (day, day_parity) = field::<Dcf77>(buf, &DAY)?;
+    let (weekday, weekday_parity) = field::<Dcf77>(buf, &WEEKDAY)?;
+    let (month, month_parity) = field::<Dcf77>(buf, &MONTH)?;
+    let (year, year_parity) = field::<Dcf77>(buf, &YEAR)?;
+
+    // Even parity, the parity bit included
+    if bit::<Dcf77>(buf, 28)? != minute_parity {
+        return Err(FrameError::Parity("minute"));
+    }
+
+    if bit::<Dcf77>(buf, 35)? != hour_parity {
+        return Err(FrameError::Parity("hour"));
+    }
+
+    if bit::<Dcf77>(buf, 58)? != day_parity ^ weekday_parity ^ month_parity ^ year_parity {
+        return Err(FrameError::Parity("date"));
+    }
+
+    // Exactly one of CEST and CET
+    let summer_time = bit::<Dcf77>(buf, 17)?;
+    if summer_time == bit::<Dcf77>(buf, 18)? {
+        return Err(FrameError::OutOfRange("zone"));
+    }
+
+    // Only the last two digits are transmitted
+    let year = 2000 + year as u16;
+
+    if minute > 59 {
+        return Err(FrameError::OutOfRange("minute"));
+    }
+
+    if hour > 23 {
+        return Err(FrameError::OutOfRange("hour"));
+    }
+
+    let Some(day) = timebase::day_of_year(year, month as u8, day as u8) else {
+        return Err(FrameError::OutOfRange("day"));
+    };
+
+    Ok(Frame {
+        minute,
+        hour,
+        day,
+        year,
+        // 1 = Monday .. 7 = Sunday
+        weekday: (1..=7).contains(&weekday).then_some(weekday as u8 % 7),
+        // A1 announces a leap second at the end of the hour, but not which
+        // way; the next decode absorbs it
+        leap_second: LeapSecond::None,
+        summer_time,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::{Decoder, Event};
+
+    /// Width and period of each pulse in the fixture.
+    fn recording() -> impl Iterator<Item = (u32, Option<u32>)> {
+        include_str!("fixtures/dcf77.txt")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let (width, period) = line.split_once(' ').unwrap();
+                (width.parse().unwrap(), period.parse().ok())
+            })
+    }
+
+    #[test]
+    fn recorded_minutes_decode() {
+        let mut decoder = Decoder::<Dcf77>::new(Dcf77::TOLERANCE_PERMILLE.0);
+        let mut unconfirmed = Vec::new();
+        let mut decoded = Vec::new();
+
+        for (width, period) in recording() {
+            if let Some(time) = decoder.push_pulse(width, period) {
+                decoded.push((time.frame.hour, time.frame.minute, decoder.recovered()));
+                assert_eq!(time.second, 0);
+                assert_eq!(
+                    (time.frame.year, time.frame.day, time.frame.weekday),
+                    (2025, 123, Some(6))
+                );
+                assert!(time.frame.summer_time);
+            }
+
+            match decoder.last_event() {
+                Event::Unconfirmed(frame) => unconfirmed.push((frame.hour, frame.minute)),
+                Event::Aborted { .. } | Event::Rejected(_) => {
+                    panic!("{:?}", decoder.last_event())
+                }
+                _ => {}
+            }
+        }
+
+        // The first frame waits for the next to confirm it, and the second
+        // has its unreadable minute bit solved from the parity
+        assert_eq!(unconfirmed, [(16, 35)]);
+        assert_eq!(decoded, [(16, 36, 1), (16, 37, 0)]);
+    }
+}
@@ -0,0 +1,374 @@
+//! JJY, Japan: 500 ms = 1, 800 ms = 0, 200 ms position markers, and a
+//! double marker opening each minute.
+
+use super::{BitWidth, Frame, FrameError, Protocol, Slot, bit, check_layout, field, frame_layout};
+use crate::timebase::{self, LeapSecond};
+
+pub struct Jjy;
+
+impl Protocol for Jjy {
+    const NAME: &'static str = "JJY";
+
+    const WIDTHS: &'static [(BitWidth, u32)] = &[
+        (BitWidth::Marker, 200),
+        (BitWidth::Short, 500),
+        (BitWidth::Long, 800),
+    ];
+
+    const TOLERANCE_PERMILLE: (u32, u32) = (120, 220);
+
+    const UTC_OFFSET_MINUTES: i32 = 9 * 60;
+
+    const ONE: BitWidth = BitWidth::Short;
+    const ZERO: BitWidth = BitWidth::Long;
+
+    /// Frames are decoded once the closing P0 marker has passed the layout
+    /// check: by then every marker and fixed zero of the minute has been
+    /// validated, and the year, weekday and leap second fields are in. The
+    /// weekday and leap second are optional.
+    const COMMIT_SECOND: Option<u8> = Some(59);
+
+    /// Hour, day, PA1, year, weekday and leap second. Minutes change every
+    /// frame and are never voted.
+    const VOTED_FIELDS: &'static [(usize, usize)] =
+        &[(12, 18), (22, 33), (36, 36), (41, 48), (50, 54)];
+
+    /// PA2 over the minute, PA1 over the hour. The day has none.
+    const PARITY_FIELDS: &'static [(&'static [(usize, u32)], usize)] =
+        &[(&MINUTE, 37), (&HOUR, 36)];
+
+    /// P0 followed by M.
+    fn starts_frame(previous: BitWidth, bit: BitWidth, _period_ms: Option<u32>) -> bool {
+        previous == BitWidth::Marker && bit == BitWidth::Marker
+    }
+
+    fn check_slot(second: u8, bit: BitWidth) -> Result<(), &'static str> {
+        check_layout::<Jjy>(&FRAME_LAYOUT, second, bit)
+    }
+
+    fn is_data(second: u8) -> bool {
+        FRAME_LAYOUT[second as usize] != Slot::Marker
+    }
+
+    fn decode(buf: &[BitWidth; 60]) -> Result<Frame, FrameError> {
+        decode(buf)
+    }
+}
+
+/// M, then P1-P5, then P0.
+const FRAME_LAYOUT: [Slot; 60] = frame_layout(
+    &[0, 9, 19, 29, 39, 49, 59],
+    &[4, 10, 11, 14, 20, 21, 24, 34, 35],
+    &[],
+);
+
+const MINUTE: [(usize, u32); 7] = [(1, 40), (2, 20), (3, 10), (5, 8), (6, 4), (7, 2), (8, 1)];
+const HOUR: [(usize, u32); 6] = [(12, 20), (13, 10), (15, 8), (16, 4), (17, 2), (18, 1)];
+const DAY: [(usize, u32); 10] = [
+    (22, 200),
+    (23, 100),
+    (25, 80),
+    (26, 40),
+    (27, 20),
+    (28, 10),
+    (30, 8),
+    (31, 4),
+    (32, 2),
+    (33, 1),
+];
+const YEAR: [(usize, u32); 8] = [
+    (41, 80),
+    (42, 40),
+    (43, 20),
+    (44, 10),
+    (45, 8),
+    (46, 4),
+    (47, 2),
+    (48, 1),
+];
+const WEEKDAY: [(usize, u32); 3] = [(50, 4), (51, 2), (52, 1)];
+
+/// Decodes the time and date fields of a frame recorded up to its P0 marker.
+fn decode(buf: &[BitWidth]) -> Result<Frame, FrameError> {
+    let (minute, minute_parity) = field::<Jjy>(buf, &MINUTE)?;
+    let (hour, hour_parity) = field::<Jjy>(buf, &HOUR)?;
+    let (day, _) = field::<Jjy>(buf, &DAY)?;
+
+    if bit::<Jjy>(buf, 36)? != hour_parity {
+        return Err(FrameError::Parity("hour"));
+    }
+
+    if bit::<Jjy>(buf, 37)? != minute_parity {
+        return Err(FrameError::Parity("minute"));
+    }
+
+    let (year, _) = field::<Jjy>(buf, &YEAR)?;
+
+    // Only the last two digits are transmitted
+    let year = 2000 + year as u16;
+    let day = day as u16;
+
+    // BCD fields can encode more than their legal maximum, and the day has
+    // no parity at all
+    if minute > 59 {
+        return Err(FrameError::OutOfRange("minute"));
+    }
+
+    if hour > 23 {
+        return Err(FrameError::OutOfRange("hour"));
+    }
+
+    if timebase::month_day(year, day).is_none() {
+        return Err(FrameError::OutOfRange("day"));
+    }
+
+    Ok(Frame {
+        minute,
+        hour,
+        day,
+        year,
+        weekday: to_weekday(buf),
+        leap_second: to_leap_second(buf),
+        summer_time: false,
+    })
+}
+
+/// LS1/LS2. Unreadable bits count as no announcement: a missed leap second
+/// costs one second until the next decode, a phantom one costs the same.
+fn to_leap_second(buf: &[BitWidth]) -> LeapSecond {
+    match (Jjy::value(buf[53]), Jjy::value(buf[54])) {
+        (Some(true), Some(true)) => LeapSecond::Insert,
+        (Some(true), Some(false)) => LeapSecond::Delete,
+        _ => LeapSecond::None,
+    }
+}
+
+fn to_weekday(buf: &[BitWidth]) -> Option<u8> {
+    let (weekday, _) = field::<Jjy>(buf, &WEEKDAY).ok()?;
+    (weekday < 7).then_some(weekday as u8)
+}
+
+/// Encodes `frame` as the pulse widths JJY sends for it, in ms, from M to
+/// P0. The reference for what `decode` reads, and the source of the replay
+/// recordings.
+#[cfg(any(test, feature = "replay"))]
+pub const fn encode(frame: &Frame) -> [u16; 60] {
+    let mut bits = [false; 60];
+
+    let minute_parity = set_field(&mut bits, &MINUTE, frame.minute);
+    let hour_parity = set_field(&mut bits, &HOUR, frame.hour);
+    set_field(&mut bits, &DAY, frame.day as u32);
+    bits[36] = hour_parity;
+    bits[37] = minute_parity;
+    set_field(&mut bits, &YEAR, frame.year as u32 % 100);
+    if let Some(weekday) = frame.weekday {
+        set_field(&mut bits, &WEEKDAY, weekday as u32);
+    }
+    (bits[53], bits[54]) = match frame.leap_second {
+        LeapSecond::Insert => (true, true),
+        LeapSecond::Delete => (true, false),
+        LeapSecond::None => (false, false),
+    };
+
+    let mut widths = [0; 60];
+    let mut second = 0;
+    while second < 60 {
+        widths[second] = match (FRAME_LAYOUT[second], bits[second]) {
+            (Slot::Marker, _) => width_ms(BitWidth::Marker),
+            (_, true) => width_ms(Jjy::ONE),
+            (_, false) => width_ms(Jjy::ZERO),
+        };
+        second += 1;
+    }
+
+    widths
+}
+
+/// Sets the bits of `value` in a binary-coded field, and returns its
+/// parity like `field` does.
+#[cfg(any(test, feature = "replay"))]
+const fn set_field(bits: &mut [bool; 60], field: &[(usize, u32)], mut value: u32) -> bool {
+    let mut parity = false;
+
+    let mut i = 0;
+    while i < field.len() {
+        let (index, weight) = field[i];
+        if value >= weight {
+            value -= weight;
+            bits[index] = true;
+            parity = !parity;
+        }
+        i += 1;
+    }
+
+    parity
+}
+
+#[cfg(any(test, feature = "replay"))]
+const fn width_ms(bit: BitWidth) -> u16 {
+    let mut i = 0;
+    while Jjy::WIDTHS[i].0 as u8 != bit as u8 {
+        i += 1;
+    }
+    Jjy::WIDTHS[i].1 as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::classify;
+
+    /// 2025-05-03, a Saturday, at 12:34 JST.
+    const FRAME: Frame = Frame {
+        minute: 34,
+        hour: 12,
+        day: 123,
+        year: 2025,
+        weekday: Some(6),
+        leap_second: LeapSecond::None,
+        summer_time: false,
+    };
+
+    /// `frame` encoded and read back as `decode` would from the receiver.
+    fn round_trip(frame: &Frame) -> Result<Frame, FrameError> {
+        let buf =
+            encode(frame).map(|width| classify::<Jjy>(width as u32, Jjy::TOLERANCE_PERMILLE.0));
+        decode(&buf)
+    }
+
+    #[test]
+    fn encodes_the_transmitted_layout() {
+        let symbols: String = encode(&FRAME)
+            .iter()
+            .map(|width| match width {
+                200 => 'M',
+                500 => '1',
+                800 => '0',
+                _ => '?',
+            })
+            .collect();
+
+        // Seconds 0-9, 10-19 and so on, worked out from the JJY time code
+        // by hand
+        let expected = [
+            "M01100100M",
+            "000100010M",
+            "000100010M",
+            "001100010M",
+            "000100101M",
+            "110000000M",
+        ];
+        assert_eq!(symbols, expected.concat());
+    }
+
+    #[test]
+    fn encoded_frames_decode_to_themselves() {
+        let frames = [
+            FRAME,
+            Frame {
+                minute: 59,
+                hour: 23,
+                day: 366,
+                year: 2024,
+                weekday: Some(2),
+                ..FRAME
+            },
+            Frame {
+                minute: 0,
+                hour: 0,
+                day: 1,
+                year: 2000,
+                weekday: Some(0),
+                ..FRAME
+            },
+            Frame {
+                year: 2099,
+                leap_second: LeapSecond::Insert,
+                ..FRAME
+            },
+            Frame {
+                leap_second: LeapSecond::Delete,
+                ..FRAME
+            },
+        ];
+
+        for frame in frames {
+            assert_eq!(round_trip(&frame), Ok(frame));
+        }
+
+        for minute in 0..60 {
+            for hour in 0..24 {
+                let frame = Frame {
+                    minute,
+                    hour,
+                    ..FRAME
+                };
+                assert_eq!(round_trip(&frame), Ok(frame));
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_fields_are_rejected() {
+        for (frame, field) in [
+            (
+                Frame {
+                    minute: 72,
+                    ..FRAME
+                },
+                "minute",
+            ),
+            (
+                Frame {
+                    minute: 60,
+                    ..FRAME
+                },
+                "minute",
+            ),
+            (Frame { hour: 33, ..FRAME }, "hour"),
+            (Frame { hour: 24, ..FRAME }, "hour"),
+            (Frame { day: 0, ..FRAME }, "day"),
+            (Frame { day: 366, ..FRAME }, "day"),
+            (Frame { day: 399, ..FRAME }, "day"),
+        ] {
+            // The encoder sets the parity to match, so only the range fails
+            assert_eq!(round_trip(&frame), Err(FrameError::OutOfRange(field)));
+        }
+
+        let leap_day = Frame {
+            day: 366,
+            year: 2024,
+            ..FRAME
+        };
+        assert_eq!(round_trip(&leap_day), Ok(leap_day));
+    }
+
+    #[test]
+    fn unreadable_data_is_rejected() {
+        let widths =
+            encode(&FRAME).map(|width| classify::<Jjy>(width as u32, Jjy::TOLERANCE_PERMILLE.0));
+
+        for (second, bit) in [
+            (3, BitWidth::Unknown),
+            (17, BitWidth::Marker),
+            (44, BitWidth::Unknown),
+        ] {
+            let mut buf = widths;
+            buf[second] = bit;
+            assert_eq!(decode(&buf), Err(FrameError::Unreadable(second)));
+        }
+    }
+
+    #[test]
+    fn parity_bits_follow_their_fields() {
+        for (minute, hour, pa2, pa1) in [(0, 0, 0, 0), (1, 0, 1, 0), (3, 3, 0, 0), (59, 23, 0, 1)] {
+            let widths = encode(&Frame {
+                minute,
+                hour,
+                ..FRAME
+            });
+            let parity = |second: usize| (widths[second] == 500) as u8;
+            assert_eq!((parity(37), parity(36)), (pa2, pa1), "{hour}:{minute}");
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! MSF, UK: two bits per second, A and B. 100 ms = 00, 200 ms = 10,
+//! 300 ms = 11, two 100 ms pulses = 01, and a 500 ms minute marker.
+
+use super::{
+    BitWidth, Frame, FrameError, Protocol, Slot, check_layout, field, frame_layout, second_bit,
+};
+use crate::timebase::{self, LeapSecond};
+
+pub struct Msf;
+
+/// Start-to-start interval of the two pulses of a 01 second.
+const DOUBLE_PERIOD_MS: core::ops::RangeInclusive<u32> = 150..=250;
+
+impl Protocol for Msf {
+    const NAME: &'static str = "MSF";
+
+    const WIDTHS: &'static [(BitWidth, u32)] = &[
+        (BitWidth::Short, 100),
+        (BitWidth::Medium, 200),
+        (BitWidth::Long, 300),
+        (BitWidth::Marker, 500),
+    ];
+
+    /// Only 100 ms apart, so 200 and 300 ms meet at 200 ‰.
+    const TOLERANCE_PERMILLE: (u32, u32) = (100, 180);
+
+    const UTC_OFFSET_MINUTES: i32 = 0;
+
+    const ONE: BitWidth = BitWidth::Medium;
+    const ZERO: BitWidth = BitWidth::Short;
+
+    /// Like DCF77, the frame gives the time at the next minute marker.
+    const COMMIT_SECOND: Option<u8> = None;
+
+    /// Year, month, day, weekday and hour, all in A.
+    const VOTED_FIELDS: &'static [(usize, usize)] = &[(17, 44)];
+
+    fn starts_frame(_previous: BitWidth, bit: BitWidth, _period_ms: Option<u32>) -> bool {
+        bit == BitWidth::Marker
+    }
+
+    fn check_slot(second: u8, bit: BitWidth) -> Result<(), &'static str> {
+        check_layout::<Msf>(&FRAME_LAYOUT, second, bit)
+    }
+
+    fn is_data(second: u8) -> bool {
+        FRAME_LAYOUT[second as usize] != Slot::Marker
+    }
+
+    fn decode(buf: &[BitWidth; 60]) -> Result<Frame, FrameError> {
+        decode(buf)
+    }
+
+    fn value(bit: BitWidth) -> Option<bool> {
+        match bit {
+            BitWidth::Short | BitWidth::Double => Some(false),
+            BitWidth::Medium | BitWidth::Long => Some(true),
+            _ => None,
+        }
+    }
+
+    fn second_value(bit: BitWidth) -> Option<bool> {
+        match bit {
+            BitWidth::Short | BitWidth::Medium => Some(false),
+            BitWidth::Long | BitWidth::Double => Some(true),
+            _ => None,
+        }
+    }
+
+    fn merge(previous: BitWidth, bit: BitWidth, period_ms: Option<u32>) -> Option<BitWidth> {
+        let double = previous == BitWidth::Short
+            && bit == BitWidth::Short
+            && period_ms.is_some_and(|period| DOUBLE_PERIOD_MS.contains(&period));
+        double.then_some(BitWidth::Double)
+    }
+}
+
+/// Minute marker, then the 01111110 minute identifier in A of 52-59.
+const FRAME_LAYOUT: [Slot; 60] = frame_layout(&[0], &[52, 59], &[53, 54, 55, 56, 57, 58]);
+
+const YEAR: [(usize, u32); 8] = [
+    (17, 80),
+    (18, 40),
+    (19, 20),
+    (20, 10),
+    (21, 8),
+    (22, 4),
+    (23, 2),
+    (24, 1),
+];
+const MONTH: [(usize, u32); 5] = [(25, 10), (26, 8), (27, 4), (28, 2), (29, 1)];
+const DAY: [(usize, u32); 6] = [(30, 20), (31, 10), (32, 8), (33, 4), (34, 2), (35, 1)];
+const WEEKDAY: [(usize, u32); 3] = [(36, 4), (37, 2), (38, 1)];
+const HOUR: [(usize, u32); 6] = [(39, 20), (40, 10), (41, 8), (42, 4), (43, 2), (44, 1)];
+const MINUTE: [(usize, u32); 7] = [
+    (45, 40),
+    (46, 20),
+    (47, 10),
+    (48, 8),
+    (49, 4),
+    (50, 2),
+    (51, 1),
+];
+
+/// B of 58: BST in effect.
+const SUMMER_TIME: usize = 58;
+
+/// Decodes the A fields of a frame and checks them against the parity
+/// bits in B of 54-57.
+fn decode(buf: &[BitWidth]) -> Result<Frame, FrameError> {
+    let (year, year_parity) = field::<Msf>(buf, &YEAR)?;
+    let (month, month_parity) = field::<Msf>(buf, &MONTH)?;
+    let (day, day_parity) = field::<Msf>(buf, &DAY)?;
+    let (weekday, weekday_parity) = field::<Msf>(buf, &WEEKDAY)?;
+    let (hour, hour_parity) = field::<Msf>(buf, &HOUR)?;
+    let (minute, minute_parity) = field::<Msf>(buf, &MINUTE)?;
+
+    // Odd parity, the parity bit included
+    if second_bit::<Msf>(buf, 54)? == year_parity {
+        return Err(FrameError::Parity("year"));
+    }
+
+    if second_bit::<Msf>(buf, 55)? == month_parity ^ day_parity {
+        return Err(FrameError::Parity("date"));
+    }
+
+    if second_bit::<Msf>(buf, 56)? == weekday_parity {
+        return Err(FrameError::Parity("weekday"));
+    }
+
+    if second_bit::<Msf>(buf, 57)? == hour_parity ^ minute_parity {
+        return Err(FrameError::Parity("time"));
+    }
+
+    // Only the last two digits are transmitted
+    let year = 2000 + year as u16;
+
+    if minute > 59 {
+        return Err(FrameError::OutOfRange("minute"));
+    }
+
+    if hour > 23 {
+        return Err(FrameError::OutOfRange("hour"));
+    }
+
+    let Some(day) = timebase::day_of_year(year, month as u8, day as u8) else {
+        return Err(FrameError::OutOfRange("day"));
+    };
+
+    Ok(Frame {
+        minute,
+        hour,
+        day,
+        year,
+        // 0 = Sunday, like ours
+        weekday: (weekday < 7).then_some(weekday as u8),
+        // Not announced at all
+        leap_second: LeapSecond::None,
+        summer_time: second_bit::<Msf>(buf, SUMMER_TIME)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2025-05-03, a Saturday, at 12:34 BST.
+    const FRAME: Frame = Frame {
+        minute: 34,
+        hour: 12,
+        day: 123,
+        year: 2025,
+        weekday: Some(6),
+        leap_second: LeapSecond::None,
+        summer_time: true,
+    };
+
+    /// The seconds MSF sends for `FRAME`, with DUT1 at +0.1 s: B of 1
+    /// set and A clear, the two pulses of a 01 second.
+    fn frame() -> [BitWidth; 60] {
+        let (mut a, mut b) = ([false; 60], [false; 60]);
+        let mut set = |field: &[(usize, u32)], mut value: u32| {
+            let mut parity = false;
+            for &(index, weight) in field {
+                if value >= weight {
+                    value -= weight;
+                    a[index] = true;
+                    parity = !parity;
+                }
+            }
+            parity
+        };
+
+        let year = set(&YEAR, 25);
+        let date = set(&MONTH, 5) ^ set(&DAY, 3);
+        let weekday = set(&WEEKDAY, 6);
+        let time = set(&HOUR, 12) ^ set(&MINUTE, 34);
+
+        // The minute identifier, and odd parity over each group
+        a[53..59].fill(true);
+        b[1] = true;
+        (b[54], b[55], b[56], b[57]) = (!year, !date, !weekday, !time);
+        b[SUMMER_TIME] = true;
+
+        core::array::from_fn(
+            |second| match (FRAME_LAYOUT[second], a[second], b[second]) {
+                (Slot::Marker, _, _) => BitWidth::Marker,
+                (_, false, false) => BitWidth::Short,
+                (_, true, false) => BitWidth::Medium,
+                (_, true, true) => BitWidth::Long,
+                (_, false, true) => {
+                    Msf::merge(BitWidth::Short, BitWidth::Short, Some(200)).unwrap()
+                }
+            },
+        )
+    }
+
+    #[test]
+    fn decodes_a_frame_with_a_01_second() {
+        let buf = frame();
+        assert_eq!(buf[1], BitWidth::Double);
+        assert_eq!(decode(&buf), Ok(FRAME));
+    }
+
+    #[test]
+    fn each_parity_bit_guards_its_group() {
+        for (second, group) in [(54, "year"), (55, "date"), (56, "weekday"), (57, "time")] {
+            // A stays set, B flips
+            let mut buf = frame();
+            buf[second] = match buf[second] {
+                BitWidth::Long => BitWidth::Medium,
+                _ => BitWidth::Long,
+            };
+            assert_eq!(decode(&buf), Err(FrameError::Parity(group)), "{second}");
+        }
+    }
+
+    #[test]
+    fn only_pulses_a_01_apart_merge() {
+        let merge = |period| Msf::merge(BitWidth::Short, BitWidth::Short, period);
+        for period in [150, 200, 250] {
+            assert_eq!(merge(Some(period)), Some(BitWidth::Double), "{period}");
+        }
+        for period in [Some(100), Some(149), Some(251), Some(1000), None] {
+            assert_eq!(merge(period), None, "{period:?}");
+        }
+
+        // Only two short pulses make one
+        let after_medium = Msf::merge(BitWidth::Medium, BitWidth::Short, Some(200));
+        assert_eq!(after_medium, None);
+    }
+}
@@ -0,0 +1,247 @@
+//! WWVB, US: 200 ms = 0, 500 ms = 1, 800 ms position markers at JJY's
+//! seconds, and the time in UTC.
+
+use super::{BitWidth, Frame, FrameError, Protocol, Slot, bit, check_layout, field, frame_layout};
+use crate::timebase::{self, LeapSecond};
+
+pub struct Wwvb;
+
+/// Standard time of the clock's zone against UTC, e.g. US Eastern.
+const UTC_OFFSET_MINUTES: i32 = -5 * 60;
+
+impl Protocol for Wwvb {
+    const NAME: &'static str = "WWVB";
+
+    const WIDTHS: &'static [(BitWidth, u32)] = &[
+        (BitWidth::Short, 200),
+        (BitWidth::Long, 500),
+        (BitWidth::Marker, 800),
+    ];
+
+    const TOLERANCE_PERMILLE: (u32, u32) = (120, 220);
+
+    const UTC_OFFSET_MINUTES: i32 = UTC_OFFSET_MINUTES;
+
+    const ONE: BitWidth = BitWidth::Long;
+    const ZERO: BitWidth = BitWidth::Short;
+
+    /// Like JJY, the frame gives the time at its own second 0 and closes
+    /// with the P0 marker.
+    const COMMIT_SECOND: Option<u8> = Some(59);
+
+    /// Hour, day, year, leap year and DST. The UT1 bits are never read.
+    const VOTED_FIELDS: &'static [(usize, usize)] = &[(12, 18), (22, 33), (45, 53), (55, 58)];
+
+    /// P0 followed by the frame reference marker.
+    fn starts_frame(previous: BitWidth, bit: BitWidth, _period_ms: Option<u32>) -> bool {
+        previous == BitWidth::Marker && bit == BitWidth::Marker
+    }
+
+    fn check_slot(second: u8, bit: BitWidth) -> Result<(), &'static str> {
+        check_layout::<Wwvb>(&FRAME_LAYOUT, second, bit)
+    }
+
+    fn is_data(second: u8) -> bool {
+        FRAME_LAYOUT[second as usize] != Slot::Marker
+    }
+
+    fn decode(buf: &[BitWidth; 60]) -> Result<Frame, FrameError> {
+        decode(buf)
+    }
+}
+
+/// Frame reference marker, then P1-P5, then P0. UT1 sign and correction
+/// (36-43) are data but belong to no field.
+const FRAME_LAYOUT: [Slot; 60] = frame_layout(
+    &[0, 9, 19, 29, 39, 49, 59],
+    &[4, 10, 11, 14, 20, 21, 24, 34, 35, 44, 54],
+    &[],
+);
+
+const MINUTE: [(usize, u32); 7] = [(1, 40), (2, 20), (3, 10), (5, 8), (6, 4), (7, 2), (8, 1)];
+const HOUR: [(usize, u32); 6] = [(12, 20), (13, 10), (15, 8), (16, 4), (17, 2), (18, 1)];
+const DAY: [(usize, u32); 10] = [
+    (22, 200),
+    (23, 100),
+    (25, 80),
+    (26, 40),
+    (27, 20),
+    (28, 10),
+    (30, 8),
+    (31, 4),
+    (32, 2),
+    (33, 1),
+];
+const YEAR: [(usize, u32); 8] = [
+    (45, 80),
+    (46, 40),
+    (47, 20),
+    (48, 10),
+    (50, 8),
+    (51, 4),
+    (52, 2),
+    (53, 1),
+];
+
+const LEAP_YEAR: usize = 55;
+/// Whether DST is in effect at 24:00 and at 00:00 UTC of the transmitted
+/// day; they differ on the day it begins or ends.
+const DST_AT_END: usize = 57;
+const DST_AT_START: usize = 58;
+
+/// Decodes a frame recorded up to its P0 marker into local time.
+fn decode(buf: &[BitWidth]) -> Result<Frame, FrameError> {
+    let (minute, _) = field::<Wwvb>(buf, &MINUTE)?;
+    let (hour, _) = field::<Wwvb>(buf, &HOUR)?;
+    let (day, _) = field::<Wwvb>(buf, &DAY)?;
+    let (year, _) = field::<Wwvb>(buf, &YEAR)?;
+
+    // Only the last two digits are transmitted
+    let year = 2000 + year as u16;
+    let day = day as u16;
+
+    // There is no parity at all, so range checks are all that catch noise
+    if minute > 59 {
+        return Err(FrameError::OutOfRange("minute"));
+    }
+
+    if hour > 23 {
+        return Err(FrameError::OutOfRange("hour"));
+    }
+
+    if timebase::month_day(year, day).is_none() {
+        return Err(FrameError::OutOfRange("day"));
+    }
+
+    if bit::<Wwvb>(buf, LEAP_YEAR)? != timebase::is_leap_year(year) {
+        return Err(FrameError::OutOfRange("leap year"));
+    }
+
+    let utc = timebase::minute_stamp(year, day, hour, minute);
+    let standard = utc.wrapping_add_signed(UTC_OFFSET_MINUTES);
+
+    // US zones switch at 02:00 local, well inside the UTC day the bits cover
+    let same_day = utc / (24 * 60) == standard / (24 * 60);
+    let minute_of_day = standard % (24 * 60);
+    let dst = match (
+        bit::<Wwvb>(buf, DST_AT_END)?,
+        bit::<Wwvb>(buf, DST_AT_START)?,
+    ) {
+        (true, true) => true,
+        (false, false) => false,
+        // Begins at 02:00 standard time
+        (true, false) => same_day && minute_of_day >= 2 * 60,
+        // Ends at 02:00 DST, which is 01:00 standard time
+        (false, true) => !same_day || minute_of_day < 60,
+    };
+
+    let local = standard + if dst { 60 } else { 0 };
+    let (year, day) = timebase::date_since_2000(local / (24 * 60));
+
+    Ok(Frame {
+        minute: local % 60,
+        hour: local / 60 % 24,
+        day,
+        year,
+        // Not transmitted, but the date is enough
+        weekday: Some(timebase::weekday_of(year, day)),
+        // Bit 56 warns of a leap second at the end of the UTC month, but
+        // not which way; the next decode absorbs it
+        leap_second: LeapSecond::None,
+        summer_time: dst,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The bits WWVB sends at `hour`:`minute` UTC of a day, with the leap
+    /// year bit as the year has it and the two DST bits as given.
+    fn frame(year: u16, day: u16, hour: u32, minute: u32, dst: (bool, bool)) -> [BitWidth; 60] {
+        let mut bits = [false; 60];
+        for (field, mut value) in [
+            (&MINUTE[..], minute),
+            (&HOUR[..], hour),
+            (&DAY[..], day as u32),
+            (&YEAR[..], year as u32 % 100),
+        ] {
+            for &(index, weight) in field {
+                if value >= weight {
+                    value -= weight;
+                    bits[index] = true;
+                }
+            }
+        }
+        bits[LEAP_YEAR] = timebase::is_leap_year(year);
+        (bits[DST_AT_END], bits[DST_AT_START]) = dst;
+
+        core::array::from_fn(|second| match (FRAME_LAYOUT[second], bits[second]) {
+            (Slot::Marker, _) => BitWidth::Marker,
+            (_, true) => Wwvb::ONE,
+            (_, false) => Wwvb::ZERO,
+        })
+    }
+
+    #[test]
+    fn decodes_a_frame_into_local_time() {
+        // 16:34 UTC on 2025-05-03, a Saturday, is 12:34 EDT
+        let buf = frame(2025, 123, 16, 34, (true, true));
+        let expected = Frame {
+            minute: 34,
+            hour: 12,
+            day: 123,
+            year: 2025,
+            weekday: Some(6),
+            leap_second: LeapSecond::None,
+            summer_time: true,
+        };
+        assert_eq!(decode(&buf), Ok(expected));
+    }
+
+    #[test]
+    fn the_leap_year_bit_must_match_the_year() {
+        let leap_day = frame(2024, 366, 12, 0, (false, false));
+        assert!(decode(&leap_day).is_ok());
+
+        let mut buf = leap_day;
+        buf[LEAP_YEAR] = Wwvb::ZERO;
+        assert_eq!(decode(&buf), Err(FrameError::OutOfRange("leap year")));
+
+        let mut buf = frame(2025, 123, 12, 0, (false, false));
+        buf[LEAP_YEAR] = Wwvb::ONE;
+        assert_eq!(decode(&buf), Err(FrameError::OutOfRange("leap year")));
+    }
+
+    #[test]
+    fn dst_bits_switch_at_two_local() {
+        // 2025-03-09 and 2025-11-02, when US DST began and ended. It
+        // begins at 02:00 EST, 07:00 UTC, and ends at 02:00 EDT, 06:00 UTC.
+        const BEGINS: u16 = 68;
+        const ENDS: u16 = 306;
+
+        for (dst, day, utc, local) in [
+            // In effect all day
+            ((true, true), BEGINS, (6, 59), (2, 59, true)),
+            ((true, true), BEGINS, (7, 0), (3, 0, true)),
+            // Not in effect at all
+            ((false, false), BEGINS, (6, 59), (1, 59, false)),
+            ((false, false), BEGINS, (7, 0), (2, 0, false)),
+            // Begins: 01:59 EST, then 03:00 EDT
+            ((true, false), BEGINS, (6, 59), (1, 59, false)),
+            ((true, false), BEGINS, (7, 0), (3, 0, true)),
+            // Ends: 01:59 EDT, then 01:00 EST
+            ((false, true), ENDS, (5, 59), (1, 59, true)),
+            ((false, true), ENDS, (6, 0), (1, 0, false)),
+        ] {
+            let (hour, minute) = utc;
+            let frame = decode(&frame(2025, day, hour, minute, dst)).unwrap();
+            assert_eq!(
+                (frame.hour, frame.minute, frame.summer_time),
+                local,
+                "{dst:?} at {hour:02}:{minute:02} UTC"
+            );
+            assert_eq!(frame.day, day);
+        }
+    }
+}
@@ -0,0 +1,681 @@
+#[cfg(feature = "i2c-display")]
+use ch32_hal::Peri;
+#[cfg(feature = "i2c-display")]
+use ch32_hal::peripherals::{I2C1, PB6, PB7};
+use ch32_jjy_clock::display::pages::{self, Editing, RIGHT_END, SetupPage, Strings};
+use ch32_jjy_clock::display::screen::{self, Screen};
+use ch32_jjy_clock::display::template::{Fields, Filled};
+use ch32_jjy_clock::wizard::{Step, Wizard};
+use embassy_futures::select::select;
+use embassy_time::{Instant, Timer};
+
+use crate::decoder::BitWidth;
+use crate::fault::{self, Fault};
+use crate::manual::{Field, ManualSet, Target};
+use crate::state::{self, ClockState, Reception};
+use crate::timebase::TimeBase;
+use crate::{log, monotonic, power, settings, timebase, watchdog};
+#[cfg(not(feature = "oled"))]
+use hd44780::Lcd;
+#[cfg(not(feature = "i2c-display"))]
+pub use hd44780::{DataBus, DisplayPins};
+#[cfg(feature = "oled")]
+use ssd1306::Oled;
+
+#[cfg(not(feature = "oled"))]
+mod hd44780;
+#[cfg(feature = "oled")]
+mod ssd1306;
+
+/// A display the screen model can be drawn on. Character codes 0-7 are
+/// the glyphs set with `define_glyph`, the rest ASCII.
+trait Panel {
+    /// Brings the display up, blank.
+    async fn init(&mut self);
+
+    /// Changes the display from showing `shown` to showing `screen`.
+    async fn show(&mut self, shown: &Screen, screen: &Screen);
+
+    /// Sets the 5x8 glyph, top row first, shown for character code `slot`
+    /// (0-7), also where it is already on screen.
+    async fn define_glyph(&mut self, slot: u8, pattern: &[u8; 8]);
+
+    async fn clear(&mut self);
+
+    /// Turns the display off and on again, keeping its contents.
+    async fn set_enabled(&mut self, on: bool);
+}
+
+/// Draws whatever differs between `shown` and `screen`.
+async fn show(panel: &mut impl Panel, shown: &mut Screen, screen: &Screen) {
+    panel.show(shown, screen).await;
+    *shown = *screen;
+}
+
+/// Redraw this long after each second boundary, so the new second is
+/// already current when it is read back.
+const REDRAW_AFTER_MS: u64 = 50;
+
+/// Between these local hours, hide the seconds and the carrier indicator
+/// so the display stays still in a bedroom. The `backlight` feature dims
+/// the backlight on a schedule of its own.
+const NIGHT_PROFILE: bool = true;
+const NIGHT_START_HOUR: u8 = 22;
+const NIGHT_END_HOUR: u8 = 7;
+
+fn is_night(hour: u8) -> bool {
+    NIGHT_PROFILE && (hour >= NIGHT_START_HOUR || hour < NIGHT_END_HOUR)
+}
+
+/// Shown in turn after "Sync --/60" while waiting for a frame to start.
+const SPINNER: [char; 4] = ['.', 'o', 'O', 'o'];
+const SPINNER_STEP_MS: u64 = 500;
+
+/// After a station change, its name replaces the sync age for this long.
+const PROTOCOL_SHOW_MS: u64 = 3000;
+
+/// Past `timebase::STALE_AFTER_MS` without a sync the time is marked
+/// stale, and after this the mark blinks.
+const STALE_BLINK_AFTER_MS: u64 = 3 * 24 * 60 * 60 * 1000;
+
+/// After a zone change, the zone replaces the whole bottom line for this
+/// long.
+const ZONE_SHOW_MS: u64 = 3000;
+
+/// After a watchdog reset, "WDT reset" replaces the bottom line for this
+/// long from boot; 0 never shows it.
+const WATCHDOG_SHOW_MS: u64 = 10 * 1000;
+
+// Where everything goes, as (line, column), for the size in `geometry`.
+// The status cells stay at the right end of the top line.
+/// "HH:MM:SS", then "?" while the seconds are imprecise. Just "HH:MM" on
+/// an 8-column module.
+const CLOCK_AT: (usize, usize) = (0, 0);
+const SHOW_SECONDS: bool = screen::COLUMNS >= 16;
+/// "!" once the last sync is `timebase::STALE_AFTER_MS` old.
+#[cfg(not(feature = "lcd-8x2"))]
+const STALE_AT: (usize, usize) = (0, 9);
+#[cfg(not(feature = "lcd-8x2"))]
+const WEEKDAY_AT: Option<(usize, usize)> = Some((0, 10));
+#[cfg(not(feature = "lcd-8x2"))]
+const QUALITY_AT: (usize, usize) = (0, screen::COLUMNS - 3);
+#[cfg(not(feature = "lcd-8x2"))]
+const SIGNAL_LOST_AT: (usize, usize) = (0, screen::COLUMNS - 2);
+// 8 columns have room for one warning cell after "HH:MM", where the signal
+// loss mark covers the stale mark, which covers the "?". The weekday is
+// only on the date page.
+#[cfg(feature = "lcd-8x2")]
+const STALE_AT: (usize, usize) = (0, 5);
+#[cfg(feature = "lcd-8x2")]
+const WEEKDAY_AT: Option<(usize, usize)> = None;
+#[cfg(feature = "lcd-8x2")]
+const QUALITY_AT: (usize, usize) = (0, 6);
+#[cfg(feature = "lcd-8x2")]
+const SIGNAL_LOST_AT: (usize, usize) = (0, 5);
+/// Antenna or signal bars.
+const STATUS_AT: (usize, usize) = (0, screen::COLUMNS - 1);
+/// "YYYY-MM-DD" once synced, "MM-DD" on an 8-column module, which has no
+/// room for the sync age after it.
+const DATE_AT: (usize, usize) = (1, 0);
+const SHOW_SYNC_AGE: bool = screen::COLUMNS >= 16;
+/// Last pulse until the first sync, and the seconds received too while
+/// the time is one set by hand.
+const PROGRESS_AT: (usize, usize) = (1, 0);
+/// "UTC+09:00", for a moment after a zone change.
+const ZONE_AT: (usize, usize) = (1, 0);
+/// "WDT reset", for a moment after boot.
+const WATCHDOG_AT: (usize, usize) = (1, 0);
+
+/// On a 4-line module, the time page goes on with the stats page's top
+/// line and the signal page's pulses from this line.
+const EXTRA_LINES_AT: Option<usize> = match screen::LINES {
+    4 => Some(2),
+    _ => None,
+};
+
+/// The strings of the language the settings have.
+fn strings() -> &'static Strings {
+    settings::get().language.strings()
+}
+
+/// "AM" or "PM" in 12-hour time, where the weekday goes otherwise; the
+/// weekday is still on the date page. 8 columns have no room for either.
+const MERIDIEM_AT: Option<(usize, usize)> = WEEKDAY_AT;
+
+/// Steps of the first-boot setup this display has nothing to offer for:
+/// the OLED font has no kana, and 8 columns no room for AM or PM.
+pub const SETUP_SKIPPED: &[Step] = &[
+    #[cfg(feature = "oled")]
+    Step::Language,
+    #[cfg(feature = "lcd-8x2")]
+    Step::HourFormat,
+];
+
+/// The hour shown, 1 to 12 in 12-hour time where there is room to tell
+/// AM from PM.
+fn shown_hour(hour: u8) -> u8 {
+    match settings::get().twelve_hour && MERIDIEM_AT.is_some() {
+        true if hour % 12 == 0 => 12,
+        true => hour % 12,
+        false => hour,
+    }
+}
+
+/// Flip the HH:MM digits that change at a minute or hour transition.
+const TRANSITION_ANIMATION: bool = true;
+
+/// Blank the clock's colons on odd seconds, outside night hours, so a
+/// still display is visibly not hung. Only the colon cells are redrawn.
+const BLINK_COLONS: bool = true;
+
+const FLIP_FRAME_MS: u64 = 60;
+
+// CGRAM slots of the custom glyphs, 8 at most
+const FLIP_TOP: u8 = 0;
+const FLIP_BOTTOM: u8 = 1;
+const SIGNAL_LOST: u8 = 2;
+const ANTENNA: u8 = 3;
+/// One to three bars, in slots 4-6.
+const BARS: [u8; 3] = [4, 5, 6];
+/// Redefined on the fly to a bar as tall as the signal quality.
+const QUALITY: u8 = 7;
+
+const FLIP_TOP_GLYPH: [u8; 8] = [
+    0b11111, 0b11111, 0b11111, 0b11111, 0b00000, 0b00000, 0b00000, 0b00000,
+];
+const FLIP_BOTTOM_GLYPH: [u8; 8] = [
+    0b00000, 0b00000, 0b00000, 0b00000, 0b11111, 0b11111, 0b11111, 0b11111,
+];
+const SIGNAL_LOST_GLYPH: [u8; 8] = [
+    0b10101, 0b01110, 0b00100, 0b00100, 0b00000, 0b01010, 0b00100, 0b01010,
+];
+const ANTENNA_GLYPH: [u8; 8] = [
+    0b10101, 0b10101, 0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000,
+];
+const BARS_GLYPHS: [[u8; 8]; 3] = [
+    [
+        0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b10000, 0b10000, 0b00000,
+    ],
+    [
+        0b00000, 0b00000, 0b00000, 0b00100, 0b00100, 0b10100, 0b10100, 0b00000,
+    ],
+    [
+        0b00000, 0b00001, 0b00001, 0b00101, 0b00101, 0b10101, 0b10101, 0b00000,
+    ],
+];
+
+const GLYPHS: [(u8, &[u8; 8]); 7] = [
+    (FLIP_TOP, &FLIP_TOP_GLYPH),
+    (FLIP_BOTTOM, &FLIP_BOTTOM_GLYPH),
+    (SIGNAL_LOST, &SIGNAL_LOST_GLYPH),
+    (ANTENNA, &ANTENNA_GLYPH),
+    (BARS[0], &BARS_GLYPHS[0]),
+    (BARS[1], &BARS_GLYPHS[1]),
+    (BARS[2], &BARS_GLYPHS[2]),
+];
+
+/// Bar for a signal quality `score`, one row per point from the bottom.
+fn quality_glyph(score: u8) -> [u8; 8] {
+    let mut pattern = [0; 8];
+    for (i, row) in pattern.iter_mut().enumerate() {
+        if 8 - i <= score as usize {
+            *row = 0b01110;
+        }
+    }
+    pattern
+}
+
+/// Decodes within this window set how many bars the status cell shows.
+const SIGNAL_WINDOW_MS: u64 = 10 * 60 * 1000;
+
+/// Status cell glyph for `decodes` within `SIGNAL_WINDOW_MS`: one bar from
+/// the first, all three from 8 of the 10 possible.
+fn bars_for(decodes: usize) -> u8 {
+    match decodes {
+        0 => b' ',
+        1..=3 => BARS[0],
+        4..=7 => BARS[1],
+        _ => BARS[2],
+    }
+}
+
+/// What the display shows, stepped through with the page button.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Page {
+    Time,
+    Date,
+    Stats,
+    Signal,
+}
+
+impl Page {
+    fn next(self) -> Self {
+        match self {
+            Page::Time => Page::Date,
+            Page::Date => Page::Stats,
+            Page::Stats => Page::Signal,
+            Page::Signal => Page::Time,
+        }
+    }
+}
+
+/// Any other page goes back to the time page after this long without a
+/// press, so diagnostics are never left up.
+const PAGE_TIMEOUT_MS: u64 = 30 * 1000;
+
+/// Clock, weekday and status on top; the date and sync age below once
+/// synced, reception progress before.
+fn time_page(screen: &mut Screen, current: &ClockState, base: Option<TimeBase>, now: u64) {
+    let strings = strings();
+    let night = base.is_some_and(|b| is_night(b.wall_clock(now, settings::zone()).hour));
+
+    match base {
+        Some(timebase) => {
+            let clock = timebase.wall_clock(now, settings::zone());
+            let (line, mut column) = CLOCK_AT;
+
+            let colon = if BLINK_COLONS && !night && clock.second % 2 == 1 {
+                ' '
+            } else {
+                ':'
+            };
+
+            let (hour, minute) = (shown_hour(clock.hour), clock.minute);
+            column = screen.write(line, column, format_args!("{hour:02}{colon}{minute:02}"));
+            if SHOW_SECONDS {
+                column = match night {
+                    true => screen.write(line, column, "   "),
+                    false => screen.write(line, column, format_args!("{colon}{:02}", clock.second)),
+                };
+            }
+
+            // "?" once the free-running error may exceed half a second
+            if !timebase.seconds_are_precise(now) {
+                screen.write(line, column, "?");
+            }
+
+            let age = current.last_sync_at().map(|at| now - at);
+            if let Some(age) = age.filter(|age| *age >= timebase::STALE_AFTER_MS)
+                && (age < STALE_BLINK_AFTER_MS || clock.second % 2 == 0)
+            {
+                let (line, column) = STALE_AT;
+                screen.write(line, column, "!");
+            }
+
+            if settings::get().twelve_hour
+                && let Some((line, column)) = MERIDIEM_AT
+            {
+                let meridiem = if clock.hour < 12 { "AM" } else { "PM" };
+                screen.write(line, column, meridiem);
+            } else if let Some(weekday) = clock.weekday
+                && let Some((line, column)) = WEEKDAY_AT
+            {
+                screen.write(line, column, strings.weekdays[weekday as usize]);
+            }
+
+            if fault::is_active(Fault::SignalLost) {
+                let (line, column) = SIGNAL_LOST_AT;
+                screen.put(line, column, &[SIGNAL_LOST]);
+            }
+        }
+        // "Sync 23/60", or "Sync --/60" and a spinner until a frame starts
+        None => {
+            let (line, column) = CLOCK_AT;
+            let (label, length) = (strings.receiving, strings.frame_length);
+            match current.progress {
+                Some(seconds) => {
+                    screen.write(line, column, format_args!("{label} {seconds:02}{length}"));
+                }
+                None => {
+                    let stage = (now / SPINNER_STEP_MS % SPINNER.len() as u64) as usize;
+                    let spinner = SPINNER[stage];
+                    screen.write(line, column, format_args!("{label} --{length} {spinner}"));
+                }
+            }
+        }
+    }
+
+    let (line, column) = QUALITY_AT;
+    screen.put(line, column, &[QUALITY]);
+
+    // The antenna while a pulse is on air, "M" while the time is one set
+    // by hand, "x" without a signal, and decode success otherwise
+    let status = if current.carrier && !night {
+        ANTENNA
+    } else if base.is_some_and(|b| b.manual) {
+        b'M'
+    } else if current.reception == Reception::NoSignal {
+        b'x'
+    } else {
+        bars_for(current.syncs_within(now, SIGNAL_WINDOW_MS))
+    };
+
+    let (line, column) = STATUS_AT;
+    screen.put(line, column, &[status]);
+
+    if let Some(line) = EXTRA_LINES_AT {
+        let stats = &current.stats;
+        let age = current.last_sync_at().map(|at| now - at);
+        pages::stats_line(screen, line, stats.decodes, stats.parity_errors, age);
+        pages::pulses_line(screen, line + 1, &current.recent_pulses);
+    }
+
+    if current.zone_at.is_some_and(|at| now - at < ZONE_SHOW_MS) {
+        let (sign, hours, minutes) = timebase::zone_parts(settings::zone());
+        let sign = sign as char;
+        let (line, column) = ZONE_AT;
+        screen.write(
+            line,
+            column,
+            format_args!("UTC{sign}{hours:02}:{minutes:02}"),
+        );
+        return;
+    }
+
+    if watchdog::was_reset() && now < WATCHDOG_SHOW_MS {
+        let (line, column) = WATCHDOG_AT;
+        screen.write(line, column, "WDT reset");
+        return;
+    }
+
+    let protocol_recent = now - current.protocol_at < PROTOCOL_SHOW_MS;
+
+    match (base, current.last_sync_at()) {
+        // "2025-05-03   12m", or the line the settings' template lays out
+        (Some(timebase), Some(synced_at)) => {
+            let date = timebase.wall_clock(now, settings::zone());
+            let template = settings::get().status_line;
+            let (line, column) = DATE_AT;
+            match template.is_empty() {
+                true => pages::put_date(screen, line, column, date.year, date.month, date.day),
+                false => {
+                    let fields = Fields {
+                        clock: date,
+                        weekdays: &strings.weekdays,
+                        quality: current.signal_quality,
+                        sync_age_ms: Some(now - synced_at),
+                        protocol: current.protocol,
+                    };
+                    let filled = Filled {
+                        template: &template,
+                        fields: &fields,
+                    };
+                    screen.write(line, column, filled)
+                }
+            };
+
+            let (line, end) = RIGHT_END;
+            if protocol_recent {
+                screen.write_end(line, end, current.protocol);
+            } else if SHOW_SYNC_AGE && template.is_empty() {
+                pages::put_sync_age(screen, line, end, now - synced_at);
+            }
+        }
+        // "No signal  JJY40": the receiver has gone quiet
+        _ if current.reception == Reception::NoSignal => {
+            let (line, column) = PROGRESS_AT;
+            screen.write(line, column, strings.no_signal);
+
+            let (line, end) = RIGHT_END;
+            screen.write_end(line, end, current.protocol);
+        }
+        // "L 12/60    JJY40": the receiver is alive, and what for
+        _ => {
+            let width = match current.latest_bitwidth {
+                BitWidth::Long => 'L',
+                BitWidth::Short => 'S',
+                BitWidth::Marker => 'M',
+                BitWidth::Medium => 'm',
+                BitWidth::Double => 'D',
+                BitWidth::Unknown => '?',
+            };
+
+            // Before the first sync the top line counts the seconds
+            let (line, column) = PROGRESS_AT;
+            match base {
+                Some(_) => screen.write(
+                    line,
+                    column,
+                    format_args!("{width} {:02}/60", current.cursor),
+                ),
+                None => screen.write(line, column, width),
+            };
+
+            let (line, end) = RIGHT_END;
+            screen.write_end(line, end, current.protocol);
+        }
+    }
+}
+
+/// The field being set is hidden for the second half of each blink.
+const BLINK_MS: u64 = 500;
+
+fn setting_page(screen: &mut Screen, setting: ManualSet, now: u64) {
+    let editing = match setting.field {
+        Field::Target => Editing::Target,
+        Field::Hour => Editing::Hour,
+        Field::Minute => Editing::Minute,
+        Field::Enabled => Editing::Enabled,
+    };
+
+    // While choosing, the target shown is the one a hold picks
+    let alarm = match setting.target {
+        Target::Clock => None,
+        Target::Alarm => Some(setting.enabled),
+    };
+
+    let hidden = now % (2 * BLINK_MS) >= BLINK_MS;
+    let (hour, minute) = (setting.hour, setting.minute);
+    pages::setting_page(screen, editing, alarm, hour, minute, hidden);
+}
+
+fn setup_page(screen: &mut Screen, wizard: Wizard, current: &ClockState) {
+    let page = match wizard.step {
+        Step::Language => SetupPage::Language(wizard.language),
+        Step::Zone => SetupPage::Zone(wizard.zone_minutes),
+        Step::HourFormat => SetupPage::TwelveHour(wizard.twelve_hour),
+        Step::Antenna => SetupPage::Antenna(&current.recent_pulses),
+        Step::FirstSync => SetupPage::FirstSync {
+            progress: current.progress,
+            synced: current.last_sync_at().is_some(),
+        },
+    };
+
+    // In the language just chosen
+    pages::setup_page(screen, wizard.language.strings(), page);
+}
+
+/// Plays the flip over each HH:MM digit that differs between `old` and `new`.
+async fn animate_transition(
+    panel: &mut impl Panel,
+    shown: &mut Screen,
+    old: [u8; 4],
+    new: [u8; 4],
+) {
+    const CELLS: [usize; 4] = [0, 1, 3, 4];
+    let (line, column) = CLOCK_AT;
+
+    for glyph in [FLIP_TOP, FLIP_BOTTOM] {
+        let mut frame = *shown;
+        for ((cell, o), n) in CELLS.iter().zip(old).zip(new) {
+            if o != n {
+                frame.put(line, column + cell, &[glyph]);
+            }
+        }
+
+        show(panel, shown, &frame).await;
+        Timer::after_millis(FLIP_FRAME_MS).await;
+    }
+}
+
+#[cfg(not(feature = "i2c-display"))]
+#[embassy_executor::task]
+pub async fn display_task(pins: DisplayPins) {
+    run(Lcd::new(pins)).await;
+}
+
+#[cfg(feature = "lcd-i2c")]
+#[embassy_executor::task]
+pub async fn display_task(
+    i2c: Peri<'static, I2C1>,
+    scl: Peri<'static, PB6>,
+    sda: Peri<'static, PB7>,
+) {
+    run(Lcd::new(hd44780::Backpack::new(i2c, scl, sda))).await;
+}
+
+#[cfg(feature = "oled")]
+#[embassy_executor::task]
+pub async fn display_task(
+    i2c: Peri<'static, I2C1>,
+    scl: Peri<'static, PB6>,
+    sda: Peri<'static, PB7>,
+) {
+    run(Oled::new(i2c, scl, sda)).await;
+}
+
+async fn run(mut panel: impl Panel) {
+    Timer::after_millis(100).await;
+
+    panel.init().await;
+
+    for (slot, pattern) in GLYPHS {
+        panel.define_glyph(slot, pattern).await;
+    }
+
+    let mut shown = Screen::blank();
+    let mut sync_screen = Screen::blank();
+    sync_screen.write(CLOCK_AT.0, CLOCK_AT.1, strings().receiving);
+    show(&mut panel, &mut shown, &sync_screen).await;
+    let mut showing_time = false;
+
+    let mut shown_digits: Option<[u8; 4]> = None;
+    let mut shown_quality: Option<u8> = None;
+    let mut blanked = false;
+
+    let mut page = Page::Time;
+    let mut page_at = 0;
+    let mut page_presses = 0;
+
+    // The first redraw with the time on it is timed, as a bus timing check
+    let mut redraw_timed = false;
+
+    let mut changes = state::watch();
+
+    loop {
+        watchdog::beat(watchdog::Task::Display);
+
+        // Redraws come with each second, or each blink while setting, and
+        // whenever the state changes; passes come at least once a heartbeat
+        let now = monotonic::now_ms();
+        let next_update_after = match (state::snapshot().setting, timebase::load()) {
+            (Some(_), _) => BLINK_MS - now % BLINK_MS,
+            (None, Some(b)) => {
+                let after = now.saturating_sub(REDRAW_AFTER_MS);
+                b.next_second_at(after) + REDRAW_AFTER_MS - now
+            }
+            (None, None) => watchdog::HEARTBEAT_MS,
+        };
+
+        let wait = next_update_after.min(watchdog::HEARTBEAT_MS);
+        select(Timer::after_millis(wait), changes.changed()).await;
+
+        let current = state::snapshot();
+        let base = timebase::load();
+        let now = monotonic::now_ms();
+
+        // A step for every press, even if several came in since the last pass
+        while page_presses != current.page_presses {
+            page_presses = page_presses.wrapping_add(1);
+            page = page.next();
+            page_at = now;
+        }
+
+        if page != Page::Time && now - page_at > PAGE_TIMEOUT_MS {
+            page = Page::Time;
+        }
+
+        if current.standby || power::is_blanked(&current, base, now) {
+            if !blanked {
+                panel.set_enabled(false).await;
+                blanked = true;
+            }
+            continue;
+        }
+
+        if blanked {
+            panel.set_enabled(true).await;
+            blanked = false;
+        }
+
+        let redraw_at = Instant::now();
+
+        // Clear only between the Sync screen and the time screen
+        if base.is_some() != showing_time {
+            panel.clear().await;
+            shown = Screen::blank();
+            showing_time = base.is_some();
+        }
+
+        // Only the time page has the digits to flip
+        let on_time_page =
+            page == Page::Time && current.setting.is_none() && current.setup.is_none();
+        let digits = base.filter(|_| on_time_page).map(|b| {
+            let c = b.wall_clock(now, settings::zone());
+            let hour = shown_hour(c.hour);
+            [hour / 10, hour % 10, c.minute / 10, c.minute % 10]
+        });
+
+        if TRANSITION_ANIMATION
+            && let (Some(old), Some(new)) = (shown_digits, digits)
+            && old != new
+        {
+            animate_transition(&mut panel, &mut shown, old, new).await;
+        }
+
+        shown_digits = digits;
+
+        // The cell keeps showing the glyph; redefining it is enough
+        if shown_quality != Some(current.signal_quality) {
+            let glyph = quality_glyph(current.signal_quality);
+            panel.define_glyph(QUALITY, &glyph).await;
+            shown_quality = Some(current.signal_quality);
+        }
+
+        let mut screen = Screen::blank();
+        match (current.setup, current.setting, page) {
+            (Some(wizard), _, _) => setup_page(&mut screen, wizard, &current),
+            (None, Some(setting), _) => setting_page(&mut screen, setting, now),
+            (None, None, Page::Time) => time_page(&mut screen, &current, base, now),
+            (None, None, Page::Date) => {
+                let date = base.map(|b| b.wall_clock(now, settings::zone()));
+                pages::date_page(&mut screen, strings(), date);
+            }
+            (None, None, Page::Stats) => {
+                let stats = &current.stats;
+                let age = current.last_sync_at().map(|at| now - at);
+                let drift = base.and_then(|b| b.drift_ppm);
+                pages::stats_page(
+                    &mut screen,
+                    stats.decodes,
+                    stats.parity_errors,
+                    age,
+                    current.temperature,
+                    drift,
+                    now,
+                );
+            }
+            (None, None, Page::Signal) => {
+                let (pulses, cursor) = (&current.recent_pulses, current.cursor);
+                pages::signal_page(&mut screen, pulses, cursor, current.protocol);
+            }
+        }
+
+        show(&mut panel, &mut shown, &screen).await;
+
+        if !redraw_timed && base.is_some() {
+            log::debug!("Display redraw took {} us", redraw_at.elapsed().as_micros());
+            redraw_timed = true;
+        }
+    }
+}
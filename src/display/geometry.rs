@@ -0,0 +1,25 @@
+//! Size of the character module and the DDRAM address each line starts
+//! at: 16x2 unless the `lcd-20x4` or `lcd-8x2` feature says otherwise.
+//! Lines are always addressed separately, so a 20x4 module's line 0
+//! running on into line 2 in DDRAM never shows.
+
+#[cfg(not(any(feature = "lcd-20x4", feature = "lcd-8x2")))]
+pub const COLUMNS: usize = 16;
+#[cfg(not(any(feature = "lcd-20x4", feature = "lcd-8x2")))]
+pub const LINES: usize = 2;
+#[cfg(not(any(feature = "lcd-20x4", feature = "lcd-8x2")))]
+pub const LINE_ADDRESSES: [u8; LINES] = [0x00, 0x40];
+
+#[cfg(feature = "lcd-20x4")]
+pub const COLUMNS: usize = 20;
+#[cfg(feature = "lcd-20x4")]
+pub const LINES: usize = 4;
+#[cfg(feature = "lcd-20x4")]
+pub const LINE_ADDRESSES: [u8; LINES] = [0x00, 0x40, 0x14, 0x54];
+
+#[cfg(feature = "lcd-8x2")]
+pub const COLUMNS: usize = 8;
+#[cfg(feature = "lcd-8x2")]
+pub const LINES: usize = 2;
+#[cfg(feature = "lcd-8x2")]
+pub const LINE_ADDRESSES: [u8; LINES] = [0x00, 0x40];
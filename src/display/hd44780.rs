@@ -0,0 +1,105 @@
+//! HD44780-compatible character LCD, on GPIOs or an I2C backpack.
+
+#[cfg(feature = "lcd-i2c")]
+pub use backpack::Backpack;
+#[cfg(not(feature = "lcd-i2c"))]
+pub use parallel::{DataBus, DisplayPins};
+
+use super::Panel;
+use super::screen::{self, Screen};
+
+#[cfg(feature = "lcd-i2c")]
+mod backpack;
+#[cfg(not(feature = "lcd-i2c"))]
+mod parallel;
+
+/// How instructions and data reach the controller.
+pub(super) trait Transport {
+    /// Writes `data` to the instruction register (`rs` low) or data
+    /// register, once the previous write has been carried out.
+    async fn write(&mut self, rs: bool, data: u8);
+
+    /// Function Set: 2 lines, 5x8 dots, in whichever bus width is wired.
+    async fn function_set(&mut self);
+
+    /// Switches the backlight, where the transport has a line for it.
+    async fn set_backlight(&mut self, _on: bool) {}
+}
+
+/// Clear Display and Return Home take 1.52 ms at the nominal 270 kHz, the
+/// rest 37 µs. Both are stretched for the slowest oscillator the datasheet
+/// allows, 190 kHz.
+#[cfg(any(feature = "lcd-rw-grounded", feature = "lcd-i2c"))]
+const CLEAR_HOME_US: u64 = 2160;
+#[cfg(any(feature = "lcd-rw-grounded", feature = "lcd-i2c"))]
+const EXECUTION_US: u64 = 53;
+
+/// How long the write of `data` keeps the controller busy, for transports
+/// that can't read the busy flag.
+#[cfg(any(feature = "lcd-rw-grounded", feature = "lcd-i2c"))]
+fn execution_us(rs: bool, data: u8) -> u64 {
+    match (rs, data) {
+        (false, 0b01..=0b11) => CLEAR_HOME_US,
+        _ => EXECUTION_US,
+    }
+}
+
+pub(super) struct Lcd<T> {
+    transport: T,
+}
+
+impl<T: Transport> Lcd<T> {
+    pub(super) fn new(transport: T) -> Self {
+        Lcd { transport }
+    }
+}
+
+impl<T: Transport> Panel for Lcd<T> {
+    async fn init(&mut self) {
+        self.transport.function_set().await;
+
+        // Display ON/OFF Control
+        self.transport.write(false, 0b0000_1100).await;
+
+        self.clear().await;
+
+        // Entry Mode Set
+        self.transport.write(false, 0b0000_0110).await;
+    }
+
+    async fn show(&mut self, shown: &Screen, screen: &Screen) {
+        for (address, bytes) in screen::diff(shown, screen) {
+            // Set DDRAM Address
+            self.transport.write(false, 0b1000_0000 | address).await;
+            for byte in bytes {
+                self.transport.write(true, *byte).await;
+            }
+        }
+    }
+
+    async fn define_glyph(&mut self, slot: u8, pattern: &[u8; 8]) {
+        // Set CGRAM Address
+        self.transport.write(false, 0b0100_0000 | slot << 3).await;
+        for row in pattern {
+            self.transport.write(true, *row).await;
+        }
+
+        // Set DDRAM Address, so data writes go to the screen again
+        self.transport.write(false, 0b1000_0000).await;
+    }
+
+    async fn clear(&mut self) {
+        // Display Clear
+        self.transport.write(false, 0b0000_0001).await;
+    }
+
+    async fn set_enabled(&mut self, on: bool) {
+        // Display ON/OFF Control
+        match on {
+            true => self.transport.write(false, 0b0000_1100).await,
+            false => self.transport.write(false, 0b0000_1000).await,
+        }
+
+        self.transport.set_backlight(on).await;
+    }
+}
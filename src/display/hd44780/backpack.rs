@@ -0,0 +1,84 @@
+//! HD44780 behind a PCF8574 I2C expander, as on the common 1602 backpacks:
+//! P0 RS, P1 RW, P2 E, P3 backlight, P4-P7 DB4-DB7.
+
+use ch32_hal::Peri;
+use ch32_hal::i2c::I2c;
+use ch32_hal::mode::Blocking;
+use ch32_hal::peripherals::{I2C1, PB6, PB7};
+use ch32_hal::time::Hertz;
+use embassy_time::Timer;
+
+use super::{Transport, execution_us};
+
+/// PCF8574 with A0-A2 left high, as shipped. A PCF8574A is at 0x3F.
+const ADDRESS: u8 = 0x27;
+
+/// The fastest the expander is rated for.
+const FREQUENCY: Hertz = Hertz::khz(100);
+
+const RS: u8 = 1 << 0;
+// RW on P1 stays low: polling the busy flag would cost a read transaction
+// per poll, longer than the wait it saves
+const ENABLE: u8 = 1 << 2;
+const BACKLIGHT: u8 = 1 << 3;
+
+pub struct Backpack {
+    i2c: I2c<'static, I2C1, Blocking>,
+    /// `BACKLIGHT` or 0, kept in every port write.
+    backlight: u8,
+}
+
+impl Backpack {
+    pub fn new(i2c: Peri<'static, I2C1>, scl: Peri<'static, PB6>, sda: Peri<'static, PB7>) -> Self {
+        Backpack {
+            i2c: I2c::new_blocking(i2c, scl, sda, FREQUENCY, Default::default()),
+            backlight: BACKLIGHT,
+        }
+    }
+
+    /// Clocks in up to two `nibbles` in one transaction, each set on
+    /// DB4-DB7 with E high, then again with E low. A port write takes
+    /// about 90 µs on the bus, far longer than E has to be held.
+    fn send_nibbles(&mut self, rs: bool, nibbles: &[u8]) {
+        let control = self.backlight | if rs { RS } else { 0 };
+
+        let mut bytes = [0; 4];
+        for (pair, nibble) in bytes.chunks_exact_mut(2).zip(nibbles) {
+            let port = control | nibble << 4;
+            pair[0] = port | ENABLE;
+            pair[1] = port;
+        }
+
+        // Without a backpack the writes NACK, and there is nothing to show
+        let _ = self
+            .i2c
+            .blocking_write(ADDRESS, &bytes[..nibbles.len() * 2]);
+    }
+}
+
+impl Transport for Backpack {
+    async fn write(&mut self, rs: bool, data: u8) {
+        self.send_nibbles(rs, &[data >> 4, data & 0x0F]);
+        Timer::after_micros(execution_us(rs, data)).await;
+    }
+
+    async fn function_set(&mut self) {
+        // Into 8-bit mode from any state and then to 4-bit, as on a 4-bit
+        // parallel bus
+        for delay_us in [4100, 100, 100] {
+            self.send_nibbles(false, &[0b0011]);
+            Timer::after_micros(delay_us).await;
+        }
+
+        self.send_nibbles(false, &[0b0010]);
+
+        self.write(false, 0b0010_1000).await;
+    }
+
+    async fn set_backlight(&mut self, on: bool) {
+        self.backlight = if on { BACKLIGHT } else { 0 };
+
+        // E stays low, so the controller doesn't see this
+        let _ = self.i2c.blocking_write(ADDRESS, &[self.backlight]);
+    }
+}
@@ -0,0 +1,224 @@
+//! HD44780 on GPIOs: RS, RW and E, and an 8- or 4-bit data bus.
+
+use ch32_hal::Peri;
+#[cfg(not(feature = "lcd-rw-grounded"))]
+use ch32_hal::gpio::Pull;
+use ch32_hal::gpio::{AnyPin, Flex, Level, Output};
+use embassy_time::Timer;
+#[cfg(not(feature = "lcd-rw-grounded"))]
+use embassy_time::{Duration, Instant};
+
+use super::Transport;
+#[cfg(feature = "lcd-rw-grounded")]
+use super::execution_us;
+
+#[inline]
+fn bool_to_level(b: bool) -> Level {
+    match b {
+        true => Level::High,
+        false => Level::Low,
+    }
+}
+
+fn u8_to_level(v: u8) -> Level {
+    match v {
+        0 => Level::Low,
+        _ => Level::High,
+    }
+}
+
+/// Data lines of the LCD, DB0 first. Outputs, except while the busy flag
+/// is read back.
+pub enum DataBus {
+    Eight([Flex<'static>; 8]),
+    /// DB4-DB7 only, each byte sent as two nibbles. DB0-DB3 are left
+    /// unconnected.
+    Four([Flex<'static>; 4]),
+}
+
+fn data_line(pin: Peri<'static, AnyPin>) -> Flex<'static> {
+    let mut line = Flex::new(pin);
+    line.set_low();
+    line.set_as_output(Default::default());
+    line
+}
+
+impl DataBus {
+    pub fn eight(pins: [Peri<'static, AnyPin>; 8]) -> Self {
+        DataBus::Eight(pins.map(data_line))
+    }
+
+    /// `pins` are DB4-DB7.
+    pub fn four(pins: [Peri<'static, AnyPin>; 4]) -> Self {
+        DataBus::Four(pins.map(data_line))
+    }
+
+    #[cfg(not(feature = "lcd-rw-grounded"))]
+    fn lines(&mut self) -> &mut [Flex<'static>] {
+        match self {
+            DataBus::Eight(lines) => lines,
+            DataBus::Four(lines) => lines,
+        }
+    }
+}
+
+/// RW is low except while the busy flag is read.
+pub struct DisplayPins {
+    rs: Output<'static>,
+    #[cfg(not(feature = "lcd-rw-grounded"))]
+    rw: Output<'static>,
+    enable: Output<'static>,
+    data: DataBus,
+}
+
+impl DisplayPins {
+    #[cfg(not(feature = "lcd-rw-grounded"))]
+    pub fn new(
+        rs: Peri<'static, AnyPin>,
+        rw: Peri<'static, AnyPin>,
+        enable: Peri<'static, AnyPin>,
+        data: DataBus,
+    ) -> Self {
+        DisplayPins {
+            rs: Output::new(rs, Level::Low, Default::default()),
+            rw: Output::new(rw, Level::Low, Default::default()),
+            enable: Output::new(enable, Level::Low, Default::default()),
+            data,
+        }
+    }
+
+    /// RW is tied to ground, leaving its pin free.
+    #[cfg(feature = "lcd-rw-grounded")]
+    pub fn new(rs: Peri<'static, AnyPin>, enable: Peri<'static, AnyPin>, data: DataBus) -> Self {
+        DisplayPins {
+            rs: Output::new(rs, Level::Low, Default::default()),
+            enable: Output::new(enable, Level::Low, Default::default()),
+            data,
+        }
+    }
+}
+
+fn set_data_lines(lines: &mut [Flex<'static>], data: u8) {
+    for (i, line) in lines.iter_mut().enumerate() {
+        line.set_level(u8_to_level(data & (1 << i)));
+    }
+}
+
+/// Bus timing in ns, for the slower 3 V grade: address setup before E
+/// rises, E high time (also how long until read data is valid), and E low
+/// time to the end of the 1000 ns cycle. All far below the embassy-time
+/// tick, so they are spun on the core clock.
+const ADDRESS_SETUP_NS: u32 = 60;
+const ENABLE_HIGH_NS: u32 = 450;
+const ENABLE_LOW_NS: u32 = 550;
+
+/// Fastest core clock of the part; spins are counted against it so they
+/// are never too short.
+const MAX_CORE_MHZ: u32 = 144;
+
+fn spin_ns(ns: u32) {
+    qingke::riscv::asm::delay(ns * MAX_CORE_MHZ / 1000);
+}
+
+fn strobe(enable: &mut Output<'static>) {
+    spin_ns(ADDRESS_SETUP_NS);
+    enable.set_high();
+
+    spin_ns(ENABLE_HIGH_NS);
+    enable.set_low();
+    spin_ns(ENABLE_LOW_NS);
+}
+
+/// Longer than any instruction takes, so a missing display, whose floating
+/// DB7 may read busy forever, doesn't stall the task.
+#[cfg(not(feature = "lcd-rw-grounded"))]
+const BUSY_TIMEOUT: Duration = Duration::from_millis(3);
+
+/// Reads the busy flag until the previous instruction has finished.
+#[cfg(not(feature = "lcd-rw-grounded"))]
+async fn wait_until_ready(pins: &mut DisplayPins) {
+    pins.rs.set_low();
+    pins.rw.set_high();
+    for line in pins.data.lines() {
+        line.set_as_input(Pull::Down);
+    }
+
+    let deadline = Instant::now() + BUSY_TIMEOUT;
+    loop {
+        // BF is DB7 of the first (or only) transfer, valid while E is high
+        spin_ns(ADDRESS_SETUP_NS);
+        pins.enable.set_high();
+        spin_ns(ENABLE_HIGH_NS);
+        let busy = match &pins.data {
+            DataBus::Eight(lines) => lines[7].is_high(),
+            DataBus::Four(lines) => lines[3].is_high(),
+        };
+        pins.enable.set_low();
+        spin_ns(ENABLE_LOW_NS);
+
+        // The address counter's low nibble has to be clocked out too
+        if let DataBus::Four(_) = pins.data {
+            strobe(&mut pins.enable);
+        }
+
+        if !busy || Instant::now() > deadline {
+            break;
+        }
+
+        // Most instructions are done within 40 µs; let other tasks run
+        // through the slow ones
+        embassy_futures::yield_now().await;
+    }
+
+    pins.rw.set_low();
+    for line in pins.data.lines() {
+        line.set_as_output(Default::default());
+    }
+}
+
+impl Transport for DisplayPins {
+    async fn write(&mut self, rs: bool, data: u8) {
+        #[cfg(not(feature = "lcd-rw-grounded"))]
+        wait_until_ready(self).await;
+
+        self.rs.set_level(bool_to_level(rs));
+
+        match &mut self.data {
+            DataBus::Eight(lines) => {
+                set_data_lines(lines, data);
+                strobe(&mut self.enable);
+            }
+            DataBus::Four(lines) => {
+                for nibble in [data >> 4, data & 0x0F] {
+                    set_data_lines(lines, nibble);
+                    strobe(&mut self.enable);
+                }
+            }
+        }
+
+        #[cfg(feature = "lcd-rw-grounded")]
+        Timer::after_micros(execution_us(rs, data)).await;
+    }
+
+    async fn function_set(&mut self) {
+        let DataBus::Four(lines) = &mut self.data else {
+            self.write(false, 0b0011_1000).await;
+            return;
+        };
+
+        // The controller may be in either mode after power-up, or halfway
+        // through a byte after a reset. Three 8-bit Function Sets bring it into
+        // 8-bit mode from any of these, then a lone 0x2 nibble switches to 4-bit
+        self.rs.set_low();
+        for delay_us in [4100, 100, 100] {
+            set_data_lines(lines, 0b0011);
+            strobe(&mut self.enable);
+            Timer::after_micros(delay_us).await;
+        }
+
+        set_data_lines(lines, 0b0010);
+        strobe(&mut self.enable);
+
+        self.write(false, 0b0010_1000).await;
+    }
+}
@@ -0,0 +1,822 @@
+//! The date, stats, signal, setting and setup pages, and the pieces the time page
+//! shares with them, drawn from plain values. Hardware-free, in the lib;
+//! the display module picks the page, the strings and what goes into them.
+//! On an 8-column module each page has a narrow layout of its own, where
+//! something on the right is left out rather than let run into the left.
+
+use core::fmt;
+
+use super::screen::{self, Screen};
+use crate::decoder::BitWidth;
+use crate::temperature::conversion::Celsius;
+use crate::timebase::{self, WallClock};
+
+// The pages other than the time page, by line
+pub const TOP_AT: (usize, usize) = (0, 0);
+pub const TOP_END: (usize, usize) = (0, screen::COLUMNS);
+pub const BOTTOM_AT: (usize, usize) = (1, 0);
+/// Sync age or protocol name end just before this column.
+pub const RIGHT_END: (usize, usize) = (1, screen::COLUMNS);
+
+const SHOW_YEAR: bool = screen::COLUMNS >= 16;
+const NARROW: bool = screen::COLUMNS < 16;
+
+/// On a narrow stats page the temperature and the drift take turns on the
+/// bottom line, this long each.
+pub const TURN_MS: u64 = 3000;
+
+/// What the time and date pages say; the other pages are diagnostics and
+/// stay in English.
+pub struct Strings {
+    pub weekdays: [&'static str; 7],
+    /// Before the first sync, followed by the seconds received and then
+    /// `frame_length`.
+    pub receiving: &'static str,
+    pub frame_length: &'static str,
+    pub no_signal: &'static str,
+    pub no_date: &'static str,
+    /// Around the day of the year.
+    pub day_before: &'static str,
+    pub day_after: &'static str,
+    /// The language's own name, to choose it by.
+    pub language: &'static str,
+}
+
+pub const EN: Strings = Strings {
+    weekdays: ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"],
+    receiving: "Sync",
+    frame_length: "/60",
+    no_signal: "No signal",
+    no_date: "No date yet",
+    day_before: "Day ",
+    day_after: "",
+    language: "English",
+};
+
+// Each dakuten takes a cell of its own: ゲツ is three cells wide, the
+// most the weekday has
+pub const JA: Strings = Strings {
+    weekdays: ["ニチ", "ゲツ", "カ", "スイ", "モク", "キン", "ド"],
+    receiving: "ジュシンチュウ",
+    frame_length: "",
+    no_signal: "シンゴウナシ",
+    no_date: "ヒヅケナシ",
+    day_before: "",
+    day_after: "ニチメ",
+    language: "ニホンゴ",
+};
+
+/// The language of the time and date pages, one of the settings.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Language {
+    En,
+    /// Katakana, for modules with the A00 character ROM. The A02 ROM has
+    /// European characters there instead and the OLED font has no kana.
+    Ja,
+}
+
+impl Language {
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Language::En => &EN,
+            Language::Ja => &JA,
+        }
+    }
+
+    /// The other language, each tap choosing between them.
+    pub fn next(self) -> Self {
+        match self {
+            Language::En => Language::Ja,
+            Language::Ja => Language::En,
+        }
+    }
+}
+
+/// Writes `text` so that it ends just before `end`, unless that leaves no
+/// blank cell after column `after`, where what is to its left ends.
+pub fn write_end_after(
+    screen: &mut Screen,
+    line: usize,
+    after: usize,
+    end: usize,
+    text: impl fmt::Display + Copy,
+) {
+    if after < end.saturating_sub(screen::width(text)) {
+        screen.write_end(line, end, text);
+    }
+}
+
+/// Time since the last sync as "12m", "5h" or "3d".
+#[derive(Clone, Copy)]
+pub struct SyncAge(pub u64);
+
+impl fmt::Display for SyncAge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let minutes = self.0 / (60 * 1000);
+        let (value, unit) = match minutes {
+            m if m < 60 => (m, 'm'),
+            m if m < 24 * 60 => (m / 60, 'h'),
+            m => (m / (24 * 60), 'd'),
+        };
+
+        write!(f, "{value}{unit}")
+    }
+}
+
+/// Time since the last sync as "12m", "5h" or "3d", ending before `end`.
+pub fn put_sync_age(screen: &mut Screen, line: usize, end: usize, age_ms: u64) {
+    screen.write_end(line, end, SyncAge(age_ms));
+}
+
+/// "2025-05-03", or "05-03" without `SHOW_YEAR`. Returns the column after
+/// it.
+pub fn put_date(
+    screen: &mut Screen,
+    line: usize,
+    column: usize,
+    year: u16,
+    month: u8,
+    day: u8,
+) -> usize {
+    match SHOW_YEAR {
+        true => screen.write(line, column, format_args!("{year:04}-{month:02}-{day:02}")),
+        false => screen.write(line, column, format_args!("{month:02}-{day:02}")),
+    }
+}
+
+/// "2025-05-03   SAT" over "Day 123", for the date shown. Narrow, the
+/// year goes to the bottom line instead of the day, after the weekday:
+/// "05-03" over "SAT 2025".
+pub fn date_page(screen: &mut Screen, strings: &Strings, date: Option<WallClock>) {
+    let Some(date) = date else {
+        let (line, column) = TOP_AT;
+        screen.write(line, column, strings.no_date);
+        return;
+    };
+
+    let (line, column) = TOP_AT;
+    let after = put_date(screen, line, column, date.year, date.month, date.day);
+
+    if NARROW {
+        let (line, column) = BOTTOM_AT;
+        let after = match date.weekday {
+            Some(weekday) => screen.write(line, column, strings.weekdays[weekday as usize]),
+            None => column,
+        };
+
+        let (line, end) = RIGHT_END;
+        write_end_after(screen, line, after, end, format_args!("{:04}", date.year));
+        return;
+    }
+
+    if let Some(weekday) = date.weekday {
+        let (line, end) = TOP_END;
+        write_end_after(screen, line, after, end, strings.weekdays[weekday as usize]);
+    }
+
+    if let Some(day) = timebase::day_of_year(date.year, date.month, date.day) {
+        let (line, column) = BOTTOM_AT;
+        let (before, after) = (strings.day_before, strings.day_after);
+        screen.write(line, column, format_args!("{before}{day}{after}"));
+    }
+}
+
+/// "OK 42 PAR 3  12m" on `line`: decodes and parity failures, and the age
+/// of the last sync if there was one. Narrow, just "42/3 12m".
+pub fn stats_line(
+    screen: &mut Screen,
+    line: usize,
+    decodes: u32,
+    parity_errors: u32,
+    sync_age_ms: Option<u64>,
+) {
+    let after = match NARROW {
+        true => screen.write(line, 0, format_args!("{decodes}/{parity_errors}")),
+        false => screen.write(line, 0, format_args!("OK {decodes} PAR {parity_errors}")),
+    };
+
+    match sync_age_ms {
+        Some(age) => write_end_after(screen, line, after, screen::COLUMNS, SyncAge(age)),
+        None => write_end_after(screen, line, after, screen::COLUMNS, "-"),
+    }
+}
+
+/// "OK 42 PAR 3  12m" over "27.5°C    +5ppm": `stats_line`, then the die
+/// temperature in tenths of °C and the oscillator drift measured, once
+/// there are any. Narrow, the temperature and the drift take turns on the
+/// bottom line, changing every `TURN_MS` of `now_ms`.
+pub fn stats_page(
+    screen: &mut Screen,
+    decodes: u32,
+    parity_errors: u32,
+    sync_age_ms: Option<u64>,
+    temperature: Option<i16>,
+    drift_ppm: Option<i32>,
+    now_ms: u64,
+) {
+    let (line, _) = TOP_AT;
+    stats_line(screen, line, decodes, parity_errors, sync_age_ms);
+
+    let drift_turn = now_ms / TURN_MS % 2 == 1;
+
+    let (line, column) = BOTTOM_AT;
+    let after = match (temperature, NARROW && drift_turn) {
+        (_, true) => column,
+        (Some(tenths), false) => screen.write(line, column, format_args!("{}°C", Celsius(tenths))),
+        (None, false) => screen.write(line, column, "--°C"),
+    };
+
+    if NARROW && !drift_turn {
+        return;
+    }
+
+    let (line, end) = RIGHT_END;
+    match drift_ppm {
+        Some(ppm) => write_end_after(screen, line, after, end, format_args!("{ppm:+}ppm")),
+        None => write_end_after(screen, line, after, end, "-ppm"),
+    };
+}
+
+/// What a pulse showed up as on the signal page.
+fn symbol(bit: BitWidth, value: Option<bool>) -> char {
+    match (bit, value) {
+        (_, Some(true)) => '1',
+        (_, Some(false)) => '0',
+        (BitWidth::Marker, None) => 'M',
+        _ => '?',
+    }
+}
+
+/// `pulses`, oldest first, on `line`, scrolling in from the right and cut
+/// off at the left on a narrow module.
+pub fn pulses_line(screen: &mut Screen, line: usize, pulses: &[Option<(BitWidth, Option<bool>)>]) {
+    let pulses = pulses.iter().rev().take(screen::COLUMNS);
+    for (i, pulse) in pulses.enumerate() {
+        let symbol = pulse.map_or(' ', |(bit, value)| symbol(bit, value));
+        screen.write(line, screen::COLUMNS - 1 - i, symbol);
+    }
+}
+
+/// The latest pulses scrolling in from the right, over the frame position
+/// and the station: "07/60      JJY40", or "07 JJY40" narrow.
+pub fn signal_page(
+    screen: &mut Screen,
+    pulses: &[Option<(BitWidth, Option<bool>)>],
+    cursor: u8,
+    protocol: &str,
+) {
+    let (line, _) = TOP_AT;
+    pulses_line(screen, line, pulses);
+
+    let (line, column) = BOTTOM_AT;
+    let after = match NARROW {
+        true => screen.write(line, column, format_args!("{cursor:02}")),
+        false => screen.write(line, column, format_args!("{cursor:02}/60")),
+    };
+
+    let (line, end) = RIGHT_END;
+    write_end_after(screen, line, after, end, protocol);
+}
+
+/// The part of a time being entered that is being edited on the setting
+/// page.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Editing {
+    /// Choosing between the clock and the alarm.
+    Target,
+    Hour,
+    Minute,
+    /// Alarm on or off.
+    Enabled,
+}
+
+/// "Set clock" or "Set alarm" to choose, then "Set 12:34" or
+/// "Alarm 07:00 on", over what the presses do. `alarm` is whether the
+/// alarm is on while it is the one being set, `None` for the clock; the
+/// part being edited is left out while `hidden`, for it to blink. Narrow,
+/// "clock?" to choose, then "12:34" or "07:00 on", over what a tap does on
+/// the left and a hold on the right.
+pub fn setting_page(
+    screen: &mut Screen,
+    editing: Editing,
+    alarm: Option<bool>,
+    hour: u8,
+    minute: u8,
+    hidden: bool,
+) {
+    let (line, mut column) = TOP_AT;
+
+    if editing == Editing::Target {
+        if !NARROW {
+            column = screen.write(line, column, "Set ");
+        }
+        if !hidden {
+            let target = match alarm {
+                None => "clock",
+                Some(_) => "alarm",
+            };
+            screen.write(line, column, target);
+        }
+        if NARROW {
+            screen.write(line, column + 5, "?");
+        }
+
+        let (line, column) = BOTTOM_AT;
+        match NARROW {
+            true => {
+                screen.write(line, column, "next");
+                screen.write_end(line, screen::COLUMNS, "ok");
+            }
+            false => {
+                screen.write(line, column, "Tap next Hold ok");
+            }
+        }
+        return;
+    }
+
+    if !NARROW {
+        let label = match alarm {
+            None => "Set ",
+            Some(_) => "Alarm ",
+        };
+        column = screen.write(line, column, label);
+    }
+    column = match (editing, hidden) {
+        (Editing::Hour, true) => screen.write(line, column, "  "),
+        _ => screen.write(line, column, format_args!("{hour:02}")),
+    };
+    column = screen.write(line, column, ":");
+    column = match (editing, hidden) {
+        (Editing::Minute, true) => screen.write(line, column, "  "),
+        _ => screen.write(line, column, format_args!("{minute:02}")),
+    };
+
+    if let Some(enabled) = alarm
+        && !(editing == Editing::Enabled && hidden)
+    {
+        let enabled = if enabled { "on" } else { "off" };
+        if NARROW {
+            screen.write_end(line, screen::COLUMNS, enabled);
+        } else {
+            screen.write(line, column, format_args!(" {enabled}"));
+        }
+    }
+
+    let (line, column) = BOTTOM_AT;
+    match NARROW {
+        true => {
+            screen.write(line, column, "+1");
+            screen.write_end(line, screen::COLUMNS, "ok");
+        }
+        false => {
+            screen.write(line, column, "Tap +1  Hold ok");
+        }
+    }
+}
+
+/// What a step of the first-boot setup shows.
+#[derive(Clone, Copy, Debug)]
+pub enum SetupPage<'a> {
+    Language(Language),
+    /// Minutes east of UTC.
+    Zone(i32),
+    TwelveHour(bool),
+    /// The latest pulses, to aim the antenna by.
+    Antenna(&'a [Option<(BitWidth, Option<bool>)>]),
+    /// Seconds received of the frame in progress, until the first sync.
+    FirstSync {
+        progress: Option<u8>,
+        synced: bool,
+    },
+}
+
+/// What a tap and a hold do, on the bottom line: "Tap next  Hold ok", or
+/// the two ends alone narrow. An empty `tap` does nothing.
+fn put_hint(screen: &mut Screen, tap: &str, hold: &str) {
+    let (line, column) = BOTTOM_AT;
+    let (_, end) = RIGHT_END;
+    match (NARROW, tap.is_empty()) {
+        (true, _) | (false, true) => screen.write(line, column, tap),
+        (false, false) => screen.write(line, column, format_args!("Tap {tap}")),
+    };
+    match NARROW {
+        true => screen.write_end(line, end, hold),
+        false => screen.write_end(line, end, format_args!("Hold {hold}")),
+    }
+}
+
+/// A step of the first-boot setup: the choice on top, over what the
+/// presses do. Each language is named in itself, "UTC+09:00" is just
+/// "+09:00" narrow, and the first sync counts the seconds as the time page
+/// does before it, in `strings`, until it can be held to finish rather
+/// than to skip.
+pub fn setup_page(screen: &mut Screen, strings: &Strings, page: SetupPage) {
+    let (line, column) = TOP_AT;
+
+    match page {
+        SetupPage::Language(language) => {
+            screen.write(line, column, language.strings().language);
+            put_hint(screen, "next", "ok");
+        }
+        SetupPage::Zone(minutes) => {
+            let (sign, hours, minutes) = timebase::zone_parts(minutes);
+            let sign = sign as char;
+            let label = if NARROW { "" } else { "Zone UTC" };
+            screen.write(
+                line,
+                column,
+                format_args!("{label}{sign}{hours:02}:{minutes:02}"),
+            );
+            put_hint(screen, "+30m", "ok");
+        }
+        SetupPage::TwelveHour(twelve_hour) => {
+            let hours = if twelve_hour { "12" } else { "24" };
+            screen.write(line, column, format_args!("{hours}-hour"));
+            put_hint(screen, "next", "ok");
+        }
+        SetupPage::Antenna(pulses) => {
+            pulses_line(screen, line, pulses);
+            let aim = if NARROW { "aim" } else { "Aim it" };
+            let (line, column) = BOTTOM_AT;
+            screen.write(line, column, aim);
+            put_hint(screen, "", "ok");
+        }
+        SetupPage::FirstSync { synced: true, .. } => {
+            screen.write(line, column, "Synced");
+            put_hint(screen, "", "ok");
+        }
+        SetupPage::FirstSync { progress, .. } => {
+            // Narrow, without the "/60"
+            let label = strings.receiving;
+            let length = if NARROW { "" } else { strings.frame_length };
+            match progress {
+                Some(seconds) => {
+                    screen.write(line, column, format_args!("{label} {seconds:02}{length}"))
+                }
+                None => screen.write(line, column, format_args!("{label} --{length}")),
+            };
+            put_hint(screen, "", "skip");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The codes on `line`.
+    fn codes(screen: &Screen, line: usize) -> Vec<u8> {
+        (0..screen::COLUMNS)
+            .map(|column| screen.cell(line, column))
+            .collect()
+    }
+
+    /// `line` as text, for lines of ASCII only.
+    fn text(screen: &Screen, line: usize) -> String {
+        codes(screen, line).into_iter().map(char::from).collect()
+    }
+
+    /// The top two lines as text, which are all the pages draw on.
+    fn lines(screen: &Screen) -> [String; 2] {
+        [text(screen, 0), text(screen, 1)]
+    }
+
+    /// A line as wide as the module, `left` at its start and `right` at
+    /// its end.
+    fn row(left: &str, right: &str) -> String {
+        let gap = screen::COLUMNS - left.len() - right.len();
+        format!("{left}{}{right}", " ".repeat(gap))
+    }
+
+    /// Saturday 2025-05-03, day 123 of the year.
+    const DATE: WallClock = WallClock {
+        year: 2025,
+        month: 5,
+        day: 3,
+        weekday: Some(6),
+        hour: 12,
+        minute: 34,
+        second: 56,
+        millisecond: 0,
+    };
+
+    const MINUTE_MS: u64 = 60 * 1000;
+
+    #[test]
+    fn sync_age_picks_its_unit() {
+        for (age_ms, shown) in [
+            (0, "0m"),
+            (59 * MINUTE_MS, "59m"),
+            (60 * MINUTE_MS, "1h"),
+            (23 * 60 * MINUTE_MS + 59 * MINUTE_MS, "23h"),
+            (24 * 60 * MINUTE_MS, "1d"),
+            (400 * 24 * 60 * MINUTE_MS, "400d"),
+        ] {
+            let mut screen = Screen::blank();
+            put_sync_age(&mut screen, 0, screen::COLUMNS, age_ms);
+            assert_eq!(text(&screen, 0), row("", shown));
+        }
+    }
+
+    #[test]
+    fn right_aligned_text_keeps_clear_of_the_left() {
+        let mut screen = Screen::blank();
+        let after = screen.write(0, 0, "x".repeat(screen::COLUMNS - 3));
+        write_end_after(&mut screen, 0, after, screen::COLUMNS, "ab");
+        write_end_after(&mut screen, 1, after, screen::COLUMNS, "abc");
+        assert_eq!(
+            text(&screen, 0),
+            row(&"x".repeat(screen::COLUMNS - 3), "ab")
+        );
+        assert_eq!(text(&screen, 1), row("", ""));
+    }
+
+    #[test]
+    fn date_page_in_english() {
+        let mut screen = Screen::blank();
+        date_page(&mut screen, &EN, Some(DATE));
+        match NARROW {
+            true => assert_eq!(lines(&screen), [row("05-03", ""), row("SAT", "2025")]),
+            false => assert_eq!(
+                lines(&screen),
+                [row("2025-05-03", "SAT"), row("Day 123", "")]
+            ),
+        }
+
+        let mut screen = Screen::blank();
+        date_page(
+            &mut screen,
+            &EN,
+            Some(WallClock {
+                weekday: None,
+                ..DATE
+            }),
+        );
+        match NARROW {
+            true => assert_eq!(lines(&screen), [row("05-03", ""), row("", "2025")]),
+            false => assert_eq!(lines(&screen), [row("2025-05-03", ""), row("Day 123", "")]),
+        }
+
+        // Cut off after "No date" on 8 columns
+        let mut screen = Screen::blank();
+        date_page(&mut screen, &EN, None);
+        let no_date = if NARROW { "No date" } else { "No date yet" };
+        assert_eq!(lines(&screen), [row(no_date, ""), row("", "")]);
+    }
+
+    #[test]
+    fn date_page_in_katakana() {
+        const DO: [u8; 2] = [0xC4, 0xDE];
+        const GETSU: [u8; 3] = [0xB9, 0xDE, 0xC2];
+        const NICHIME: [u8; 3] = [0xC6, 0xC1, 0xD2];
+
+        let mut screen = Screen::blank();
+        date_page(&mut screen, &JA, Some(DATE));
+
+        // ド is ト and a dakuten, right-aligned; ニチメ after the day
+        let (mut top, mut bottom) = (row("", "").into_bytes(), row("", "").into_bytes());
+        match NARROW {
+            true => {
+                top[..5].copy_from_slice(b"05-03");
+                bottom[..2].copy_from_slice(&DO);
+                bottom[4..].copy_from_slice(b"2025");
+            }
+            false => {
+                top[..10].copy_from_slice(b"2025-05-03");
+                top[screen::COLUMNS - 2..].copy_from_slice(&DO);
+                bottom[..3].copy_from_slice(b"123");
+                bottom[3..6].copy_from_slice(&NICHIME);
+            }
+        }
+        assert_eq!(codes(&screen, 0), top);
+        assert_eq!(codes(&screen, 1), bottom);
+
+        // ゲツ, three cells, still clear of the date or the year
+        let mut screen = Screen::blank();
+        date_page(
+            &mut screen,
+            &JA,
+            Some(WallClock {
+                weekday: Some(1),
+                ..DATE
+            }),
+        );
+        match NARROW {
+            true => assert_eq!(codes(&screen, 1)[..3], GETSU),
+            false => assert_eq!(codes(&screen, 0)[screen::COLUMNS - 3..], GETSU),
+        }
+    }
+
+    #[test]
+    fn stats_page_shows_counters_temperature_and_drift() {
+        // 27.5°C, the degree sign being the ROM's
+        let celsius = |tenths: &str, end: &str| {
+            let mut line = row(&format!("{tenths} C"), end).into_bytes();
+            line[tenths.len()] = 0xDF;
+            line
+        };
+
+        let mut screen = Screen::blank();
+        stats_page(
+            &mut screen,
+            42,
+            3,
+            Some(12 * MINUTE_MS),
+            Some(275),
+            Some(5),
+            0,
+        );
+        match NARROW {
+            true => {
+                assert_eq!(text(&screen, 0), row("42/3", "12m"));
+                assert_eq!(codes(&screen, 1), celsius("27.5", ""));
+            }
+            false => {
+                assert_eq!(text(&screen, 0), row("OK 42 PAR 3", "12m"));
+                assert_eq!(codes(&screen, 1), celsius("27.5", "+5ppm"));
+            }
+        }
+
+        // Narrow, the drift's turn
+        let mut screen = Screen::blank();
+        stats_page(&mut screen, 42, 3, None, Some(275), Some(5), TURN_MS);
+        if NARROW {
+            assert_eq!(lines(&screen), [row("42/3", "-"), row("", "+5ppm")]);
+        }
+
+        let mut screen = Screen::blank();
+        stats_page(&mut screen, 0, 0, None, None, None, 0);
+        match NARROW {
+            true => {
+                assert_eq!(text(&screen, 0), row("0/0", "-"));
+                assert_eq!(codes(&screen, 1), celsius("--", ""));
+            }
+            false => {
+                assert_eq!(text(&screen, 0), row("OK 0 PAR 0", "-"));
+                assert_eq!(codes(&screen, 1), celsius("--", "-ppm"));
+            }
+        }
+
+        let mut screen = Screen::blank();
+        stats_page(&mut screen, 1, 0, None, Some(-35), Some(-12), 0);
+        assert_eq!(codes(&screen, 1)[..4], *b"-3.5");
+        stats_page(&mut screen, 1, 0, None, Some(-35), Some(-12), TURN_MS);
+        assert_eq!(codes(&screen, 1)[screen::COLUMNS - 6..], *b"-12ppm");
+    }
+
+    #[test]
+    fn counters_too_wide_leave_out_the_sync_age() {
+        // Up to the last two cells, or past the end
+        let decodes = if NARROW { 1234 } else { 1234567 };
+        let mut screen = Screen::blank();
+        stats_line(&mut screen, 0, decodes, 567, Some(5 * 24 * 60 * MINUTE_MS));
+        match NARROW {
+            true => assert_eq!(text(&screen, 0), row("1234/567", "")),
+            false => {
+                let counters = &"OK 1234567 PAR 567"[..screen::COLUMNS.min(18)];
+                assert_eq!(text(&screen, 0), row(counters, ""));
+            }
+        }
+    }
+
+    #[test]
+    fn signal_page_scrolls_pulses_in_from_the_right() {
+        let pulses = [
+            None,
+            Some((BitWidth::Marker, None)),
+            Some((BitWidth::Short, Some(true))),
+            Some((BitWidth::Long, Some(false))),
+            Some((BitWidth::Unknown, None)),
+        ];
+
+        let mut screen = Screen::blank();
+        signal_page(&mut screen, &pulses, 7, "JJY40");
+        match NARROW {
+            true => assert_eq!(lines(&screen), [row("", "M10?"), row("07", "JJY40")]),
+            false => assert_eq!(lines(&screen), [row("", "M10?"), row("07/60", "JJY40")]),
+        }
+
+        // Only the newest that fit
+        let many = [Some((BitWidth::Short, Some(true))); 24];
+        let mut screen = Screen::blank();
+        pulses_line(&mut screen, 0, &many);
+        assert_eq!(text(&screen, 0), "1".repeat(screen::COLUMNS));
+    }
+
+    #[test]
+    fn setting_page_blinks_the_target() {
+        let mut screen = Screen::blank();
+        setting_page(&mut screen, Editing::Target, Some(true), 7, 0, false);
+        match NARROW {
+            true => assert_eq!(lines(&screen), [row("alarm?", ""), row("next", "ok")]),
+            false => assert_eq!(
+                lines(&screen),
+                [row("Set alarm", ""), row("Tap next Hold ok", "")]
+            ),
+        }
+
+        let mut screen = Screen::blank();
+        setting_page(&mut screen, Editing::Target, None, 7, 0, true);
+        match NARROW {
+            true => assert_eq!(text(&screen, 0), row("     ?", "")),
+            false => assert_eq!(text(&screen, 0), row("Set", "")),
+        }
+    }
+
+    #[test]
+    fn setting_page_keeps_every_field_on_screen() {
+        let mut screen = Screen::blank();
+        setting_page(&mut screen, Editing::Minute, None, 12, 34, false);
+        match NARROW {
+            true => assert_eq!(lines(&screen), [row("12:34", ""), row("+1", "ok")]),
+            false => assert_eq!(
+                lines(&screen),
+                [row("Set 12:34", ""), row("Tap +1  Hold ok", "")]
+            ),
+        }
+
+        let mut screen = Screen::blank();
+        setting_page(&mut screen, Editing::Hour, None, 12, 34, true);
+        match NARROW {
+            true => assert_eq!(text(&screen, 0), row("  :34", "")),
+            false => assert_eq!(text(&screen, 0), row("Set   :34", "")),
+        }
+
+        for (enabled, shown) in [(true, "on"), (false, "off")] {
+            let mut screen = Screen::blank();
+            setting_page(&mut screen, Editing::Minute, Some(enabled), 7, 0, true);
+            match NARROW {
+                true => assert_eq!(text(&screen, 0), row("07:", shown)),
+                false => assert_eq!(text(&screen, 0), row(&format!("Alarm 07:   {shown}"), "")),
+            }
+
+            let mut screen = Screen::blank();
+            setting_page(&mut screen, Editing::Enabled, Some(enabled), 7, 0, true);
+            match NARROW {
+                true => assert_eq!(text(&screen, 0), row("07:00", "")),
+                false => assert_eq!(text(&screen, 0), row("Alarm 07:00", "")),
+            }
+        }
+    }
+
+    #[test]
+    fn setup_page_shows_each_choice() {
+        let mut screen = Screen::blank();
+        setup_page(&mut screen, &EN, SetupPage::Language(Language::En));
+        match NARROW {
+            true => assert_eq!(lines(&screen), [row("English", ""), row("next", "ok")]),
+            false => assert_eq!(
+                lines(&screen),
+                [row("English", ""), row("Tap next", "Hold ok")]
+            ),
+        }
+
+        // ニホンゴ, the dakuten a cell of its own
+        let mut screen = Screen::blank();
+        setup_page(&mut screen, &EN, SetupPage::Language(Language::Ja));
+        assert_eq!(codes(&screen, 0)[..5], [0xC6, 0xCE, 0xDD, 0xBA, 0xDE]);
+
+        let mut screen = Screen::blank();
+        setup_page(&mut screen, &EN, SetupPage::Zone(5 * 60 + 30));
+        match NARROW {
+            true => assert_eq!(lines(&screen), [row("+05:30", ""), row("+30m", "ok")]),
+            false => assert_eq!(
+                lines(&screen),
+                [row("Zone UTC+05:30", ""), row("Tap +30m", "Hold ok")]
+            ),
+        }
+
+        let mut screen = Screen::blank();
+        setup_page(&mut screen, &EN, SetupPage::TwelveHour(true));
+        assert_eq!(text(&screen, 0), row("12-hour", ""));
+    }
+
+    #[test]
+    fn setup_page_aims_the_antenna_and_waits_for_the_sync() {
+        let pulses = [
+            Some((BitWidth::Marker, None)),
+            Some((BitWidth::Short, Some(true))),
+        ];
+        let mut screen = Screen::blank();
+        setup_page(&mut screen, &EN, SetupPage::Antenna(&pulses));
+        match NARROW {
+            true => assert_eq!(lines(&screen), [row("", "M1"), row("aim", "ok")]),
+            false => assert_eq!(lines(&screen), [row("", "M1"), row("Aim it", "Hold ok")]),
+        }
+
+        let hold = |action: &str| match NARROW {
+            true => action.to_string(),
+            false => format!("Hold {action}"),
+        };
+        let length = if NARROW { "" } else { "/60" };
+        for (progress, synced, top, action) in [
+            (None, false, format!("Sync --{length}"), "skip"),
+            (Some(23), false, format!("Sync 23{length}"), "skip"),
+            (Some(23), true, "Synced".to_string(), "ok"),
+        ] {
+            let mut screen = Screen::blank();
+            setup_page(&mut screen, &EN, SetupPage::FirstSync { progress, synced });
+            assert_eq!(lines(&screen), [row(&top, ""), row("", &hold(action))]);
+        }
+    }
+}
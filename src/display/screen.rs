@@ -0,0 +1,384 @@
+//! What the LCD shows, as a buffer of character codes, text formatted
+//! into it, and the DDRAM writes that turn one buffer into another.
+//! Katakana is encoded for the A00 ROM common on modules sold in Japan.
+//! Hardware-free, in the lib.
+
+use core::fmt::{self, Write};
+
+pub use super::geometry::{COLUMNS, LINE_ADDRESSES, LINES};
+
+const BLANK: u8 = b' ';
+
+/// Shown for a character the character ROM has no code for.
+const FALLBACK: u8 = b'?';
+
+/// Characters outside ASCII that the HD44780's A00 ROM has, and their
+/// codes. The OLED font has ASCII only.
+const EXTRA_CODES: [(char, u8); 7] = [
+    ('¥', 0x5C),
+    ('→', 0x7E),
+    ('←', 0x7F),
+    ('°', 0xDF),
+    ('µ', 0xE4),
+    ('Ω', 0xF4),
+    ('█', 0xFF),
+];
+
+/// The character code showing `c`. The ROM has ¥ and → where ASCII has
+/// `\` and `~`, so those fall back like anything else it lacks.
+fn code_for(c: char) -> u8 {
+    match c {
+        '\\' => FALLBACK,
+        ' '..='}' => c as u8,
+        _ => EXTRA_CODES
+            .iter()
+            .find(|(extra, _)| *extra == c)
+            .map_or(FALLBACK, |(_, code)| *code),
+    }
+}
+
+/// The A00 ROM's kana and punctuation from 0xA1 on, in JIS X 0201 order.
+const KATAKANA: &str = "。「」、・ヲァィゥェォャュョッー\
+                        アイウエオカキクケコサシスセソタチツテトナニヌネノ\
+                        ハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+
+const DAKUTEN: u8 = 0xDE;
+const HANDAKUTEN: u8 = 0xDF;
+
+/// Kana the ROM only has as the plain kana and a mark in the next cell.
+const MARKED_KANA: [(&str, &str, u8); 2] = [
+    (
+        "ガギグゲゴザジズゼゾダヂヅデドバビブベボヴ",
+        "カキクケコサシスセソタチツテトハヒフヘホウ",
+        DAKUTEN,
+    ),
+    ("パピプペポ", "ハヒフヘホ", HANDAKUTEN),
+];
+
+fn katakana_code(c: char) -> Option<u8> {
+    match c {
+        // Half-width forms are in the ROM's order already
+        '\u{FF61}'..='\u{FF9F}' => Some((c as u32 - 0xFF61) as u8 + 0xA1),
+        _ => KATAKANA
+            .chars()
+            .position(|k| k == c)
+            .map(|i| 0xA1 + i as u8),
+    }
+}
+
+/// The codes showing `c`, and how many there are: one, or two for a kana
+/// with a (han)dakuten.
+fn codes_for(c: char) -> ([u8; 2], usize) {
+    if let Some(code) = katakana_code(c) {
+        return ([code, 0], 1);
+    }
+
+    for (marked, plain, mark) in MARKED_KANA {
+        if let Some(i) = marked.chars().position(|m| m == c) {
+            let base = plain.chars().nth(i).and_then(katakana_code);
+            return ([base.unwrap_or(FALLBACK), mark], 2);
+        }
+    }
+
+    ([code_for(c), 0], 1)
+}
+
+/// Formatted text of a line at most, as character codes.
+struct Text {
+    codes: [u8; COLUMNS],
+    len: usize,
+}
+
+impl Text {
+    fn new(text: impl fmt::Display) -> Self {
+        let mut formatted = Text {
+            codes: [BLANK; COLUMNS],
+            len: 0,
+        };
+        let _ = write!(formatted, "{text}");
+        formatted
+    }
+
+    fn codes(&self) -> &[u8] {
+        &self.codes[..self.len]
+    }
+}
+
+impl Write for Text {
+    /// Anything past a line is cut off.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            let (codes, count) = codes_for(c);
+            for code in &codes[..count] {
+                if self.len < COLUMNS {
+                    self.codes[self.len] = *code;
+                    self.len += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The cells `text` takes, cut off at a line.
+pub fn width(text: impl fmt::Display) -> usize {
+    Text::new(text).len
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Screen {
+    cells: [[u8; COLUMNS]; LINES],
+}
+
+impl Screen {
+    pub const fn blank() -> Self {
+        Screen {
+            cells: [[BLANK; COLUMNS]; LINES],
+        }
+    }
+
+    /// Writes `bytes` from `column` of `line` on, cut off at the edge, and
+    /// returns the column after them.
+    pub fn put(&mut self, line: usize, column: usize, bytes: &[u8]) -> usize {
+        let cells = &mut self.cells[line];
+        for (cell, byte) in cells.iter_mut().skip(column).zip(bytes) {
+            *cell = *byte;
+        }
+
+        column + bytes.len()
+    }
+
+    /// Writes `bytes` so that they end just before column `end`.
+    pub fn put_end(&mut self, line: usize, end: usize, bytes: &[u8]) {
+        self.put(line, end.saturating_sub(bytes.len()), bytes);
+    }
+
+    /// Writes `text`, a string or `format_args!`, from `column` of `line`
+    /// on as `put` writes codes, and returns the column after it.
+    pub fn write(&mut self, line: usize, column: usize, text: impl fmt::Display) -> usize {
+        self.put(line, column, Text::new(text).codes())
+    }
+
+    /// Writes `text` so that it ends just before column `end`.
+    pub fn write_end(&mut self, line: usize, end: usize, text: impl fmt::Display) {
+        self.put_end(line, end, Text::new(text).codes());
+    }
+
+    #[cfg(any(test, feature = "oled"))]
+    pub fn cell(&self, line: usize, column: usize) -> u8 {
+        self.cells[line][column]
+    }
+}
+
+/// The writes, as DDRAM address and the bytes to write from there on,
+/// that bring a display showing `old` to `new`. Each is a run of changed
+/// cells within a line: joining two runs across an unchanged cell costs
+/// that cell's write, the same as the Set DDRAM Address it saves.
+pub fn diff<'a>(old: &'a Screen, new: &'a Screen) -> Diff<'a> {
+    Diff {
+        old,
+        new,
+        line: 0,
+        column: 0,
+    }
+}
+
+pub struct Diff<'a> {
+    old: &'a Screen,
+    new: &'a Screen,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Iterator for Diff<'a> {
+    type Item = (u8, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.line < LINES {
+            let old = &self.old.cells[self.line];
+            let new = &self.new.cells[self.line];
+
+            let Some(start) = (self.column..COLUMNS).find(|&c| old[c] != new[c]) else {
+                self.line += 1;
+                self.column = 0;
+                continue;
+            };
+
+            let end = (start..COLUMNS)
+                .find(|&c| old[c] == new[c])
+                .unwrap_or(COLUMNS);
+            self.column = end;
+
+            return Some((LINE_ADDRESSES[self.line] + start as u8, &new[start..end]));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A blank screen with `text` written at each line and column.
+    fn screen(texts: &[(usize, usize, &str)]) -> Screen {
+        let mut screen = Screen::blank();
+        for (line, column, text) in texts {
+            screen.write(*line, *column, text);
+        }
+        screen
+    }
+
+    /// The writes from `old` to `new`, as owned bytes.
+    fn writes(old: &Screen, new: &Screen) -> Vec<(u8, Vec<u8>)> {
+        diff(old, new)
+            .map(|(address, bytes)| (address, bytes.to_vec()))
+            .collect()
+    }
+
+    /// `old` with the writes from it to `new` applied, as the module would
+    /// show it.
+    fn apply(old: &Screen, new: &Screen) -> Screen {
+        let mut shown = *old;
+        for (address, bytes) in diff(old, new) {
+            let line = (0..LINES)
+                .find(|&l| (0..COLUMNS as u8).contains(&(address.wrapping_sub(LINE_ADDRESSES[l]))))
+                .unwrap();
+            let column = (address - LINE_ADDRESSES[line]) as usize;
+            assert!(column + bytes.len() <= COLUMNS, "{address:#x}");
+            shown.put(line, column, bytes);
+        }
+        shown
+    }
+
+    /// The codes `text` is written as.
+    fn codes(text: &str) -> Vec<u8> {
+        Text::new(text).codes().to_vec()
+    }
+
+    #[test]
+    fn ascii_passes_through() {
+        assert_eq!(codes("12:34:56"), b"12:34:56");
+        assert_eq!(codes("SAT -5"), b"SAT -5");
+        assert_eq!(codes("{|}"), b"{|}");
+    }
+
+    #[test]
+    fn rom_glyphs_replace_their_characters() {
+        assert_eq!(codes("27.5°C"), [b'2', b'7', b'.', b'5', 0xDF, b'C']);
+        assert_eq!(codes("¥→←µΩ█"), [0x5C, 0x7E, 0x7F, 0xE4, 0xF4, 0xFF]);
+    }
+
+    #[test]
+    fn unmappable_characters_fall_back() {
+        // The ROM has ¥ and → where ASCII has these
+        assert_eq!(codes("\\~"), b"??");
+        assert_eq!(codes("é漢\u{7}"), b"???");
+    }
+
+    #[test]
+    fn katakana_maps_to_the_a00_rom() {
+        assert_eq!(codes("アイウエオ"), [0xB1, 0xB2, 0xB3, 0xB4, 0xB5]);
+        assert_eq!(codes("ニチメ"), [0xC6, 0xC1, 0xD2]);
+        assert_eq!(codes("ヲァッーワン"), [0xA6, 0xA7, 0xAF, 0xB0, 0xDC, 0xDD]);
+        assert_eq!(codes("。「」、・"), [0xA1, 0xA2, 0xA3, 0xA4, 0xA5]);
+
+        // Half-width forms are the same codes
+        assert_eq!(codes("ｱﾆﾁﾒ｡ﾞﾟ"), [0xB1, 0xC6, 0xC1, 0xD2, 0xA1, 0xDE, 0xDF]);
+    }
+
+    #[test]
+    fn marked_kana_take_a_cell_for_the_mark() {
+        assert_eq!(codes("ド"), [0xC4, DAKUTEN]);
+        assert_eq!(codes("ゲツ"), [0xB9, DAKUTEN, 0xC2]);
+        assert_eq!(codes("ヴ"), [0xB3, DAKUTEN]);
+        assert_eq!(codes("パ"), [0xCA, HANDAKUTEN]);
+        assert_eq!(codes("ポ"), [0xCE, HANDAKUTEN]);
+        assert_eq!(width("ゲツ"), 3);
+    }
+
+    #[test]
+    fn text_is_cut_off_at_the_line_end() {
+        let long = "0123456789".repeat(3);
+        assert_eq!(codes(&long), long.as_bytes()[..COLUMNS]);
+
+        // A mark that doesn't fit is dropped with the rest
+        let kana = format!("{}ド", "x".repeat(COLUMNS - 1));
+        let codes = codes(&kana);
+        assert_eq!(codes.len(), COLUMNS);
+        assert_eq!(codes[COLUMNS - 1], 0xC4);
+    }
+
+    #[test]
+    fn writes_are_placed_and_cut_off() {
+        let mut screen = Screen::blank();
+        assert_eq!(screen.write(0, 1, "ab"), 3);
+        screen.write_end(1, COLUMNS, "yz");
+        screen.write(1, COLUMNS - 1, "cut");
+
+        assert_eq!(
+            (screen.cell(0, 0), screen.cell(0, 1), screen.cell(0, 2)),
+            (BLANK, b'a', b'b')
+        );
+        assert_eq!(screen.cell(1, COLUMNS - 2), b'y');
+        assert_eq!(screen.cell(1, COLUMNS - 1), b'c');
+    }
+
+    #[test]
+    fn unchanged_screen_writes_nothing() {
+        let shown = screen(&[(0, 0, "12:34"), (1, 0, "JJY")]);
+        assert!(diff(&shown, &shown).next().is_none());
+        assert!(diff(&Screen::blank(), &Screen::blank()).next().is_none());
+    }
+
+    #[test]
+    fn changed_cells_are_written_at_their_address() {
+        let old = screen(&[(0, 0, "12:34:56")]);
+
+        let new = screen(&[(0, 0, "12:34:57")]);
+        assert_eq!(writes(&old, &new), [(7, b"7".to_vec())]);
+
+        let new = screen(&[(0, 0, "12:35:00")]);
+        assert_eq!(
+            writes(&old, &new),
+            [(4, b"5".to_vec()), (6, b"00".to_vec())]
+        );
+
+        let new = screen(&[(0, 0, "12:34:56"), (1, 2, "ok")]);
+        assert_eq!(
+            writes(&old, &new),
+            [(LINE_ADDRESSES[1] + 2, b"ok".to_vec())]
+        );
+    }
+
+    #[test]
+    fn runs_end_at_the_line_end() {
+        let old = Screen::blank();
+        let mut new = Screen::blank();
+        for line in 0..LINES {
+            new.put(line, 0, &[b'#'; COLUMNS]);
+        }
+
+        let expected: Vec<_> = LINE_ADDRESSES
+            .iter()
+            .map(|address| (*address, vec![b'#'; COLUMNS]))
+            .collect();
+        assert_eq!(writes(&old, &new), expected);
+    }
+
+    #[test]
+    fn writes_bring_the_old_screen_to_the_new() {
+        let screens = [
+            Screen::blank(),
+            screen(&[(0, 0, "12:34:56"), (1, 0, "Sync")]),
+            screen(&[(0, 1, "2025-05-03"), (1, 3, "JJY  +3ppm")]),
+            screen(&[(0, 0, "a b c d"), (1, 0, " b c d ")]),
+        ];
+
+        for old in &screens {
+            for new in &screens {
+                assert_eq!(apply(old, new), *new);
+            }
+        }
+    }
+}
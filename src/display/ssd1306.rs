@@ -0,0 +1,197 @@
+//! 128x64 SSD1306 OLED on I2C, drawing the same 16x2 screen as the LCD:
+//! the clock in large digits on the top half, everything else in 5x7 text
+//! below. Only changed cells are written, each into its own pages.
+
+use ch32_hal::Peri;
+use ch32_hal::i2c::I2c;
+use ch32_hal::mode::Blocking;
+use ch32_hal::peripherals::{I2C1, PB6, PB7};
+use ch32_hal::time::Hertz;
+
+use super::screen::{COLUMNS, LINES, Screen};
+use super::{CLOCK_AT, Panel};
+
+mod font;
+
+/// With SA0 low, as on the common modules.
+const ADDRESS: u8 = 0x3C;
+
+const FREQUENCY: Hertz = Hertz::khz(400);
+
+/// Control byte ahead of a transaction of commands, or of display data.
+const COMMANDS: u8 = 0x00;
+const DATA: u8 = 0x40;
+
+const WIDTH: usize = 128;
+const PAGES: u8 = 8;
+
+/// Display off; clock, multiplex and offset for 128x64; charge pump on;
+/// page addressing; mirrored to the usual orientation; contrast, precharge
+/// and VCOMH as recommended for the internal charge pump.
+const INIT: [&[u8]; 14] = [
+    &[0xAE],
+    &[0xD5, 0x80],
+    &[0xA8, 0x3F],
+    &[0xD3, 0x00],
+    &[0x40],
+    &[0x8D, 0x14],
+    &[0x20, 0x02],
+    &[0xA1],
+    &[0xC8],
+    &[0xDA, 0x12],
+    &[0x81, 0xCF],
+    &[0xD9, 0xF1],
+    &[0xDB, 0x40],
+    &[0xA4, 0xA6],
+];
+
+const DISPLAY_OFF: u8 = 0xAE;
+const DISPLAY_ON: u8 = 0xAF;
+
+/// "HH:MM:SS", from `CLOCK_AT`.
+const CLOCK_CELLS: usize = 8;
+
+/// Clock cells: the font doubled across and quadrupled down, 2 columns
+/// apart, centred on pages 0-3.
+const LARGE_WIDTH: usize = 12;
+const LARGE_PAGES: u8 = 4;
+const LARGE_LEFT: usize = (WIDTH - CLOCK_CELLS * LARGE_WIDTH) / 2;
+
+/// Other cells: 16 to a line of 8 columns each, line 0 (right of the
+/// clock) on page 5 and line 1 on page 7.
+const SMALL_WIDTH: usize = WIDTH / COLUMNS;
+const SMALL_PAGES: [u8; LINES] = [5, 7];
+
+/// Longest display data write.
+const CHUNK: usize = 16;
+
+pub struct Oled {
+    i2c: I2c<'static, I2C1, Blocking>,
+    /// Character codes 0-7, as font columns.
+    glyphs: [[u8; 5]; 8],
+    /// Bit per glyph redefined since the last `show`, to be redrawn
+    /// wherever it is shown.
+    stale_glyphs: u8,
+}
+
+impl Oled {
+    pub fn new(i2c: Peri<'static, I2C1>, scl: Peri<'static, PB6>, sda: Peri<'static, PB7>) -> Self {
+        Oled {
+            i2c: I2c::new_blocking(i2c, scl, sda, FREQUENCY, Default::default()),
+            glyphs: [[0; 5]; 8],
+            stale_glyphs: 0,
+        }
+    }
+
+    fn send(&mut self, control: u8, bytes: &[u8]) {
+        let mut buffer = [0; 1 + CHUNK];
+        buffer[0] = control;
+        buffer[1..=bytes.len()].copy_from_slice(bytes);
+
+        // Without a module the writes NACK, and there is nothing to show
+        let _ = self.i2c.blocking_write(ADDRESS, &buffer[..=bytes.len()]);
+    }
+
+    /// Writes `bytes` into `page` from column `x` on.
+    fn write_page(&mut self, page: u8, x: usize, bytes: &[u8]) {
+        self.send(
+            COMMANDS,
+            &[0xB0 | page, x as u8 & 0x0F, 0x10 | (x >> 4) as u8],
+        );
+        for chunk in bytes.chunks(CHUNK) {
+            self.send(DATA, chunk);
+        }
+    }
+
+    fn columns(&self, code: u8) -> [u8; 5] {
+        match self.glyphs.get(code as usize) {
+            Some(glyph) => *glyph,
+            None => font::columns(code),
+        }
+    }
+
+    fn draw_cell(&mut self, line: usize, column: usize, code: u8) {
+        let columns = self.columns(code);
+
+        let clock = CLOCK_AT.1..CLOCK_AT.1 + CLOCK_CELLS;
+        if line == CLOCK_AT.0 && clock.contains(&column) {
+            let x = LARGE_LEFT + (column - CLOCK_AT.1) * LARGE_WIDTH;
+            let tall = columns.map(stretch);
+
+            for page in 0..LARGE_PAGES {
+                let mut bytes = [0; LARGE_WIDTH];
+                for (pair, column) in bytes.chunks_exact_mut(2).zip(tall) {
+                    pair.fill((column >> (8 * page)) as u8);
+                }
+                self.write_page(page, x, &bytes);
+            }
+        } else {
+            let mut bytes = [0; SMALL_WIDTH];
+            bytes[..5].copy_from_slice(&columns);
+            self.write_page(SMALL_PAGES[line], column * SMALL_WIDTH, &bytes);
+        }
+    }
+}
+
+/// A font column 4 times as tall, over 4 pages.
+fn stretch(column: u8) -> u32 {
+    (0..8)
+        .filter(|row| (column >> row) & 1 != 0)
+        .fold(0, |tall, row| tall | 0xF << (4 * row))
+}
+
+/// A 5x8 row-per-byte glyph, as LCD CGRAM takes it, turned into columns.
+fn to_columns(pattern: &[u8; 8]) -> [u8; 5] {
+    let mut columns = [0; 5];
+    for (row, bits) in pattern.iter().enumerate() {
+        for (i, column) in columns.iter_mut().enumerate() {
+            if (bits >> (4 - i)) & 1 != 0 {
+                *column |= 1 << row;
+            }
+        }
+    }
+    columns
+}
+
+impl Panel for Oled {
+    async fn init(&mut self) {
+        for command in INIT {
+            self.send(COMMANDS, command);
+        }
+
+        self.clear().await;
+        self.send(COMMANDS, &[DISPLAY_ON]);
+    }
+
+    async fn show(&mut self, shown: &Screen, screen: &Screen) {
+        let stale_glyphs = core::mem::take(&mut self.stale_glyphs);
+
+        for line in 0..LINES {
+            for column in 0..COLUMNS {
+                let code = screen.cell(line, column);
+                let stale = code < 8 && stale_glyphs & (1 << code) != 0;
+                if code != shown.cell(line, column) || stale {
+                    self.draw_cell(line, column, code);
+                }
+            }
+        }
+    }
+
+    async fn define_glyph(&mut self, slot: u8, pattern: &[u8; 8]) {
+        self.glyphs[slot as usize] = to_columns(pattern);
+        self.stale_glyphs |= 1 << slot;
+    }
+
+    async fn clear(&mut self) {
+        for page in 0..PAGES {
+            self.write_page(page, 0, &[0; WIDTH]);
+        }
+    }
+
+    async fn set_enabled(&mut self, on: bool) {
+        match on {
+            true => self.send(COMMANDS, &[DISPLAY_ON]),
+            false => self.send(COMMANDS, &[DISPLAY_OFF]),
+        }
+    }
+}
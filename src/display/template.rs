@@ -0,0 +1,244 @@
+//! The time page's bottom line laid out by a template from the settings,
+//! so a layout is a console command rather than a rebuild. `%` and a
+//! letter stand for a value, anything else for itself:
+//!
+//! - `%Y`, `%m`, `%d`: year, month and day, zero-padded
+//! - `%a`: weekday, in the language of the pages
+//! - `%H`, `%M`: hour and minute
+//! - `%j`: day of the year
+//! - `%q`: signal quality, 0 to `quality::MAX_SCORE`
+//! - `%s`: age of the last sync, as "12m", "5h" or "3d"
+//! - `%p`: the station
+//! - `%%`: a `%`
+//!
+//! Any other letter after a `%` is shown as it is. Hardware-free.
+
+use core::fmt::{self, Write};
+
+use super::pages::SyncAge;
+use crate::timebase::{self, WallClock};
+
+/// Characters a template can have, as wide as the widest module.
+pub const LENGTH: usize = 20;
+
+/// A template of printable ASCII, `LENGTH` at most. An empty one leaves
+/// the line to the built-in layout.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Template {
+    bytes: [u8; LENGTH],
+    len: u8,
+}
+
+impl Template {
+    pub const NONE: Template = Template {
+        bytes: [0; LENGTH],
+        len: 0,
+    };
+
+    /// `text` as a template, if it is one.
+    pub fn new(text: &str) -> Option<Self> {
+        let printable = text.bytes().all(|b| b.is_ascii_graphic() || b == b' ');
+        if text.len() > LENGTH || !printable {
+            return None;
+        }
+
+        let mut bytes = [0; LENGTH];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        Some(Template {
+            bytes,
+            len: text.len() as u8,
+        })
+    }
+
+    /// The template stored as `bytes`, padded out with zeros.
+    pub fn from_bytes(bytes: &[u8; LENGTH]) -> Option<Self> {
+        let len = bytes.iter().position(|b| *b == 0).unwrap_or(LENGTH);
+        let text = core::str::from_utf8(&bytes[..len]).ok()?;
+        // Nothing but zeros may follow the end
+        match bytes[len..].iter().all(|b| *b == 0) {
+            true => Template::new(text),
+            false => None,
+        }
+    }
+
+    /// The template padded out with zeros, as `from_bytes` takes it.
+    pub fn to_bytes(&self) -> [u8; LENGTH] {
+        self.bytes
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only ever made from a `str` of ASCII
+        core::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or("")
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// What the tokens of a template stand for.
+#[derive(Clone, Copy)]
+pub struct Fields<'a> {
+    pub clock: WallClock,
+    pub weekdays: &'a [&'static str; 7],
+    pub quality: u8,
+    pub sync_age_ms: Option<u64>,
+    pub protocol: &'a str,
+}
+
+/// A template filled in from `fields`, to write to the screen.
+#[derive(Clone, Copy)]
+pub struct Filled<'a> {
+    pub template: &'a Template,
+    pub fields: &'a Fields<'a>,
+}
+
+impl fmt::Display for Filled<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (fields, clock) = (self.fields, self.fields.clock);
+        let mut chars = self.template.as_str().chars();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                f.write_char(c)?;
+                continue;
+            }
+
+            match chars.next() {
+                Some('Y') => write!(f, "{:04}", clock.year)?,
+                Some('m') => write!(f, "{:02}", clock.month)?,
+                Some('d') => write!(f, "{:02}", clock.day)?,
+                Some('a') => {
+                    if let Some(weekday) = clock.weekday {
+                        f.write_str(fields.weekdays[weekday as usize])?;
+                    }
+                }
+                Some('H') => write!(f, "{:02}", clock.hour)?,
+                Some('M') => write!(f, "{:02}", clock.minute)?,
+                Some('j') => {
+                    let day = timebase::day_of_year(clock.year, clock.month, clock.day);
+                    write!(f, "{:03}", day.unwrap_or(0))?;
+                }
+                Some('q') => write!(f, "{}", fields.quality)?,
+                Some('s') => match fields.sync_age_ms {
+                    Some(age) => write!(f, "{}", SyncAge(age))?,
+                    None => f.write_char('-')?,
+                },
+                Some('p') => f.write_str(fields.protocol)?,
+                Some('%') => f.write_char('%')?,
+                Some(other) => {
+                    f.write_char('%')?;
+                    f.write_char(other)?;
+                }
+                None => f.write_char('%')?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::display::pages::{EN, JA};
+    use crate::display::screen::Screen;
+
+    const FIELDS: Fields = Fields {
+        clock: WallClock {
+            year: 2025,
+            month: 5,
+            day: 3,
+            weekday: Some(6),
+            hour: 7,
+            minute: 5,
+            second: 0,
+            millisecond: 0,
+        },
+        weekdays: &EN.weekdays,
+        quality: 4,
+        sync_age_ms: Some(12 * 60 * 1000),
+        protocol: "JJY40",
+    };
+
+    fn filled(template: &str, fields: &Fields) -> String {
+        let template = Template::new(template).unwrap();
+        Filled {
+            template: &template,
+            fields,
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn tokens_are_filled_in() {
+        for (template, shown) in [
+            ("%Y-%m-%d %a", "2025-05-03 SAT"),
+            ("%H:%M q%q", "07:05 q4"),
+            ("day %j %s", "day 123 12m"),
+            ("%p 100%%", "JJY40 100%"),
+            ("", ""),
+        ] {
+            assert_eq!(filled(template, &FIELDS), shown, "{template}");
+        }
+    }
+
+    #[test]
+    fn values_not_known_yet_are_left_out() {
+        let fields = Fields {
+            clock: WallClock {
+                weekday: None,
+                ..FIELDS.clock
+            },
+            sync_age_ms: None,
+            ..FIELDS
+        };
+        assert_eq!(filled("%a|%s", &fields), "|-");
+    }
+
+    #[test]
+    fn unknown_tokens_show_as_they_are() {
+        assert_eq!(filled("%x %", &FIELDS), "%x %");
+    }
+
+    #[test]
+    fn weekdays_follow_the_language() {
+        let fields = Fields {
+            weekdays: &JA.weekdays,
+            ..FIELDS
+        };
+        let template = Template::new("%a").unwrap();
+        let mut screen = Screen::blank();
+        let filled = Filled {
+            template: &template,
+            fields: &fields,
+        };
+        // ド, a ト and a dakuten
+        assert_eq!(screen.write(0, 0, filled), 2);
+        assert_eq!((screen.cell(0, 0), screen.cell(0, 1)), (0xC4, 0xDE));
+        assert_eq!(screen.cell(0, 2), b' ');
+    }
+
+    #[test]
+    fn templates_are_short_printable_ascii() {
+        assert!(Template::new(&"x".repeat(LENGTH)).is_some());
+        assert_eq!(Template::new(&"x".repeat(LENGTH + 1)), None);
+        assert_eq!(Template::new("tab\there"), None);
+        assert_eq!(Template::new("ド"), None);
+        assert!(Template::new("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn templates_survive_their_bytes() {
+        let template = Template::new("%Y-%m-%d %a").unwrap();
+        assert_eq!(Template::from_bytes(&template.to_bytes()), Some(template));
+        assert_eq!(Template::from_bytes(&[0; LENGTH]), Some(Template::NONE));
+
+        // Anything after the end, or not printable, is no template
+        let mut bytes = template.to_bytes();
+        bytes[LENGTH - 1] = b'x';
+        assert_eq!(Template::from_bytes(&bytes), None);
+        let mut bytes = [0; LENGTH];
+        bytes[0] = 0x7F;
+        assert_eq!(Template::from_bytes(&bytes), None);
+    }
+}
@@ -0,0 +1,209 @@
+use core::cell::Cell;
+
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+
+use crate::syslog::{self, Event};
+use crate::{backup, monotonic, timebase};
+
+/// Persistent faults, in order of severity. The discriminant is the number
+/// of blinks the status LED emits for it.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Fault {
+    /// No edge from the receiver for several seconds.
+    ReceiverSilent = 2,
+    /// Edges arrive but no frame has decoded for a long time.
+    NoValidFrame = 3,
+    /// Frames decoded once, but none for days: usually a knocked antenna.
+    SignalLost = 4,
+    /// The capture queue overflowed and edges were lost, until the next
+    /// valid frame.
+    EdgesDropped = 5,
+    /// The settings page failed to erase or program, until a write
+    /// succeeds.
+    FlashWrite = 6,
+}
+
+impl Fault {
+    const ALL: [Fault; 5] = [
+        Fault::ReceiverSilent,
+        Fault::NoValidFrame,
+        Fault::SignalLost,
+        Fault::EdgesDropped,
+        Fault::FlashWrite,
+    ];
+
+    fn index(self) -> usize {
+        Fault::ALL.iter().position(|f| *f == self).unwrap()
+    }
+
+    fn mask(self) -> u8 {
+        1 << self.index()
+    }
+
+    fn from_code(code: u16) -> Option<Fault> {
+        Fault::ALL.into_iter().find(|f| *f as u16 == code)
+    }
+
+    pub fn blinks(self) -> u8 {
+        self as u8
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Fault::ReceiverSilent => "ReceiverSilent",
+            Fault::NoValidFrame => "NoValidFrame",
+            Fault::SignalLost => "SignalLost",
+            Fault::EdgesDropped => "EdgesDropped",
+            Fault::FlashWrite => "FlashWrite",
+        }
+    }
+}
+
+/// Second of day recorded for faults raised before the first sync.
+pub const UNSYNCED: u32 = u32::MAX;
+
+/// What survives a reset: the last fault, when it happened, and how often
+/// each fault has occurred.
+#[derive(Clone, Copy, Debug)]
+pub struct Record {
+    pub last: Option<Fault>,
+    pub last_at: u32,
+    pub counts: [u8; Fault::ALL.len()],
+}
+
+impl Record {
+    const fn new() -> Self {
+        Record {
+            last: None,
+            last_at: UNSYNCED,
+            counts: [0; Fault::ALL.len()],
+        }
+    }
+
+    pub fn count(&self, fault: Fault) -> u8 {
+        self.counts[fault.index()]
+    }
+}
+
+// Backup register layout. The counts saturate at 255 and share a register
+// in pairs, the first in the low byte.
+const MAGIC: u16 = 0xFA18;
+const SLOT_MAGIC: usize = 0;
+const SLOT_LAST: usize = 1;
+const SLOT_LAST_AT_HIGH: usize = 2;
+const SLOT_LAST_AT_LOW: usize = 3;
+const SLOT_COUNTS: usize = 4;
+
+/// First backup register past the fault record.
+pub const SLOTS_END: usize = SLOT_COUNTS + Fault::ALL.len().div_ceil(2);
+
+fn load() -> Record {
+    if backup::read(SLOT_MAGIC) != MAGIC {
+        return Record::new();
+    }
+
+    let mut record = Record {
+        last: Fault::from_code(backup::read(SLOT_LAST)),
+        last_at: (backup::read(SLOT_LAST_AT_HIGH) as u32) << 16
+            | backup::read(SLOT_LAST_AT_LOW) as u32,
+        counts: [0; Fault::ALL.len()],
+    };
+
+    for (i, count) in record.counts.iter_mut().enumerate() {
+        *count = (backup::read(SLOT_COUNTS + i / 2) >> (i % 2 * 8)) as u8;
+    }
+
+    record
+}
+
+fn store(record: &Record) {
+    backup::write(SLOT_LAST, record.last.map_or(0, |f| f as u16));
+    backup::write(SLOT_LAST_AT_HIGH, (record.last_at >> 16) as u16);
+    backup::write(SLOT_LAST_AT_LOW, record.last_at as u16);
+
+    for (i, pair) in record.counts.chunks(2).enumerate() {
+        let high = pair.get(1).copied().unwrap_or(0);
+        backup::write(SLOT_COUNTS + i, (high as u16) << 8 | pair[0] as u16);
+    }
+
+    backup::write(SLOT_MAGIC, MAGIC);
+}
+
+#[derive(Clone, Copy)]
+struct Registry {
+    active: u8,
+    record: Record,
+}
+
+static REGISTRY: Mutex<CriticalSectionRawMutex, Cell<Registry>> = Mutex::new(Cell::new(Registry {
+    active: 0,
+    record: Record::new(),
+}));
+
+/// Restores the record left by the previous boot.
+pub fn init() -> Record {
+    backup::init();
+
+    let record = load();
+    REGISTRY.lock(|r| r.set(Registry { active: 0, record }));
+    record
+}
+
+/// Marks `fault` active. Only the transition from inactive is counted.
+pub fn raise(fault: Fault) {
+    let now = monotonic::now_ms();
+    let at = timebase::load().map_or(UNSYNCED, |b| b.second_of_day(now));
+
+    let raised = REGISTRY.lock(|r| {
+        let mut registry = r.get();
+        if registry.active & fault.mask() != 0 {
+            return false;
+        }
+
+        registry.active |= fault.mask();
+
+        let record = &mut registry.record;
+        record.last = Some(fault);
+        record.last_at = at;
+        record.counts[fault.index()] = record.counts[fault.index()].saturating_add(1);
+
+        store(record);
+        r.set(registry);
+        true
+    });
+
+    // Queued once the critical section is over: it holds off the capture
+    // interrupt, and the event waits for the log task anyway
+    if raised {
+        syslog::emit(Event::FaultRaised(fault));
+    }
+}
+
+pub fn clear(fault: Fault) {
+    let cleared = REGISTRY.lock(|r| {
+        let mut registry = r.get();
+        if registry.active & fault.mask() == 0 {
+            return false;
+        }
+
+        registry.active &= !fault.mask();
+        r.set(registry);
+        true
+    });
+
+    if cleared {
+        syslog::emit(Event::FaultCleared(fault));
+    }
+}
+
+pub fn is_active(fault: Fault) -> bool {
+    REGISTRY.lock(|r| r.get().active & fault.mask() != 0)
+}
+
+/// The most severe fault currently active.
+pub fn most_severe() -> Option<Fault> {
+    let active = REGISTRY.lock(|r| r.get().active);
+    Fault::ALL.into_iter().find(|f| active & f.mask() != 0)
+}
@@ -0,0 +1,84 @@
+use ch32_hal::pac;
+
+/// The last 4 KiB page of the 64 KiB flash. `memory.x` keeps the image
+/// below it.
+const PAGE_ADDRESS: usize = 0x0800_F000;
+
+const KEY1: u32 = 0x4567_0123;
+const KEY2: u32 = 0xCDEF_89AB;
+
+/// Half-word `index` of the page.
+pub fn read(index: usize) -> u16 {
+    // Flash reads like memory; the page is never executed from
+    unsafe { core::ptr::read_volatile((PAGE_ADDRESS as *const u16).add(index)) }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error {
+    /// The page is write protected.
+    Protected,
+    /// The erase or a program ended without signalling completion.
+    Incomplete,
+}
+
+impl Error {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Error::Protected => "write protected",
+            Error::Incomplete => "incomplete",
+        }
+    }
+}
+
+fn wait_idle() {
+    while pac::FLASH.statr().read().bsy() {}
+}
+
+/// Waits for the operation started last and reads how it ended, clearing
+/// the flags for the next one.
+fn finish() -> Result<(), Error> {
+    wait_idle();
+
+    let status = pac::FLASH.statr().read();
+    pac::FLASH.statr().write(|w| {
+        w.set_wrprterr(true);
+        w.set_eop(true);
+    });
+
+    match (status.wrprterr(), status.eop()) {
+        (true, _) => Err(Error::Protected),
+        (false, false) => Err(Error::Incomplete),
+        (false, true) => Ok(()),
+    }
+}
+
+fn erase_and_program(words: &[u16]) -> Result<(), Error> {
+    pac::FLASH.ctlr().modify(|w| w.set_per(true));
+    pac::FLASH.addr().write(|w| w.set_far(PAGE_ADDRESS as u32));
+    pac::FLASH.ctlr().modify(|w| w.set_strt(true));
+    let erased = finish();
+    pac::FLASH.ctlr().modify(|w| w.set_per(false));
+    erased?;
+
+    pac::FLASH.ctlr().modify(|w| w.set_pg(true));
+    let programmed = words.iter().enumerate().try_for_each(|(i, word)| {
+        unsafe { core::ptr::write_volatile((PAGE_ADDRESS as *mut u16).add(i), *word) };
+        finish()
+    });
+    pac::FLASH.ctlr().modify(|w| w.set_pg(false));
+    programmed
+}
+
+/// Erases the page and programs `words` from its start, stopping at the
+/// first step that fails. The core stalls until the flash is done, a few
+/// ms for the erase, interrupts included.
+pub fn write(words: &[u16]) -> Result<(), Error> {
+    pac::FLASH.keyr().write(|w| w.set_keyr(KEY1));
+    pac::FLASH.keyr().write(|w| w.set_keyr(KEY2));
+    wait_idle();
+
+    let written = erase_and_program(words);
+
+    pac::FLASH.ctlr().modify(|w| w.set_lock(true));
+    written
+}
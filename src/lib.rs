@@ -0,0 +1,47 @@
+//! The hardware-free part of the clock: decoding, timekeeping, and the
+//! logic behind the buttons, the console and the outputs. Built for the
+//! firmware and for the host alike, so the tests run with `cargo test-host`.
+
+#![cfg_attr(not(test), no_std)]
+// State is built by `const fn new` for statics; nothing wants `Default`
+#![allow(clippy::new_without_default)]
+
+// Provides the critical section the chip's HAL does in the firmware
+#[cfg(test)]
+use critical_section as _;
+
+pub mod alarm {
+    pub mod machine;
+}
+pub mod board {
+    pub mod strap;
+}
+pub mod button;
+pub mod console {
+    pub mod parse;
+}
+pub mod decoder;
+pub mod display {
+    pub mod geometry;
+    pub mod pages;
+    pub mod screen;
+    pub mod template;
+}
+pub mod monotonic {
+    pub mod clock;
+}
+pub mod nmea {
+    pub mod sentence;
+}
+pub mod pips {
+    pub mod mode;
+}
+pub mod quality;
+pub mod receiver {
+    pub mod cross_check;
+}
+pub mod temperature {
+    pub mod conversion;
+}
+pub mod timebase;
+pub mod wizard;
@@ -0,0 +1,106 @@
+//! Leveled logging: pulse widths at trace, frame results at debug, time
+//! updates and other events at info, faults at error.
+//!
+//! With the `defmt` feature, everything goes out over RTT, and `DEFMT_LOG`
+//! (`.cargo/config.toml`) sets the level at build time. Otherwise it is
+//! printed over SDI with a level letter, with info and above enabled unless
+//! `log-debug` or `log-trace` adds more. Either way, a disabled level is
+//! compiled out, arguments and formatting included.
+//!
+//! Format strings must suit both: positional arguments, `{}`, `{:?}` and
+//! zero padding as `{:02}`.
+
+use core::fmt::{self, Write};
+
+#[cfg(not(feature = "defmt"))]
+pub const TRACE: bool = cfg!(feature = "log-trace");
+#[cfg(not(feature = "defmt"))]
+pub const DEBUG: bool = TRACE || cfg!(feature = "log-debug");
+
+#[cfg(not(feature = "defmt"))]
+macro_rules! sdi {
+    ($enabled:expr, $level:literal, $fmt:literal $(, $arg:expr)* $(,)?) => {
+        if $enabled {
+            ch32_hal::println!(concat!($level, " ", $fmt) $(, $arg)*);
+        }
+    };
+}
+
+#[cfg(feature = "defmt")]
+macro_rules! trace {
+    ($($arg:tt)*) => { defmt::trace!($($arg)*) };
+}
+#[cfg(not(feature = "defmt"))]
+macro_rules! trace {
+    ($($arg:tt)*) => { $crate::log::sdi!($crate::log::TRACE, "T", $($arg)*) };
+}
+
+#[cfg(feature = "defmt")]
+macro_rules! debug {
+    ($($arg:tt)*) => { defmt::debug!($($arg)*) };
+}
+#[cfg(not(feature = "defmt"))]
+macro_rules! debug {
+    ($($arg:tt)*) => { $crate::log::sdi!($crate::log::DEBUG, "D", $($arg)*) };
+}
+
+#[cfg(feature = "defmt")]
+macro_rules! info {
+    ($($arg:tt)*) => { defmt::info!($($arg)*) };
+}
+#[cfg(not(feature = "defmt"))]
+macro_rules! info {
+    ($($arg:tt)*) => { $crate::log::sdi!(true, "I", $($arg)*) };
+}
+
+#[cfg(feature = "defmt")]
+macro_rules! error {
+    ($($arg:tt)*) => { defmt::error!($($arg)*) };
+}
+#[cfg(not(feature = "defmt"))]
+macro_rules! error {
+    ($($arg:tt)*) => { $crate::log::sdi!(true, "E", $($arg)*) };
+}
+
+#[cfg(not(feature = "defmt"))]
+pub(crate) use sdi;
+pub(crate) use {debug, error, info, trace};
+
+/// Up to `N` bytes of formatted text, for a `Display` value defmt can't
+/// format itself. Anything past `N` is cut off.
+pub struct Text<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Text<N> {
+    pub fn new(value: impl fmt::Display) -> Self {
+        let mut text = Text {
+            bytes: [0; N],
+            len: 0,
+        };
+        let _ = write!(text, "{value}");
+        text
+    }
+
+    pub fn as_str(&self) -> &str {
+        // A cut can split a character
+        match core::str::from_utf8(&self.bytes[..self.len]) {
+            Ok(s) => s,
+            Err(e) => core::str::from_utf8(&self.bytes[..e.valid_up_to()]).unwrap_or(""),
+        }
+    }
+}
+
+impl<const N: usize> Write for Text<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let take = s.len().min(N - self.len);
+        self.bytes[self.len..self.len + take].copy_from_slice(&s.as_bytes()[..take]);
+        self.len += take;
+        Ok(())
+    }
+}
+
+/// Timestamps defmt frames with `monotonic::now_ms`.
+#[cfg(feature = "defmt")]
+defmt::timestamp!("{=u64:ms}", crate::monotonic::now_ms());
@@ -2,515 +2,1443 @@
 #![no_main]
 #![feature(type_alias_impl_trait)]
 
+mod adc;
+mod alarm;
+#[cfg(feature = "backlight")]
+mod backlight;
+mod backup;
+mod board;
+mod buzzer;
+#[cfg(feature = "console")]
+mod console;
+#[cfg(not(any(feature = "monitor", feature = "seven-segment")))]
+mod display;
+mod fault;
+mod flash;
+mod log;
+mod manual;
+mod monotonic;
+#[cfg(feature = "nmea")]
+mod nmea;
+mod outputs;
+mod persist;
+mod pips;
+mod power;
+mod receiver;
+#[cfg(feature = "replay")]
+mod replay;
+mod rtc;
+#[cfg(feature = "seven-segment")]
+mod segments;
+mod settings;
+mod state;
+mod stats;
+mod syslog;
+mod temperature;
+mod watchdog;
+
+#[cfg(all(feature = "nmea", feature = "i2c-display"))]
+compile_error!("`nmea` needs USART2, which the I2C displays take for the log");
+#[cfg(all(feature = "console", any(feature = "nmea", feature = "i2c-display")))]
+compile_error!("`console` needs USART2, which `nmea` and the I2C displays use too");
+#[cfg(all(feature = "replay", feature = "exti-timing"))]
+compile_error!("`replay` replaces the receiver input, so it can't be timed by EXTI too");
+#[cfg(all(
+    feature = "cross-check",
+    any(feature = "exti-timing", feature = "replay")
+))]
+compile_error!("`cross-check` compares the TIM1 capture with EXTI, so it needs the capture");
+#[cfg(all(feature = "lcd-20x4", feature = "lcd-8x2"))]
+compile_error!("pick one of `lcd-20x4` and `lcd-8x2`");
+#[cfg(all(feature = "oled", any(feature = "lcd-20x4", feature = "lcd-8x2")))]
+compile_error!("the OLED always draws a 16x2 screen");
+#[cfg(all(feature = "lcd-i2c", feature = "oled"))]
+compile_error!("pick one of `lcd-i2c` and `oled`");
+#[cfg(all(feature = "seven-segment", feature = "i2c-display"))]
+compile_error!("`seven-segment` replaces the display, so it can't be on I2C too");
+#[cfg(all(feature = "button-ladder", feature = "i2c-display"))]
+compile_error!("`button-ladder` reads PA0, where the I2C displays move the buzzer");
+
 use ch32_hal::Config;
-use ch32_hal::Peri;
 use ch32_hal::exti::ExtiInput;
-use ch32_hal::gpio::{AnyPin, Level, Output};
-use ch32_hal::println;
+use ch32_hal::gpio::{Level, Output};
+use ch32_jjy_clock::{button, decoder, quality, timebase, wizard};
+#[cfg(feature = "defmt")]
+use defmt_rtt as _;
 use embassy_executor::Spawner;
-use embassy_time::{Instant, Timer};
+use embassy_time::{Duration, Timer, with_timeout};
+#[cfg(not(feature = "defmt"))]
 use panic_halt as _;
 
-use embassy_futures::select::{Either, select};
-use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
-use embassy_sync::channel::Channel;
+use board::Board;
+#[cfg(feature = "button-ladder")]
+use button::ladder::Ladder;
+use button::{Press, Presses};
+use decoder::{Abort, AnyDecoder, BitWidth, Event, Selection, Station};
+use fault::Fault;
+use manual::{ManualSet, Target};
+use outputs::{OutputSink, Outputs};
+use quality::SignalQuality;
+use receiver::JjyInput;
+use state::Reception;
+use stats::Stats;
+use timebase::{LeapSecond, Slew, TimeBase, WallClock};
+use wizard::Wizard;
+
+/// A change to the shared state, applied with `state::post`.
+enum StatusUpdate {
+    JJYOn(u8),
+    /// The pulse's width class, and the data bit it carries if any.
+    JJYOff(BitWidth, Option<bool>),
+    TimeBaseUpdate(TimeBase),
+    /// The decoder task's counters.
+    Stats(Stats),
+    Standby(bool),
+    /// Name of a station or station setting, shown for a moment.
+    Protocol(&'static str),
+    /// 0 to `quality::MAX_SCORE`.
+    SignalQuality(u8),
+    /// The page button was pressed.
+    NextPage,
+    /// Manual time setting started, moved on, or ended.
+    Setting(Option<ManualSet>),
+    /// The first-boot setup started, moved on, or ended.
+    Setup(Option<Wizard>),
+    /// A new display zone was chosen, in minutes east of UTC.
+    Zone(i32),
+    /// A button pressed while the display is blanked for the night.
+    Wake,
+    /// The decoder's coarse state changed.
+    Reception(Reception),
+    /// Seconds received of the frame in progress, or `None` while waiting
+    /// for a frame to start. At most once per `PROGRESS_INTERVAL_MS`.
+    FrameProgress(Option<u8>),
+    /// Smoothed die temperature, in tenths of °C.
+    Temperature(i16),
+}
 
-static DRAW_CHANNEL: Channel<CriticalSectionRawMutex, StatusUpdate, 8> = Channel::new();
+/// Reports the message and location over RTT, then halts like `panic_halt`.
+#[cfg(feature = "defmt")]
+#[panic_handler]
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    defmt::error!("{}", defmt::Display2Format(info));
+    loop {}
+}
 
-#[inline]
-fn bool_to_level(b: bool) -> Level {
-    match b {
-        true => Level::High,
-        false => Level::Low,
+#[embassy_executor::main(entry = "ch32_hal::entry")]
+async fn main(spawner: Spawner) -> ! {
+    #[cfg(not(feature = "defmt"))]
+    ch32_hal::debug::SDIPrint::enable();
+
+    let p = ch32_hal::init(Config::default());
+
+    if watchdog::init() {
+        log::error!("Reset by the watchdog");
     }
-}
 
-fn u8_to_level(v: u8) -> Level {
-    match v {
-        0 => Level::Low,
-        _ => Level::High,
+    let faults = fault::init();
+    if let Some(last) = faults.last {
+        match faults.last_at {
+            fault::UNSYNCED => log::info!("Last fault: {} (unsynced)", last.as_str()),
+            at => log::info!(
+                "Last fault: {} at {:02}:{:02}:{:02} UTC",
+                last.as_str(),
+                at / 3600,
+                at / 60 % 60,
+                at % 60
+            ),
+        }
+
+        log::info!(
+            "Fault counts: ReceiverSilent {}, NoValidFrame {}, SignalLost {}, EdgesDropped {}, FlashWrite {}",
+            faults.count(Fault::ReceiverSilent),
+            faults.count(Fault::NoValidFrame),
+            faults.count(Fault::SignalLost),
+            faults.count(Fault::EdgesDropped),
+            faults.count(Fault::FlashWrite)
+        );
     }
-}
 
-struct DisplayPins {
-    rs: Output<'static>,
-    rw: Output<'static>,
-    enable: Output<'static>,
-    db0: Output<'static>,
-    db1: Output<'static>,
-    db2: Output<'static>,
-    db3: Output<'static>,
-    db4: Output<'static>,
-    db5: Output<'static>,
-    db6: Output<'static>,
-    db7: Output<'static>,
+    if !rtc::init().await {
+        log::info!("No LSE crystal, running without RTC");
+    }
+
+    settings::init();
+    spawner.spawn(settings::settings_task()).unwrap();
+
+    if let Some(base) = persist::restore() {
+        timebase::store(base, monotonic::now_ms());
+    }
+    spawner.spawn(persist::persist_task()).unwrap();
+
+    adc::init();
+    let board = Board::take(p);
+
+    #[cfg(not(any(
+        feature = "monitor",
+        feature = "seven-segment",
+        feature = "i2c-display"
+    )))]
+    spawner.spawn(display::display_task(board.display)).unwrap();
+    #[cfg(all(
+        not(feature = "monitor"),
+        not(feature = "seven-segment"),
+        feature = "i2c-display"
+    ))]
+    {
+        let bus = board.display;
+        spawner
+            .spawn(display::display_task(bus.i2c, bus.scl, bus.sda))
+            .unwrap();
+    }
+
+    #[cfg(feature = "monitor")]
+    spawner.spawn(monitor_task()).unwrap();
+
+    #[cfg(feature = "backlight")]
+    spawner
+        .spawn(backlight::backlight_task(board.backlight))
+        .unwrap();
+
+    #[cfg(feature = "seven-segment")]
+    {
+        spawner
+            .spawn(segments::refresh_task(board.segments))
+            .unwrap();
+        spawner.spawn(segments::render_task()).unwrap();
+    }
+
+    #[cfg(not(feature = "replay"))]
+    let jjy = board.receiver;
+    // Recorded minutes, at the level the decoder expects for the carrier
+    #[cfg(feature = "replay")]
+    let jjy = {
+        let carrier = match RECEIVER_POLARITY {
+            Polarity::ActiveHigh => Level::High,
+            Polarity::ActiveLow | Polarity::Auto => Level::Low,
+        };
+        spawner.spawn(replay::replay_task(carrier)).unwrap();
+        JjyInput::replayed()
+    };
+
+    #[cfg(feature = "cross-check")]
+    spawner
+        .spawn(receiver::cross_check_task(board.cross_check))
+        .unwrap();
+
+    #[cfg(not(feature = "button-ladder"))]
+    let mut power_button = board.power_button;
+    #[cfg(not(feature = "button-ladder"))]
+    let selection = select_station(&mut power_button).await;
+    #[cfg(feature = "button-ladder")]
+    let selection = {
+        adc::init_pin(board::ButtonLadder::CHANNEL);
+        select_station(&board.ladder).await
+    };
+    spawner.spawn(jjy_task(jjy, board.pon, selection)).unwrap();
+
+    spawner.spawn(syslog::syslog_task(board.syslog)).unwrap();
+    syslog::emit(syslog::Event::Boot);
+
+    #[cfg(feature = "console")]
+    {
+        let (tx, rx) = board.console;
+        spawner.spawn(console::console_task(tx, rx)).unwrap();
+    }
+
+    #[cfg(not(feature = "button-ladder"))]
+    {
+        spawner.spawn(power_button_task(power_button)).unwrap();
+        spawner.spawn(page_button_task(board.page_button)).unwrap();
+    }
+    #[cfg(feature = "button-ladder")]
+    spawner.spawn(ladder_task(board.ladder)).unwrap();
+    spawner.spawn(fault_led_task(board.fault_led)).unwrap();
+
+    #[cfg(feature = "status-led")]
+    spawner.spawn(status_led_task(board.status_led)).unwrap();
+
+    buzzer::init(board.buzzer);
+
+    spawner.spawn(signal_loss_task()).unwrap();
+    spawner.spawn(alarm::alarm_task()).unwrap();
+    spawner.spawn(pips::pips_task()).unwrap();
+    spawner.spawn(stats::summary_task()).unwrap();
+    spawner.spawn(temperature::temperature_task()).unwrap();
+
+    if let Some(trigger) = board.trigger {
+        spawner.spawn(trigger_task(trigger)).unwrap();
+    }
+
+    if let Some(zero_cross) = board.zero_cross {
+        spawner.spawn(mains_task(zero_cross)).unwrap();
+    }
+
+    spawner.spawn(watchdog::watchdog_task()).unwrap();
+
+    // Everything else happens in the tasks, and the outputs on this one;
+    // the executor sleeps in WFI whenever none of them has work
+    let mut pps = board.pps.map(|pin| Pps { pin, high: false });
+    #[cfg(feature = "nmea")]
+    let mut nmea = nmea::Nmea::new(board.nmea);
+
+    let mut outputs = Outputs::new();
+    if let Some(pps) = &mut pps {
+        outputs.register(pps);
+    }
+    #[cfg(feature = "nmea")]
+    outputs.register(&mut nmea);
+
+    outputs.run().await
 }
 
-enum StatusUpdate {
-    JJYOn(u8),
-    JJYOff(BitWidth),
-    TimeBaseUpdate(TimeBase),
+/// Headless replacement for the display: logs reception statistics once a
+/// minute.
+#[cfg(feature = "monitor")]
+#[embassy_executor::task]
+async fn monitor_task() {
+    let mut report_at = embassy_time::Instant::now() + Duration::from_secs(60);
+    let mut reported = state::snapshot();
+
+    loop {
+        Timer::at(report_at).await;
+        report_at += Duration::from_secs(60);
+
+        let current = state::snapshot();
+        let pulses = |bit: BitWidth| {
+            current.stats.pulses[bit as usize] - reported.stats.pulses[bit as usize]
+        };
+
+        log::info!(
+            "MON marker {} short {} medium {} long {} double {} unknown {} decodes {} quality {}",
+            pulses(BitWidth::Marker),
+            pulses(BitWidth::Short),
+            pulses(BitWidth::Medium),
+            pulses(BitWidth::Long),
+            pulses(BitWidth::Double),
+            pulses(BitWidth::Unknown),
+            current.stats.decodes - reported.stats.decodes,
+            current.signal_quality
+        );
+
+        reported = current;
+    }
 }
 
-async fn send_display_bus(pins: &mut DisplayPins, rs: bool, rw: bool, data: u8) {
-    pins.rs.set_level(bool_to_level(rs));
-    pins.rw.set_level(bool_to_level(rw));
-    pins.db7.set_level(u8_to_level(data & 0x80));
-    pins.db6.set_level(u8_to_level(data & 0x40));
-    pins.db5.set_level(u8_to_level(data & 0x20));
-    pins.db4.set_level(u8_to_level(data & 0x10));
-    pins.db3.set_level(u8_to_level(data & 0x08));
-    pins.db2.set_level(u8_to_level(data & 0x04));
-    pins.db1.set_level(u8_to_level(data & 0x02));
-    pins.db0.set_level(u8_to_level(data & 0x01));
+const DEBOUNCE_MS: u64 = 30;
+
+/// While the power button is held at boot, the station setting advances
+/// this often.
+const SELECTION_STEP: Duration = Duration::from_secs(1);
 
-    Timer::after_micros(5).await;
-    pins.enable.set_high();
+/// Reads the saved station setting. Holding the power button at boot steps
+/// through the settings; the one shown on release is saved.
+#[cfg(not(feature = "button-ladder"))]
+async fn select_station(button: &mut ExtiInput<'static>) -> Selection {
+    let mut selection = settings::get().selection;
+    state::post(StatusUpdate::Protocol(selection.as_str()));
 
-    Timer::after_micros(1000).await;
-    pins.enable.set_low();
+    if button.is_high() {
+        return selection;
+    }
+
+    Timer::after_millis(DEBOUNCE_MS).await;
+    while button.is_low() {
+        if with_timeout(SELECTION_STEP, button.wait_for_rising_edge())
+            .await
+            .is_ok()
+        {
+            break;
+        }
+
+        selection = next_selection(selection);
+    }
+    Timer::after_millis(DEBOUNCE_MS).await;
+
+    settings::update(|s| s.selection = selection);
+    selection
 }
 
-#[embassy_executor::task]
-async fn display_task(
-    rs: Peri<'static, AnyPin>,
-    rw: Peri<'static, AnyPin>,
-    enable: Peri<'static, AnyPin>,
-    db0: Peri<'static, AnyPin>,
-    db1: Peri<'static, AnyPin>,
-    db2: Peri<'static, AnyPin>,
-    db3: Peri<'static, AnyPin>,
-    db4: Peri<'static, AnyPin>,
-    db5: Peri<'static, AnyPin>,
-    db6: Peri<'static, AnyPin>,
-    db7: Peri<'static, AnyPin>,
-) {
-    let mut pins = DisplayPins {
-        rs: Output::new(rs, Level::Low, Default::default()),
-        rw: Output::new(rw, Level::Low, Default::default()),
-        enable: Output::new(enable, Level::Low, Default::default()),
-        db0: Output::new(db0, Level::Low, Default::default()),
-        db1: Output::new(db1, Level::Low, Default::default()),
-        db2: Output::new(db2, Level::Low, Default::default()),
-        db3: Output::new(db3, Level::Low, Default::default()),
-        db4: Output::new(db4, Level::Low, Default::default()),
-        db5: Output::new(db5, Level::Low, Default::default()),
-        db6: Output::new(db6, Level::Low, Default::default()),
-        db7: Output::new(db7, Level::Low, Default::default()),
-    };
+/// `select_station` for the ladder's power button, read every
+/// `DEBOUNCE_MS`.
+#[cfg(feature = "button-ladder")]
+async fn select_station(ladder: &board::ButtonLadder) -> Selection {
+    let mut selection = settings::get().selection;
+    state::post(StatusUpdate::Protocol(selection.as_str()));
+
+    let reader = Ladder::new(ladder.below);
+    let power = ladder
+        .buttons
+        .iter()
+        .position(|b| *b == board::Button::Power);
+    let held = || reader.classify(adc::sample(board::ButtonLadder::CHANNEL)) == power;
+    if power.is_none() || !held() {
+        return selection;
+    }
 
-    Timer::after_millis(100).await;
+    let mut step_at = embassy_time::Instant::now() + SELECTION_STEP;
+    while held() {
+        if embassy_time::Instant::now() >= step_at {
+            step_at += SELECTION_STEP;
+            selection = next_selection(selection);
+        }
+        Timer::after_millis(DEBOUNCE_MS).await;
+    }
 
-    // Function Set
-    send_display_bus(&mut pins, false, false, 0b0011_1000).await;
+    settings::update(|s| s.selection = selection);
+    selection
+}
 
-    // Display ON/OFF Control
-    send_display_bus(&mut pins, false, false, 0b0000_1100).await;
+/// Shows and logs the station setting after `selection`.
+fn next_selection(selection: Selection) -> Selection {
+    let selection = selection.next();
+    state::post(StatusUpdate::Protocol(selection.as_str()));
+    log::info!("Station setting: {}", selection.as_str());
+    selection
+}
 
-    // Display Clear
-    send_display_bus(&mut pins, false, false, 0b0000_0001).await;
-    Timer::after_micros(530).await;
+/// While the alarm rings, a short press of either button snoozes it and a
+/// long one stops it; returns whether `press` went to the alarm.
+fn answer_alarm(press: Press) -> bool {
+    match press {
+        Press::Short => alarm::snooze(),
+        Press::Long => alarm::stop(),
+        Press::Repeat => false,
+    }
+}
 
-    // Entry Mode Set
-    send_display_bus(&mut pins, false, false, 0b0000_0110).await;
+/// A short press steps the display to its next page. A long press starts
+/// setting the time or the alarm by hand: short presses (or holding on)
+/// step the field being edited, and long presses move on to the next field,
+/// then confirm. The first-boot setup takes the presses the same way
+/// while it runs.
+struct PageButton {
+    setting: Option<ManualSet>,
+    setup: Option<Wizard>,
+}
 
-    // "Sync"
-    send_display_bus(&mut pins, true, false, 0b0101_0011).await;
-    send_display_bus(&mut pins, true, false, 0b0111_1001).await;
-    send_display_bus(&mut pins, true, false, 0b0110_1110).await;
-    send_display_bus(&mut pins, true, false, 0b0110_0011).await;
+impl PageButton {
+    const fn new() -> Self {
+        PageButton {
+            setting: None,
+            setup: None,
+        }
+    }
 
-    let mut timebase: Option<TimeBase> = None;
-    let mut jjy_status = false;
-    let mut cursor = 0;
-    let mut latest_bitwidth = BitWidth::Unknown;
+    /// Starts the first-boot setup, unless it has run before.
+    #[cfg(not(any(feature = "monitor", feature = "seven-segment")))]
+    fn begin_setup(&mut self) {
+        let settings = settings::get();
+        if settings.set_up {
+            return;
+        }
 
-    loop {
-        match timebase {
-            Some(b) => {
-                let now = Instant::now().as_millis();
-                let next_update_after = 1000 - ((now - b.system_time - 50) % 1000);
+        log::info!("Not set up yet, starting the setup");
+        self.setup = Some(Wizard::start(
+            settings.language,
+            settings.zone_minutes,
+            settings.twelve_hour,
+            display::SETUP_SKIPPED,
+        ));
+        state::post(StatusUpdate::Setup(self.setup));
+    }
 
-                let next_update_waiter = Timer::after_millis(next_update_after);
+    fn press_setup(&mut self, wizard: Wizard, press: Press) {
+        let next = match press {
+            _ if answer_alarm(press) => Some(wizard),
+            Press::Short | Press::Repeat => Some(wizard.increment()),
+            Press::Long => wizard.advance(),
+        };
 
-                let receiver = DRAW_CHANNEL.receiver();
+        if next.is_none() {
+            log::info!("Setup done");
+            settings::update(|s| {
+                s.language = wizard.language;
+                s.twelve_hour = wizard.twelve_hour;
+                s.set_up = true;
+            });
+            if wizard.zone_minutes != settings::zone() {
+                state::post(StatusUpdate::Zone(wizard.zone_minutes));
+            }
+        }
 
-                match select(next_update_waiter, receiver.receive()).await {
-                    Either::First(_) => {}
-                    Either::Second(StatusUpdate::TimeBaseUpdate(base)) => {
-                        timebase = Some(base);
-                    }
-                    Either::Second(StatusUpdate::JJYOn(c)) => {
-                        jjy_status = true;
-                        cursor = c;
+        if next != self.setup {
+            self.setup = next;
+            state::post(StatusUpdate::Setup(next));
+        }
+    }
+
+    fn press(&mut self, press: Press, now: u64) {
+        if let Some(wizard) = self.setup {
+            self.press_setup(wizard, press);
+            return;
+        }
+
+        let setting = self.setting;
+        let next = match (setting, press) {
+            _ if answer_alarm(press) => setting,
+            (None, Press::Short) if is_blanked(now) => {
+                state::post(StatusUpdate::Wake);
+                None
+            }
+            (None, Press::Short) => {
+                state::post(StatusUpdate::NextPage);
+                None
+            }
+            (None, Press::Long) => Some(ManualSet::start(timebase::load(), alarm::setting(), now)),
+            (None, Press::Repeat) => None,
+            (Some(s), Press::Short | Press::Repeat) => Some(s.increment()),
+            (Some(s), Press::Long) => {
+                let advanced = s.advance();
+                match (advanced, s.target) {
+                    (Some(_), _) => {}
+                    (None, Target::Clock) => {
+                        let base = s.timebase(timebase::load(), now);
+                        log::info!("Time set by hand to {:02}:{:02}", s.hour, s.minute);
+                        state::post(StatusUpdate::TimeBaseUpdate(base));
                     }
-                    Either::Second(StatusUpdate::JJYOff(bit_width)) => {
-                        jjy_status = false;
-                        latest_bitwidth = bit_width;
+                    (None, Target::Alarm) => {
+                        log::info!(
+                            "Alarm set to {:02}:{:02}, {}",
+                            s.hour,
+                            s.minute,
+                            if s.enabled { "on" } else { "off" }
+                        );
+                        alarm::set(s.alarm());
                     }
                 }
+                advanced
             }
-            None => match DRAW_CHANNEL.receiver().receive().await {
-                StatusUpdate::TimeBaseUpdate(base) => {
-                    timebase = Some(base);
-                }
-                StatusUpdate::JJYOn(c) => {
-                    jjy_status = true;
-                    cursor = c;
-                }
-                StatusUpdate::JJYOff(bit_width) => {
-                    jjy_status = false;
-                    latest_bitwidth = bit_width;
-                }
-            },
-        }
-
-        // Display Clear
-        send_display_bus(&mut pins, false, false, 0b0000_0001).await;
-        Timer::after_micros(530).await;
-
-        match timebase {
-            Some(timebase) => {
-                let now = Instant::now().as_millis();
-                let diff = ((now - timebase.system_time) / 1000) as u32;
-
-                let remaining = (timebase.clock + diff) % (60 * 60 * 24);
-                let hour = remaining / (60 * 60);
-                let remaining = remaining % (60 * 60);
-                let minute = remaining / 60;
-                let remaining = remaining % 60;
-                let sec = remaining;
-
-                let hour_h = (hour / 10) as u8;
-                let hour_l = (hour % 10) as u8;
-                let minute_h = (minute / 10) as u8;
-                let minute_l = (minute % 10) as u8;
-                let sec_h = (sec / 10) as u8;
-                let sec_l = (sec % 10) as u8;
-
-                send_display_bus(&mut pins, true, false, 0b0011_0000 + hour_h).await;
-                send_display_bus(&mut pins, true, false, 0b0011_0000 + hour_l).await;
-                send_display_bus(&mut pins, true, false, 0b0011_1010).await;
-                send_display_bus(&mut pins, true, false, 0b0011_0000 + minute_h).await;
-                send_display_bus(&mut pins, true, false, 0b0011_0000 + minute_l).await;
-                send_display_bus(&mut pins, true, false, 0b0011_1010).await;
-                send_display_bus(&mut pins, true, false, 0b0011_0000 + sec_h).await;
-                send_display_bus(&mut pins, true, false, 0b0011_0000 + sec_l).await;
-                for _ in 8..40 {
-                    send_display_bus(&mut pins, true, false, 0b0010_0000).await;
+        };
+
+        if next != setting {
+            self.setting = next;
+            state::post(StatusUpdate::Setting(next));
+        }
+    }
+}
+
+/// Whether the display is off for the night, so a press only wakes it.
+fn is_blanked(now: u64) -> bool {
+    power::is_blanked(&state::snapshot(), timebase::load(), now)
+}
+
+/// Toggles display-off standby; timekeeping and reception carry on.
+/// During the blank hours, a press shows the display for a while. While
+/// the backlight is dimmed for the night, a press lights it up for
+/// a while instead. Holding it steps the display zone 30 minutes east at a
+/// time.
+struct PowerButton {
+    standby: bool,
+}
+
+impl PowerButton {
+    const fn new() -> Self {
+        PowerButton { standby: false }
+    }
+
+    fn press(&mut self, press: Press, now: u64) {
+        match press {
+            press if answer_alarm(press) => {}
+            Press::Short if is_blanked(now) => state::post(StatusUpdate::Wake),
+            Press::Short => {
+                // At night, a press while dimmed only brings the backlight up
+                #[cfg(feature = "backlight")]
+                let boosted = backlight::boost();
+                #[cfg(not(feature = "backlight"))]
+                let boosted = false;
+
+                if !boosted {
+                    self.standby = !self.standby;
+                    state::post(StatusUpdate::Standby(self.standby));
                 }
             }
-            None => {
-                // "Sync"
-                send_display_bus(&mut pins, true, false, 0b0101_0011).await;
-                send_display_bus(&mut pins, true, false, 0b0111_1001).await;
-                send_display_bus(&mut pins, true, false, 0b0110_1110).await;
-                send_display_bus(&mut pins, true, false, 0b0110_0011).await;
-                for _ in 4..40 {
-                    send_display_bus(&mut pins, true, false, 0b0010_0000).await;
+            Press::Long | Press::Repeat => step_zone(),
+        }
+    }
+}
+
+/// Moves the display zone 30 minutes east.
+fn step_zone() {
+    let zone = timebase::next_zone(settings::zone());
+    let (sign, hours, minutes) = timebase::zone_parts(zone);
+    log::info!("Zone: UTC{}{:02}:{:02}", sign as char, hours, minutes);
+    state::post(StatusUpdate::Zone(zone));
+}
+
+/// Waits for the next edge of `button`, or the moment a held press turns
+/// long or repeats, then waits out the bounces after an edge.
+#[cfg(not(feature = "button-ladder"))]
+async fn wait_for_button(button: &mut ExtiInput<'static>, presses: &Presses, now: u64) {
+    let edge = button.wait_for_any_edge();
+    let changed = match presses.deadline() {
+        Some(at) => {
+            let wait = Duration::from_millis(at.saturating_sub(now));
+            with_timeout(wait, edge).await.is_ok()
+        }
+        None => {
+            edge.await;
+            true
+        }
+    };
+
+    if changed {
+        Timer::after_millis(DEBOUNCE_MS).await;
+    }
+}
+
+#[cfg(not(feature = "button-ladder"))]
+#[embassy_executor::task]
+async fn page_button_task(mut button: ExtiInput<'static>) {
+    let mut presses = Presses::new();
+    let mut page = PageButton::new();
+    // Builds without a display have nothing to show it on
+    #[cfg(not(any(feature = "monitor", feature = "seven-segment")))]
+    page.begin_setup();
+
+    loop {
+        let now = monotonic::now_ms();
+        if let Some(press) = presses.update(button.is_low(), now) {
+            page.press(press, now);
+        }
+
+        wait_for_button(&mut button, &presses, now).await;
+    }
+}
+
+#[cfg(not(feature = "button-ladder"))]
+#[embassy_executor::task]
+async fn power_button_task(mut button: ExtiInput<'static>) {
+    let mut presses = Presses::new();
+    let mut power = PowerButton::new();
+
+    loop {
+        let now = monotonic::now_ms();
+        if let Some(press) = presses.update(button.is_low(), now) {
+            power.press(press, now);
+        }
+
+        wait_for_button(&mut button, &presses, now).await;
+    }
+}
+
+/// The ladder is read this often: a press settles within two readings,
+/// about `DEBOUNCE_MS`.
+#[cfg(feature = "button-ladder")]
+const LADDER_POLL_MS: u64 = 15;
+
+/// Every button on the ladder, each doing what its `board::Button` says.
+/// There is no edge to wait for, so the ladder is polled.
+#[cfg(feature = "button-ladder")]
+#[embassy_executor::task]
+async fn ladder_task(ladder: board::ButtonLadder) {
+    // Kept for as long as the ladder is read
+    let _pin = ladder.pin;
+    let mut reader = Ladder::new(ladder.below);
+    let mut presses = [const { Presses::new() }; board::ButtonLadder::MAX_BUTTONS];
+    let mut power = PowerButton::new();
+    let mut page = PageButton::new();
+    // Builds without a display have nothing to show it on
+    #[cfg(not(any(feature = "monitor", feature = "seven-segment")))]
+    page.begin_setup();
+
+    loop {
+        let now = monotonic::now_ms();
+        let held = reader.update(adc::sample(board::ButtonLadder::CHANNEL));
+
+        for (i, (button, presses)) in ladder.buttons.iter().zip(&mut presses).enumerate() {
+            let Some(press) = presses.update(held == Some(i), now) else {
+                continue;
+            };
+
+            match button {
+                board::Button::Power => power.press(press, now),
+                board::Button::Page => page.press(press, now),
+                board::Button::Snooze => {
+                    answer_alarm(press);
                 }
+                board::Button::Zone if answer_alarm(press) => {}
+                board::Button::Zone => step_zone(),
             }
         }
 
-        if jjy_status {
-            send_display_bus(&mut pins, true, false, 0b1111_1111).await;
-        } else {
-            send_display_bus(&mut pins, true, false, 0b0010_0000).await;
+        Timer::after_millis(LADDER_POLL_MS).await;
+    }
+}
+
+#[embassy_executor::task]
+async fn fault_led_task(mut led: Output<'static>) {
+    loop {
+        if state::snapshot().standby {
+            led.set_low();
+            Timer::after_millis(1000).await;
+            continue;
         }
 
-        let character = match latest_bitwidth {
-            BitWidth::Long => 0b0100_1100,
-            BitWidth::Short => 0b0101_0011,
-            BitWidth::Marker => 0b0100_1101,
-            BitWidth::Unknown => 0b0011_1111,
+        let Some(fault) = fault::most_severe() else {
+            led.set_low();
+            Timer::after_millis(1000).await;
+            continue;
         };
 
-        send_display_bus(&mut pins, true, false, character).await;
+        for _ in 0..fault.blinks() {
+            led.set_high();
+            Timer::after_millis(200).await;
+            led.set_low();
+            Timer::after_millis(300).await;
+        }
 
-        let cursor_h = cursor / 10;
-        let cursor_l = cursor % 10;
-        send_display_bus(&mut pins, true, false, 0b0011_0000 + cursor_h).await;
-        send_display_bus(&mut pins, true, false, 0b0011_0000 + cursor_l).await;
+        Timer::after_millis(1500).await;
     }
 }
 
-#[embassy_executor::main(entry = "ch32_hal::entry")]
-async fn main(spawner: Spawner) -> ! {
-    ch32_hal::debug::SDIPrint::enable();
+/// LED on/off times in ms, repeated, for each reception state: a fast
+/// blink while receiving, a heartbeat while synced, a double blink once
+/// stale, and dark without a signal.
+#[cfg(feature = "status-led")]
+fn status_pattern(reception: Reception) -> &'static [u64] {
+    match reception {
+        Reception::Receiving => &[100, 100],
+        Reception::Synced => &[50, 1950],
+        Reception::Stale => &[100, 150, 100, 1650],
+        Reception::NoSignal => &[0, 1000],
+    }
+}
 
-    let p = ch32_hal::init(Config::default());
+/// Sync status on an LED bright enough to watch while placing the antenna.
+#[cfg(feature = "status-led")]
+#[embassy_executor::task]
+async fn status_led_task(mut led: Output<'static>) {
+    loop {
+        let current = state::snapshot();
+        let pattern: &[u64] = match current.standby {
+            true => &[0, 1000],
+            false => status_pattern(current.reception),
+        };
 
-    spawner
-        .spawn(display_task(
-            p.PB0.into(), // rs
-            p.PB1.into(), // rw
-            p.PA8.into(), // enable
-            p.PA0.into(), // d0
-            p.PA1.into(), // d1
-            p.PA2.into(), // d2
-            p.PA3.into(), // d3
-            p.PA4.into(), // d4
-            p.PA5.into(), // d5
-            p.PA6.into(), // d6
-            p.PA7.into(), // d7
-        ))
-        .unwrap();
+        for (i, ms) in pattern.iter().enumerate() {
+            led.set_level(if i % 2 == 0 && *ms > 0 {
+                Level::High
+            } else {
+                Level::Low
+            });
+            Timer::after_millis(*ms).await;
+        }
+    }
+}
+
+/// Warn when reception has been failing for days: the antenna has most
+/// likely been knocked out of position.
+const SIGNAL_LOSS_ALARM: bool = true;
+
+const SIGNAL_LOSS_DAYS: u64 = 3;
 
-    // 外部割り込みを使用する場合のタスク
-    // ExtiInputを作成するために、ペリフェラル、EXTIライン、プル設定が必要
-    let exti_button = ExtiInput::new(p.PA9, p.EXTI9, ch32_hal::gpio::Pull::None);
-    spawner.spawn(jjy_task(exti_button)).unwrap();
+/// Local hours during which the alarm may chime.
+const CHIME_HOURS: core::ops::RangeInclusive<u8> = 9..=20;
 
+/// Logs how long ago the last sync was, once per hour without one, and
+/// raises the signal loss alarm if enabled.
+#[embassy_executor::task]
+async fn signal_loss_task() {
+    let mut chimed = false;
+    let mut reported_hours = 0;
+
+    loop {
+        Timer::after_secs(60).await;
+
+        let now = monotonic::now_ms();
+        let base = timebase::load();
+
+        if let Some(at) = state::snapshot().last_sync_at() {
+            let minutes = (now - at) / (60 * 1000);
+            if minutes / 60 != reported_hours {
+                reported_hours = minutes / 60;
+                if reported_hours > 0 {
+                    log::info!("Last sync {} min ago", minutes);
+                }
+            }
+        }
+
+        if !SIGNAL_LOSS_ALARM {
+            continue;
+        }
+
+        // Before the first sync, count from boot
+        let synced_at = base.map_or(0, |b| b.system_time);
+        if now - synced_at < SIGNAL_LOSS_DAYS * 24 * 60 * 60 * 1000 {
+            fault::clear(Fault::SignalLost);
+            chimed = false;
+            continue;
+        }
+
+        fault::raise(Fault::SignalLost);
+
+        // Only a known time of day can tell whether chiming is polite
+        let Some(base) = base else {
+            continue;
+        };
+
+        if !chimed && CHIME_HOURS.contains(&base.wall_clock(now, settings::zone()).hour) {
+            buzzer::chime(500).await;
+            chimed = true;
+            syslog::emit(syslog::Event::Alarm("signal-loss"));
+        }
+    }
+}
+
+#[embassy_executor::task]
+async fn trigger_task(mut trigger: ExtiInput<'static>) {
     loop {
-        Timer::after_millis(1000).await;
-        // println!("poll");
+        trigger.wait_for_any_edge().await;
+
+        let at = monotonic::now_ms();
+        let edge = if trigger.is_high() { "rise" } else { "fall" };
+
+        match timebase::load() {
+            Some(base) => {
+                let c = base.wall_clock(at, settings::zone());
+                log::info!(
+                    "TRIG {} {:02}:{:02}:{:02}.{:03}",
+                    edge,
+                    c.hour,
+                    c.minute,
+                    c.second,
+                    c.millisecond
+                );
+            }
+            None => log::info!("TRIG {} unsynced (+{} ms)", edge, at),
+        }
     }
 }
 
-#[repr(u8)]
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum BitWidth {
-    Unknown,
-    Marker,
-    Short,
-    Long,
+/// Width of each PPS pulse.
+const PPS_PULSE_MS: u64 = 100;
+
+/// One pulse per second, rising on each second boundary of a radio-synced
+/// timebase and low before.
+struct Pps {
+    pin: Output<'static>,
+    high: bool,
 }
 
-impl BitWidth {
-    fn as_str(&self) -> &'static str {
-        match self {
-            BitWidth::Unknown => "Unknown",
-            BitWidth::Marker => "Marker",
-            BitWidth::Short => "Short",
-            BitWidth::Long => "Long",
+impl OutputSink for Pps {
+    fn on_second(&mut self, _at_ms: u64, clock: Option<&WallClock>) {
+        if clock.is_some() {
+            self.pin.set_high();
+            self.high = true;
         }
     }
 
-    fn try_as_bool(&self) -> Option<bool> {
-        match self {
-            BitWidth::Unknown => None,
-            BitWidth::Marker => None,
-            BitWidth::Short => Some(true),
-            BitWidth::Long => Some(false),
+    fn render(&mut self, since_ms: u64) -> Option<u64> {
+        if !self.high {
+            return None;
         }
+
+        if since_ms < PPS_PULSE_MS {
+            return Some(PPS_PULSE_MS);
+        }
+        self.pin.set_low();
+        self.high = false;
+        None
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-struct TimeBase {
-    system_time: u64,
-    clock: u32,
-}
+/// Nominal grid frequency (50 Hz east of the Fuji river, 60 Hz west).
+const MAINS_HZ: u64 = 50;
 
+const MAINS_REPORT_MS: u64 = 10 * 1000;
+
+/// Counts zero crossings against the JJY-disciplined clock and logs the
+/// grid frequency plus the accumulated grid time deviation.
 #[embassy_executor::task]
-async fn jjy_task(mut exti_button: ExtiInput<'static>) {
-    const ALLOWED_ERROR: f32 = 0.20;
+async fn mains_task(mut zero_cross: ExtiInput<'static>) {
+    let mut first_at: Option<u64> = None;
+    let mut window_at = 0;
+    let mut window_cycles = 0u64;
+    let mut total_cycles = 0u64;
 
-    let mut buffer = [BitWidth::Unknown; 60];
-    let mut cursor = 0u8;
-    let mut recording = false;
-    let mut previous_is_marker = false;
+    loop {
+        zero_cross.wait_for_rising_edge().await;
 
-    fn is_in_width(left_hand: u32, right_hand: u32) -> bool {
-        let max_time = right_hand as f32 * (1.0 + ALLOWED_ERROR);
-        let min_time = right_hand as f32 * (1.0 - ALLOWED_ERROR);
-        let actual_time = left_hand as f32;
-        min_time < actual_time && actual_time < max_time
-    }
+        let at = monotonic::now_ms();
 
-    loop {
-        exti_button.wait_for_falling_edge().await;
+        if timebase::load().is_none() {
+            continue;
+        }
 
-        let up_at = Instant::now().as_millis();
+        let Some(first) = first_at else {
+            first_at = Some(at);
+            window_at = at;
+            continue;
+        };
 
-        DRAW_CHANNEL
-            .sender()
-            .send(StatusUpdate::JJYOn(cursor))
-            .await;
+        window_cycles += 1;
+        total_cycles += 1;
 
-        exti_button.wait_for_rising_edge().await;
+        let window_ms = at - window_at;
+        if window_ms < MAINS_REPORT_MS {
+            continue;
+        }
 
-        let down_at = Instant::now().as_millis();
+        let millihertz = window_cycles * 1_000_000 / window_ms;
+        let grid_ms = (total_cycles * 1000 / MAINS_HZ) as i64;
+        let deviation_ms = grid_ms - (at - first) as i64;
 
-        // 87 is Dirty Hack
-        let elapsed_ms = (down_at - up_at) as u32;
+        log::info!(
+            "MAINS {}.{:03} Hz, grid time {} ms",
+            millihertz / 1000,
+            millihertz % 1000,
+            deviation_ms
+        );
 
-        let bit = match elapsed_ms {
-            ms if is_in_width(ms, 200) => BitWidth::Marker,
-            ms if is_in_width(ms, 500) => BitWidth::Short,
-            ms if is_in_width(ms, 800) => BitWidth::Long,
-            _ => BitWidth::Unknown,
-        };
+        window_at = at;
+        window_cycles = 0;
+    }
+}
+
+/// PON levels of the receiver module (low keeps it powered).
+const RECEIVER_ON: Level = Level::Low;
+const RECEIVER_OFF: Level = Level::High;
+
+/// Receiver output level while the carrier is at full power, i.e. during
+/// the timed part of each second.
+#[allow(dead_code)] // only one of these is selected
+enum Polarity {
+    /// Open-collector modules pull the output low.
+    ActiveLow,
+    /// e.g. MAS6180 breakouts.
+    ActiveHigh,
+    /// Watch a few seconds of edges and pick whichever level times like
+    /// JJY pulses.
+    Auto,
+}
+
+const RECEIVER_POLARITY: Polarity = Polarity::ActiveLow;
 
-        DRAW_CHANNEL.sender().send(StatusUpdate::JJYOff(bit)).await;
+/// Pulses timed under each interpretation before auto-detect decides.
+const POLARITY_SAMPLES: u32 = 10;
 
-        println!("{} ms ({})", elapsed_ms, bit.as_str());
+/// Spikes shorter than this are merged into the surrounding level.
+const GLITCH_FILTER: Duration = Duration::from_millis(30);
 
-        if bit == BitWidth::Unknown {
-            println!("ABORT! Unknown width is comming");
-            cursor = 0;
-            recording = false;
+fn opposite(level: Level) -> Level {
+    match level {
+        Level::Low => Level::High,
+        Level::High => Level::Low,
+    }
+}
+
+/// Waits until `input` settles at `level` and returns when it got there.
+/// An edge that reverts within `GLITCH_FILTER` doesn't count.
+async fn wait_for_stable_level(input: &mut JjyInput, level: Level) -> u64 {
+    let high = matches!(level, Level::High);
+
+    loop {
+        let edge = input.next_edge().await;
+        if edge.high != high {
             continue;
         }
 
-        if bit == BitWidth::Marker {
-            if previous_is_marker {
-                println!("Start Bit Detected!");
-                recording = true;
-                cursor = 0;
+        match with_timeout(GLITCH_FILTER, input.next_edge()).await {
+            Err(_) => return edge.at_ms,
+            // Queued late, but the level did hold long enough
+            Ok(next) if next.at_ms - edge.at_ms >= GLITCH_FILTER.as_millis() => {
+                input.unread(next);
+                return edge.at_ms;
             }
+            Ok(_) => {}
+        }
+    }
+}
+
+/// Times both levels of the receiver output and returns the one whose
+/// durations classify as pulses of `station` more often.
+async fn detect_polarity(input: &mut JjyInput, station: Station) -> Level {
+    let tolerance_permille = PROFILES[PROFILE_WEAK].tolerance_permille(station);
+    let mut plausible = [0u32; 2];
+    let mut samples = 0;
+    let mut previous: Option<receiver::Edge> = None;
+
+    while samples < POLARITY_SAMPLES * 2 {
+        let Ok(edge) = with_timeout(RECEIVER_SILENCE, input.next_edge()).await else {
+            fault::raise(Fault::ReceiverSilent);
+            previous = None;
+            continue;
+        };
+        fault::clear(Fault::ReceiverSilent);
 
-            previous_is_marker = true;
-        } else {
-            previous_is_marker = false;
+        if let Some(previous) = previous.replace(edge) {
+            let elapsed_ms = (edge.at_ms - previous.at_ms) as u32;
+            if station.classify(elapsed_ms, tolerance_permille) != BitWidth::Unknown {
+                plausible[previous.high as usize] += 1;
+            }
+            samples += 1;
         }
+    }
 
-        if recording {
-            if cursor == 38 {
-                fn to_minute_hour_day(buf: &[BitWidth]) -> Option<(u32, u32, u32)> {
-                    let mut minute = 0;
-                    let mut minute_parity = false;
-                    let mut hour = 0;
-                    let mut hour_parity = false;
-                    let mut day = 0;
+    let high = plausible[1] > plausible[0];
+    log::info!(
+        "Polarity: {} low / {} high plausible, carrier is active {}",
+        plausible[0],
+        plausible[1],
+        if high { "high" } else { "low" }
+    );
 
-                    if buf[1].try_as_bool()? {
-                        minute += 40;
-                        minute_parity = !minute_parity;
-                    }
+    if high { Level::High } else { Level::Low }
+}
 
-                    if buf[2].try_as_bool()? {
-                        minute += 20;
-                        minute_parity = !minute_parity;
-                    }
+/// No legal JJY state lasts this long, so the receiver has gone quiet.
+const RECEIVER_SILENCE: Duration = Duration::from_secs(3);
 
-                    if buf[3].try_as_bool()? {
-                        minute += 10;
-                        minute_parity = !minute_parity;
-                    }
+/// How late the start of each second is timestamped, mostly the receiver's
+/// demodulator delay. Measure once against a reference second (e.g. a GPS
+/// PPS on the trigger input); capture latency is already removed by
+/// `receiver`.
+const EDGE_LATENCY_MS: u64 = 25;
 
-                    if buf[5].try_as_bool()? {
-                        minute += 8;
-                        minute_parity = !minute_parity;
-                    }
+/// Silence this long invalidates the learned pulse-width calibration.
+const CALIBRATION_SILENCE_MS: u64 = 5 * 60 * 1000;
 
-                    if buf[6].try_as_bool()? {
-                        minute += 4;
-                        minute_parity = !minute_parity;
-                    }
+/// Frame progress is posted no more often than this, a little under the
+/// pulse period so a clean frame shows every second.
+const PROGRESS_INTERVAL_MS: u64 = 900;
 
-                    if buf[7].try_as_bool()? {
-                        minute += 2;
-                        minute_parity = !minute_parity;
-                    }
+/// Edges without a valid frame for this long power-cycle the receiver.
+const ACQUISITION_TIMEOUT_MS: u64 = 30 * 60 * 1000;
 
-                    if buf[8].try_as_bool()? {
-                        minute += 1;
-                        minute_parity = !minute_parity;
-                    }
+/// The RTC is set on the edge starting the second after a decode only if
+/// the edge comes within this of a second after the decoded one.
+const RTC_SET_WINDOW_MS: u64 = 100;
 
-                    if buf[12].try_as_bool()? {
-                        hour += 20;
-                        hour_parity = !hour_parity;
-                    }
+/// Pulse-width tolerance used by the decoder for a given reception quality.
+struct ReceptionProfile {
+    name: &'static str,
+    /// Use the protocol's wider tolerance, which is still checked to keep
+    /// the symbols apart.
+    weak: bool,
+}
 
-                    if buf[13].try_as_bool()? {
-                        hour += 10;
-                        hour_parity = !hour_parity;
-                    }
+impl ReceptionProfile {
+    /// Accepted deviation from the nominal width, in per-mille.
+    fn tolerance_permille(&self, station: Station) -> u32 {
+        let (strong, weak) = station.tolerance_permille();
+        if self.weak { weak } else { strong }
+    }
+}
 
-                    if buf[15].try_as_bool()? {
-                        hour += 8;
-                        hour_parity = !hour_parity;
-                    }
+const PROFILES: [ReceptionProfile; 2] = [
+    ReceptionProfile {
+        name: "near-field strong",
+        weak: false,
+    },
+    // Wide enough to catch a stretched pulse, still no symbol overlap
+    ReceptionProfile {
+        name: "fringe weak",
+        weak: true,
+    },
+];
+
+const PROFILE_STRONG: usize = 0;
+const PROFILE_WEAK: usize = 1;
+
+/// Decode success is judged over windows of this many minutes.
+const PROFILE_WINDOW_MINUTES: u32 = 10;
+
+/// Stations the auto setting tries in turn. JJY60 decodes like JJY40.
+const AUTO_STATIONS: [Station; 4] = [Station::Jjy40, Station::Dcf77, Station::Wwvb, Station::Msf];
+
+/// Time each station gets under the auto setting: enough for a frame start
+/// and the frame after it.
+const AUTO_DWELL_MS: u64 = 3 * 60 * 1000;
+
+/// One line per frame, at debug: the station, a symbol per second, and the
+/// outcome, so a capture can be replayed or diffed offline.
+fn log_frame(station: Station, frame: &[BitWidth], outcome: impl core::fmt::Display) {
+    let mut symbols = [0; 60];
+    for (symbol, bit) in symbols.iter_mut().zip(frame) {
+        *symbol = station.symbol(*bit);
+    }
+    let symbols = core::str::from_utf8(&symbols[..frame.len()]).unwrap_or("");
 
-                    if buf[16].try_as_bool()? {
-                        hour += 4;
-                        hour_parity = !hour_parity;
-                    }
+    let outcome = log::Text::<48>::new(outcome);
 
-                    if buf[17].try_as_bool()? {
-                        hour += 2;
-                        hour_parity = !hour_parity;
-                    }
+    log::debug!(
+        "FRAME {} {} {}",
+        station.as_str(),
+        symbols,
+        outcome.as_str()
+    );
+}
 
-                    if buf[18].try_as_bool()? {
-                        hour += 1;
-                        hour_parity = !hour_parity;
-                    }
+#[embassy_executor::task]
+async fn jjy_task(mut input: JjyInput, mut pon: Output<'static>, selection: Selection) {
+    let mut station = match selection {
+        Selection::Fixed(station) => station,
+        Selection::Auto => AUTO_STATIONS[0],
+    };
 
-                    if buf[22].try_as_bool()? {
-                        day += 200;
-                    }
+    // Until the first valid frame under the auto setting
+    let mut searching = selection == Selection::Auto;
 
-                    if buf[23].try_as_bool()? {
-                        day += 100;
-                    }
+    let mut profile = PROFILE_WEAK;
+    let mut decoder = AnyDecoder::new(station, PROFILES[profile].tolerance_permille(station));
+    let mut last_frame_at = monotonic::now_ms();
+    let mut station_at = last_frame_at;
 
-                    if buf[25].try_as_bool()? {
-                        day += 80;
-                    }
+    // Time to first fix, from boot and from every receiver restart
+    let mut acquisition_at = Some(last_frame_at);
+    let mut fixes = 0u32;
+    let mut fix_total_ms = 0u64;
 
-                    if buf[26].try_as_bool()? {
-                        day += 40;
-                    }
+    let mut window_at = last_frame_at;
+    let mut window_decodes = 0;
 
-                    if buf[27].try_as_bool()? {
-                        day += 20;
-                    }
+    let carrier = match RECEIVER_POLARITY {
+        Polarity::ActiveLow => Level::Low,
+        Polarity::ActiveHigh => Level::High,
+        Polarity::Auto => detect_polarity(&mut input, station).await,
+    };
+    let idle = opposite(carrier);
 
-                    if buf[28].try_as_bool()? {
-                        day += 10;
-                    }
+    log::info!("Decoding {} ({})", station.as_str(), selection.as_str());
+    state::post(StatusUpdate::Protocol(station.as_str()));
 
-                    if buf[30].try_as_bool()? {
-                        day += 8;
-                    }
+    // Since when the receiver output has been stuck at either level
+    let mut silent_since: Option<u64> = None;
 
-                    if buf[31].try_as_bool()? {
-                        day += 4;
-                    }
+    // The coarse state last posted
+    let mut reception = Reception::Receiving;
 
-                    if buf[32].try_as_bool()? {
-                        day += 2;
-                    }
+    // Start of the last pulse, for the interval the DCF77 minute gap shows in
+    let mut pulse_at: Option<u64> = None;
 
-                    if buf[33].try_as_bool()? {
-                        day += 1;
-                    }
+    // Seconds since 2000 of the last decoded second and its edge, for the
+    // RTC
+    let mut rtc_pending: Option<(u32, u64)> = None;
 
-                    if buf[36].try_as_bool()? != hour_parity {
-                        return None;
-                    }
+    let mut quality = SignalQuality::new();
+    let mut stats = Stats::new();
+    let mut posted_at = 0;
 
-                    if buf[37].try_as_bool()? != minute_parity {
-                        return None;
-                    }
+    let mut progress: Option<u8> = None;
+    let mut progress_at = 0;
 
-                    Some((minute, hour, day))
+    loop {
+        // Console requests wait for the gap between two pulses
+        #[cfg(feature = "console")]
+        while let Ok(request) = console::REQUESTS.try_receive() {
+            match request {
+                console::Request::TolerancePermille(permille) => {
+                    let widest = station.tolerance_permille().1;
+                    decoder.set_tolerance(permille.min(widest));
                 }
+                console::Request::Resync => decoder.reset(),
+                console::Request::ResetStats => {
+                    stats = Stats::new();
+                    state::post(StatusUpdate::Stats(stats));
+                }
+            }
+        }
+
+        let now = monotonic::now_ms();
+        let latest = Reception::at(
+            state::snapshot().last_sync_at(),
+            silent_since.is_some(),
+            now,
+        );
+        if latest != reception {
+            reception = latest;
+            state::post(StatusUpdate::Reception(reception));
+
+            // Edges coming back have to start from a frame marker again, so
+            // the stale part of a frame is never completed with fresh bits
+            if reception == Reception::NoSignal {
+                log::info!("No signal, dropping the frame in progress");
+                decoder.reset();
+                pulse_at = None;
+                rtc_pending = None;
+                stats.lose_signal();
+                state::post(StatusUpdate::Stats(stats));
+                progress = None;
+                state::post(StatusUpdate::FrameProgress(progress));
+            }
+        }
+
+        let carrier_on = wait_for_stable_level(&mut input, carrier);
+        let Ok(up_at) = with_timeout(RECEIVER_SILENCE, carrier_on).await else {
+            fault::raise(Fault::ReceiverSilent);
+
+            // The score has to fall without edges to drive it
+            for _ in 0..RECEIVER_SILENCE.as_secs() {
+                quality.push_silence();
+            }
+            state::post(StatusUpdate::SignalQuality(quality.score()));
+
+            let since = *silent_since.get_or_insert(monotonic::now_ms());
+            if monotonic::now_ms() - since > CALIBRATION_SILENCE_MS {
+                decoder.reset_calibration();
+            }
+            continue;
+        };
+        fault::clear(Fault::ReceiverSilent);
+        silent_since = None;
+
+        // Set only if this edge starts the second after the decoded one;
+        // a missed pulse or a glitch in between makes the decode stale
+        if let Some((seconds, at)) = rtc_pending.take() {
+            match up_at.abs_diff(at + 1000) <= RTC_SET_WINDOW_MS {
+                true => rtc::set(seconds + 1),
+                false => log::debug!("RTC not set, next edge {} ms after decode", up_at - at),
+            }
+        }
+
+        // Some modules latch into a bad AGC state; a power cycle clears it
+        if up_at - last_frame_at > ACQUISITION_TIMEOUT_MS {
+            log::error!("No valid frame for 30 min, power-cycling receiver");
+            fault::raise(Fault::NoValidFrame);
+            pon.set_level(RECEIVER_OFF);
+            Timer::after_millis(1000).await;
+            pon.set_level(RECEIVER_ON);
+
+            last_frame_at = monotonic::now_ms();
+            acquisition_at = Some(last_frame_at);
+            decoder.reset();
+            continue;
+        }
+
+        if searching && up_at - station_at > AUTO_DWELL_MS {
+            let i = AUTO_STATIONS.iter().position(|s| *s == station).unwrap();
+            station = AUTO_STATIONS[(i + 1) % AUTO_STATIONS.len()];
+            log::info!("No valid frame, trying {}", station.as_str());
+            state::post(StatusUpdate::Protocol(station.as_str()));
+
+            decoder = AnyDecoder::new(station, PROFILES[profile].tolerance_permille(station));
+            quality = SignalQuality::new();
+            station_at = up_at;
+            pulse_at = None;
+        }
 
-                let Some((minute, hour, day)) = to_minute_hour_day(&buffer) else {
-                    cursor = 0;
-                    recording = false;
-                    continue;
-                };
-
-                DRAW_CHANNEL
-                    .sender()
-                    .send(StatusUpdate::TimeBaseUpdate(TimeBase {
-                        clock: minute * 60 + hour * 3600 + (cursor as u32),
-                        system_time: up_at,
-                    }))
-                    .await;
-
-                println!("{hour:0>2}:{minute:0>2} (day: {day})");
+        if up_at - window_at >= PROFILE_WINDOW_MINUTES as u64 * 60 * 1000 {
+            let next = match window_decodes {
+                n if n >= PROFILE_WINDOW_MINUTES * 8 / 10 => PROFILE_STRONG,
+                n if n <= PROFILE_WINDOW_MINUTES * 2 / 10 => PROFILE_WEAK,
+                _ => profile,
+            };
+
+            if next != profile {
+                log::info!(
+                    "{}/{} decodes, switching to {} profile",
+                    window_decodes,
+                    PROFILE_WINDOW_MINUTES,
+                    PROFILES[next].name
+                );
+                profile = next;
+                decoder.set_tolerance(PROFILES[profile].tolerance_permille(station));
             }
 
-            buffer[cursor as usize] = bit;
+            window_at = up_at;
+            window_decodes = 0;
+        }
+
+        if buzzer::AUDIO_MONITOR {
+            buzzer::set(true);
+        }
+
+        state::post(StatusUpdate::JJYOn(decoder.cursor()));
+
+        // A carrier held on is as dead as none at all
+        let carrier_off = wait_for_stable_level(&mut input, idle);
+        let down_at = with_timeout(RECEIVER_SILENCE, carrier_off).await;
+
+        if buzzer::AUDIO_MONITOR {
+            buzzer::set(false);
+        }
+
+        let Ok(down_at) = down_at else {
+            fault::raise(Fault::ReceiverSilent);
+            silent_since = Some(monotonic::now_ms());
+            continue;
+        };
+
+        let elapsed_ms = (down_at - up_at) as u32;
+
+        let period_ms = pulse_at.replace(up_at).map(|at| (up_at - at) as u32);
+        let decoded = decoder.push_pulse(elapsed_ms, period_ms);
+        let bit = decoder.last_bit();
+
+        #[cfg(feature = "console")]
+        console::publish_frame(decoder.received());
+        #[cfg(feature = "console")]
+        if let (Some(symbols), Some(confidence)) =
+            (decoder.ended_frame(), decoder.ended_confidence())
+        {
+            console::record_frame(
+                station.as_str(),
+                decoder.last_event(),
+                symbols,
+                confidence,
+                down_at,
+            );
+        }
+
+        state::post(StatusUpdate::JJYOff(bit, station.value(bit)));
+
+        quality.push_pulse(elapsed_ms, bit, station.widths());
+        stats.record(bit, decoder.last_event(), decoder.recovered(), down_at);
+        if down_at - posted_at >= 1000 {
+            state::post(StatusUpdate::SignalQuality(quality.score()));
+            state::post(StatusUpdate::Stats(stats));
+            posted_at = down_at;
+        }
+
+        // A rejected frame shows as the count starting over
+        let received = decoder.is_recording().then(|| decoder.cursor());
+        if received != progress && down_at - progress_at >= PROGRESS_INTERVAL_MS {
+            progress = received;
+            progress_at = down_at;
+            state::post(StatusUpdate::FrameProgress(progress));
+        }
+
+        log::trace!("{} ms ({})", elapsed_ms, bit.as_str());
+
+        let frame = match decoder.last_event() {
+            Event::None => continue,
+            Event::FrameStarted => {
+                log::trace!("Start Bit Detected!");
+
+                let calibration = decoder.calibration();
+                for (i, (bit, _)) in station.widths().iter().enumerate() {
+                    log::trace!(
+                        "Calibration: {} {:?} ms",
+                        bit.as_str(),
+                        calibration.offset_ms(i)
+                    );
+                }
+                continue;
+            }
+            Event::Aborted {
+                reason: Abort::UnknownWidth,
+                ..
+            } => {
+                if let Some(symbols) = decoder.ended_frame() {
+                    log_frame(station, symbols, "unknown-width");
+                }
+                continue;
+            }
+            Event::Aborted {
+                reason: Abort::Layout(reason),
+                ..
+            } => {
+                if let Some(symbols) = decoder.ended_frame() {
+                    log_frame(station, symbols, reason);
+                }
+                continue;
+            }
+            Event::Rejected(e) => {
+                if let Some(symbols) = decoder.ended_frame() {
+                    log_frame(station, symbols, e);
+                }
+                syslog::emit(syslog::Event::SyncFailed(e.as_str()));
+                continue;
+            }
+            Event::Unconfirmed(frame) => frame,
+            Event::Decoded(decoded) => decoded.frame,
+        };
+
+        let decoder::Frame {
+            minute,
+            hour,
+            day,
+            year,
+            weekday,
+            leap_second,
+            summer_time,
+        } = frame;
+
+        // Already range checked by the decoder
+        let (month, day_of_month) = timebase::month_day(year, day).unwrap_or_default();
+
+        if let Some(symbols) = decoder.ended_frame() {
+            // Frames that needed the parity to fill a second say so
+            let outcome = match (decoded.is_some(), decoder.recovered() > 0) {
+                (true, false) => "ok",
+                (true, true) => "ok-recovered",
+                (false, false) => "unconfirmed",
+                (false, true) => "unconfirmed-recovered",
+            };
+            log_frame(
+                station,
+                symbols,
+                format_args!(
+                    "{outcome} {year}-{month:0>2}-{day_of_month:0>2} {hour:0>2}:{minute:0>2}"
+                ),
+            );
+        }
+
+        if leap_second != LeapSecond::None {
+            log::info!("Leap second announced: {:?}", leap_second);
+        }
+
+        let expected = timebase::weekday_of(year, day);
+        if weekday.is_some_and(|w| w != expected) {
+            log::info!("Weekday {:?} disagrees with date ({})", weekday, expected);
+        }
+
+        last_frame_at = up_at;
+        window_decodes += 1;
+        fault::clear(Fault::NoValidFrame);
+        fault::clear(Fault::EdgesDropped);
+
+        if searching {
+            log::info!("Valid frame, staying on {}", station.as_str());
+            searching = false;
+        }
+
+        let Some(decoded) = decoded else {
+            continue;
+        };
+
+        if let Some(started_at) = acquisition_at.take() {
+            let elapsed_ms = up_at - started_at;
+            fixes += 1;
+            fix_total_ms += elapsed_ms;
+
+            log::info!(
+                "Acquired in {} s (average {} s over {} acquisitions)",
+                elapsed_ms / 1000,
+                fix_total_ms / fixes as u64 / 1000,
+                fixes
+            );
+        }
+
+        let base = TimeBase {
+            clock: decoded.second_of_day(),
+            system_time: up_at - EDGE_LATENCY_MS,
+            year,
+            day,
+            weekday,
+            leap_second,
+            drift_ppm: None,
+            synced: true,
+            manual: false,
+            slew: Slew::NONE,
+        }
+        .in_utc(station.utc_offset_minutes(summer_time));
+
+        state::post(StatusUpdate::TimeBaseUpdate(base));
+        syslog::emit(syslog::Event::Synced);
+
+        rtc_pending = Some((
+            timebase::days_since_2000(base.year, base.day) * timebase::SECONDS_PER_DAY + base.clock,
+            up_at,
+        ));
+
+        if summer_time {
+            log::info!("Summer time in effect");
+        }
 
-            cursor += 1;
-            cursor %= 60;
+        if let Some(ppm) = timebase::load().and_then(|b| b.drift_ppm) {
+            log::info!("Drift: {} ppm", ppm);
         }
     }
 }
@@ -0,0 +1,136 @@
+//! Setting the time by hand, for where the signal never comes in, or the
+//! alarm.
+
+use crate::timebase::{self, LeapSecond, Slew, TimeBase};
+use crate::{alarm, settings};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Field {
+    /// Choosing between the clock and the alarm.
+    Target,
+    Hour,
+    Minute,
+    /// Alarm on or off, after its time.
+    Enabled,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Target {
+    Clock,
+    Alarm,
+}
+
+/// A time being entered, one field at a time.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ManualSet {
+    pub field: Field,
+    pub target: Target,
+    pub hour: u8,
+    pub minute: u8,
+    pub enabled: bool,
+    alarm: alarm::Setting,
+}
+
+impl ManualSet {
+    /// Starts with the choice of target, and the time shown, or midnight
+    /// without one, to edit for the clock.
+    pub fn start(base: Option<TimeBase>, alarm: alarm::Setting, now: u64) -> Self {
+        let (hour, minute) = base.map_or((0, 0), |b| {
+            let clock = b.wall_clock(now, settings::zone());
+            (clock.hour, clock.minute)
+        });
+
+        ManualSet {
+            field: Field::Target,
+            target: Target::Clock,
+            hour,
+            minute,
+            enabled: alarm.enabled,
+            alarm,
+        }
+    }
+
+    /// Steps the field being edited, wrapping around.
+    pub fn increment(self) -> Self {
+        match self.field {
+            Field::Target => ManualSet {
+                target: match self.target {
+                    Target::Clock => Target::Alarm,
+                    Target::Alarm => Target::Clock,
+                },
+                ..self
+            },
+            Field::Hour => ManualSet {
+                hour: (self.hour + 1) % 24,
+                ..self
+            },
+            Field::Minute => ManualSet {
+                minute: (self.minute + 1) % 60,
+                ..self
+            },
+            Field::Enabled => ManualSet {
+                enabled: !self.enabled,
+                ..self
+            },
+        }
+    }
+
+    /// Moves on to the next field, or `None` once the last is done: the
+    /// minutes for the clock, on or off for the alarm.
+    pub fn advance(self) -> Option<Self> {
+        let field = match (self.field, self.target) {
+            (Field::Target, Target::Alarm) => {
+                return Some(ManualSet {
+                    field: Field::Hour,
+                    hour: self.alarm.hour,
+                    minute: self.alarm.minute,
+                    ..self
+                });
+            }
+            (Field::Target, Target::Clock) => Field::Hour,
+            (Field::Hour, _) => Field::Minute,
+            (Field::Minute, Target::Alarm) => Field::Enabled,
+            (Field::Minute, Target::Clock) | (Field::Enabled, _) => return None,
+        };
+
+        Some(ManualSet { field, ..self })
+    }
+
+    /// The alarm as entered.
+    pub fn alarm(self) -> alarm::Setting {
+        alarm::Setting {
+            hour: self.hour,
+            minute: self.minute,
+            enabled: self.enabled,
+        }
+    }
+
+    /// The entered time, in the chosen zone, as a timebase starting at
+    /// second 0 at `now`: on the date shown for `base` if there is one,
+    /// 2001-01-01 otherwise, which is still after 2000 in UTC.
+    pub fn timebase(self, base: Option<TimeBase>, now: u64) -> TimeBase {
+        let (year, day) = base
+            .and_then(|b| {
+                let clock = b.wall_clock(now, settings::zone());
+                Some((
+                    clock.year,
+                    timebase::day_of_year(clock.year, clock.month, clock.day)?,
+                ))
+            })
+            .unwrap_or((2001, 1));
+
+        TimeBase {
+            system_time: now,
+            clock: self.hour as u32 * 3600 + self.minute as u32 * 60,
+            year,
+            day,
+            weekday: Some(timebase::weekday_of(year, day)),
+            leap_second: LeapSecond::None,
+            drift_ppm: None,
+            synced: false,
+            manual: true,
+            slew: Slew::NONE,
+        }
+        .in_utc(settings::zone())
+    }
+}
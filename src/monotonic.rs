@@ -0,0 +1,31 @@
+use core::cell::Cell;
+
+use ch32_jjy_clock::monotonic::clock::Clock;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_time::Instant;
+
+static CLOCK: Mutex<CriticalSectionRawMutex, Cell<Clock>> = Mutex::new(Cell::new(Clock::new()));
+
+/// Milliseconds since boot. Every timestamp compared against a `TimeBase`
+/// must come from here rather than from `Instant::now()`, so that a mode
+/// stopping the tick timer only has to be accounted for in one place. None
+/// does yet: the core only sleeps in WFI, where the tick keeps running.
+///
+/// Never goes backwards: a regression of the underlying tick is held at
+/// the last value instead of wrapping a subtraction somewhere downstream.
+pub fn now_ms() -> u64 {
+    let ticks = Instant::now().as_millis();
+
+    CLOCK.lock(|c| {
+        let mut clock = c.get();
+        let now = clock.read(ticks);
+        c.set(clock);
+        now
+    })
+}
+
+/// The `Instant` at which `now_ms` reads `ms`, for `Timer::at`.
+pub fn instant_at(ms: u64) -> Instant {
+    Instant::from_millis(ms)
+}
@@ -0,0 +1,54 @@
+//! The guard behind `monotonic::now_ms`, apart from the tick source.
+
+/// Tick readings in, timestamps that never go backwards out.
+#[derive(Clone, Copy)]
+pub struct Clock {
+    last_ms: u64,
+}
+
+impl Clock {
+    pub const fn new() -> Self {
+        Clock { last_ms: 0 }
+    }
+
+    /// The timestamp for a tick reading of `ticks_ms`. A reading below the
+    /// last one is held at the last timestamp instead of wrapping a
+    /// subtraction somewhere downstream.
+    pub fn read(&mut self, ticks_ms: u64) -> u64 {
+        self.last_ms = ticks_ms.max(self.last_ms);
+        self.last_ms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_the_ticks() {
+        let mut clock = Clock::new();
+        for ticks in [0, 1, 999, 1000, 86_400_000] {
+            assert_eq!(clock.read(ticks), ticks);
+        }
+    }
+
+    #[test]
+    fn holds_a_regression_until_the_ticks_catch_up() {
+        let mut clock = Clock::new();
+        assert_eq!(clock.read(5000), 5000);
+        assert_eq!(clock.read(4000), 5000);
+        assert_eq!(clock.read(0), 5000);
+        assert_eq!(clock.read(5001), 5001);
+    }
+
+    #[test]
+    fn elapsed_time_is_never_negative() {
+        let mut clock = Clock::new();
+        let mut previous = 0;
+        for ticks in [10, 30, 20, u64::MAX, 0, 40] {
+            let now = clock.read(ticks);
+            assert!(now >= previous, "{ticks}");
+            previous = now;
+        }
+    }
+}
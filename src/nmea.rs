@@ -0,0 +1,56 @@
+//! NMEA 0183 time sentences for hosts that take their time from a GPS
+//! receiver, e.g. gpsd or an NTP reference clock.
+//!
+//! Once a second, right after the second boundary of a radio-synced
+//! timebase, the UTC time as
+//!
+//! `$GPZDA,hhmmss.ss,dd,mm,yyyy,00,00*CS`
+//!
+//! and until then, or while the time is only restored or set by hand,
+//!
+//! `$GPTXT,01,01,02,UNSYNCED*CS`
+
+use core::fmt::Write;
+
+use ch32_hal::mode::Blocking;
+use ch32_hal::peripherals::USART2;
+use ch32_hal::usart::UartTx;
+use ch32_jjy_clock::nmea::sentence::Sentence;
+
+use crate::outputs::OutputSink;
+use crate::timebase::WallClock;
+
+/// Sends a sentence on every second, synced or not.
+pub struct Nmea {
+    uart: UartTx<'static, USART2, Blocking>,
+}
+
+impl Nmea {
+    pub fn new(uart: UartTx<'static, USART2, Blocking>) -> Self {
+        Nmea { uart }
+    }
+}
+
+impl OutputSink for Nmea {
+    fn on_second(&mut self, _at_ms: u64, clock: Option<&WallClock>) {
+        let mut sentence = Sentence::new();
+        let written = match clock {
+            Some(c) => write!(
+                sentence,
+                "GPZDA,{:0>2}{:0>2}{:0>2}.{:0>2},{:0>2},{:0>2},{:0>4},00,00",
+                c.hour,
+                c.minute,
+                c.second,
+                c.millisecond / 10,
+                c.day,
+                c.month,
+                c.year
+            ),
+            None => write!(sentence, "GPTXT,01,01,02,UNSYNCED"),
+        };
+
+        if let (Ok(()), Ok(bytes)) = (written, sentence.finish()) {
+            let _ = self.uart.blocking_write(bytes);
+        }
+    }
+}
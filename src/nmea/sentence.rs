@@ -0,0 +1,89 @@
+//! Sentence framing and checksums, apart from the UART.
+
+use core::fmt::{self, Write};
+
+/// XOR of the bytes between `$` and `*`.
+pub fn checksum(body: &[u8]) -> u8 {
+    body.iter().fold(0, |sum, b| sum ^ b)
+}
+
+/// The longest sentence NMEA allows, `$` through CR LF.
+const MAX_SENTENCE: usize = 82;
+
+/// A sentence formatted into a fixed buffer.
+pub struct Sentence {
+    bytes: [u8; MAX_SENTENCE],
+    len: usize,
+}
+
+impl Sentence {
+    pub fn new() -> Self {
+        let mut bytes = [0; MAX_SENTENCE];
+        bytes[0] = b'$';
+        Sentence { bytes, len: 1 }
+    }
+
+    /// Appends the checksum and line end, returning the whole sentence.
+    pub fn finish(&mut self) -> Result<&[u8], fmt::Error> {
+        let sum = checksum(&self.bytes[1..self.len]);
+        write!(self, "*{sum:02X}\r\n")?;
+        Ok(&self.bytes[..self.len])
+    }
+}
+
+impl Write for Sentence {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > MAX_SENTENCE {
+            return Err(fmt::Error);
+        }
+
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A ZDA sentence as quoted in the NMEA 0183 references.
+    const ZDA: &[u8] = b"$GPZDA,201530.00,04,07,2002,00,00*60\r\n";
+
+    #[test]
+    fn checksum_matches_a_known_sentence() {
+        assert_eq!(checksum(&ZDA[1..33]), 0x60);
+        assert_eq!(checksum(b""), 0);
+    }
+
+    #[test]
+    fn finished_sentence_is_framed() {
+        let mut sentence = Sentence::new();
+        write!(
+            sentence,
+            "GPZDA,{}.{:0>2},{:0>2},{:0>2},{},00,00",
+            201530, 0, 4, 7, 2002
+        )
+        .unwrap();
+        assert_eq!(sentence.finish(), Ok(ZDA));
+
+        let mut sentence = Sentence::new();
+        write!(sentence, "GPTXT,01,01,02,UNSYNCED").unwrap();
+        assert_eq!(
+            sentence.finish(),
+            Ok(&b"$GPTXT,01,01,02,UNSYNCED*50\r\n"[..])
+        );
+    }
+
+    #[test]
+    fn overlong_sentences_are_refused() {
+        // Room for the body, but not for the checksum and line end after it
+        let mut sentence = Sentence::new();
+        write!(sentence, "{:A<80}", "").unwrap();
+        assert_eq!(sentence.finish(), Err(fmt::Error));
+
+        let mut sentence = Sentence::new();
+        assert_eq!(write!(sentence, "{:A<82}", ""), Err(fmt::Error));
+    }
+}
@@ -0,0 +1,111 @@
+//! Outputs that follow the seconds of the clock: the PPS pin and the NMEA
+//! sentences, and whatever comes next. Each is an `OutputSink` that `main`
+//! registers; `Outputs::run` waits for the second boundaries and calls
+//! every sink in the order registered, so a new output needs no task or
+//! timer of its own. The display redraws on every state change instead,
+//! and follows `state::watch`.
+
+use embassy_time::Timer;
+
+use crate::monotonic;
+use crate::timebase::{self, TimeBase, WallClock};
+
+/// Sinks one `Outputs` holds.
+const SINKS: usize = 4;
+
+/// Hooks for the clock's seconds; each does nothing unless overridden.
+/// They run on the task that called `Outputs::run`, one after the other,
+/// so none may block for long.
+pub trait OutputSink {
+    /// At each second boundary `at_ms`. `clock` is the UTC time there, or
+    /// `None` while there is no radio-synced timebase to align to and the
+    /// seconds are only a second apart.
+    fn on_second(&mut self, _at_ms: u64, _clock: Option<&WallClock>) {}
+
+    /// At the start of each synced minute, after `on_second`.
+    fn on_minute(&mut self, _clock: &WallClock) {}
+
+    /// Before the first second aligned to a newly accepted decode.
+    fn on_sync(&mut self, _base: &TimeBase) {}
+
+    /// `since_ms` after the boundary, first right after `on_second`, then
+    /// again whenever a sink asked for it. Returns when in the second this
+    /// sink wants to be called next, if at all.
+    fn render(&mut self, _since_ms: u64) -> Option<u64> {
+        None
+    }
+}
+
+pub struct Outputs<'a> {
+    sinks: [Option<&'a mut dyn OutputSink>; SINKS],
+}
+
+impl<'a> Outputs<'a> {
+    pub const fn new() -> Self {
+        Outputs {
+            sinks: [const { None }; SINKS],
+        }
+    }
+
+    /// Adds `sink` after those registered before.
+    pub fn register(&mut self, sink: &'a mut dyn OutputSink) {
+        let free = self.sinks.iter_mut().find(|s| s.is_none());
+        *free.expect("more output sinks than `SINKS`") = Some(sink);
+    }
+
+    fn each(&mut self) -> impl Iterator<Item = &mut &'a mut dyn OutputSink> {
+        self.sinks.iter_mut().flatten()
+    }
+
+    /// Drives the sinks from now on. The next boundary is worked out afresh
+    /// after every second, so a new decode moves the seconds from the next
+    /// one on without cutting a pulse short.
+    pub async fn run(mut self) -> ! {
+        let mut synced_at = None;
+
+        loop {
+            let now = monotonic::now_ms();
+            let base = timebase::load().filter(|b| b.synced);
+
+            if let Some(base) = base
+                && synced_at != Some(base.system_time)
+            {
+                synced_at = Some(base.system_time);
+                self.each().for_each(|sink| sink.on_sync(&base));
+            }
+
+            let (at, clock) = match base {
+                Some(base) => {
+                    let at = base.next_second_at(now);
+                    (at, Some(base.wall_clock(at, 0)))
+                }
+                None => (now + 1000, None),
+            };
+            Timer::at(monotonic::instant_at(at)).await;
+
+            for sink in self.each() {
+                sink.on_second(at, clock.as_ref());
+            }
+            if let Some(clock) = clock.filter(|c| c.second == 0) {
+                self.each().for_each(|sink| sink.on_minute(&clock));
+            }
+
+            // Follow-ups within the second, earliest first; none may hold
+            // up the next boundary
+            let mut since_ms = 0;
+            loop {
+                let next = self
+                    .each()
+                    .filter_map(|sink| sink.render(since_ms))
+                    .filter(|&next| next > since_ms && next < 1000)
+                    .min();
+                let Some(next) = next else {
+                    break;
+                };
+
+                Timer::at(monotonic::instant_at(at + next)).await;
+                since_ms = next;
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use embassy_time::Timer;
+
+use crate::timebase::{self, LeapSecond, SECONDS_PER_DAY, Slew, TimeBase};
+use crate::{backup, fault, log, monotonic, rtc};
+
+// Backup register layout, after the fault record
+const SLOT_DAYS: usize = fault::SLOTS_END;
+const SLOT_SECOND_LOW: usize = SLOT_DAYS + 1;
+// Bit 0 is bit 16 of the second of day, the rest a check value over the
+// time. The fault record and the time take all ten backup registers the
+// part has.
+const SLOT_SECOND_HIGH_CHECK: usize = SLOT_DAYS + 2;
+
+const CHECK_SEED: u16 = 0x5A5A;
+
+fn check(days: u16, second_low: u16) -> u16 {
+    (days ^ second_low ^ CHECK_SEED) << 1
+}
+
+fn save(days: u32, second: u32) {
+    let days = days as u16;
+    let low = second as u16;
+
+    backup::write(SLOT_DAYS, days);
+    backup::write(SLOT_SECOND_LOW, low);
+    backup::write(
+        SLOT_SECOND_HIGH_CHECK,
+        check(days, low) | (second >> 16) as u16,
+    );
+}
+
+fn unsynced(days: u32, second: u32) -> TimeBase {
+    let (year, day) = timebase::date_since_2000(days);
+
+    TimeBase {
+        system_time: monotonic::now_ms(),
+        clock: second,
+        year,
+        day,
+        weekday: Some(timebase::weekday_of(year, day)),
+        leap_second: LeapSecond::None,
+        drift_ppm: None,
+        synced: false,
+        manual: false,
+        slew: Slew::NONE,
+    }
+}
+
+/// The time from before the reset, as an unsynced timebase starting now.
+/// An RTC that ran through the reset on VBAT knows the current time;
+/// otherwise the last saved time is used as is, since nobody knows how
+/// long the outage was.
+pub fn restore() -> Option<TimeBase> {
+    if let Some(seconds) = rtc::read() {
+        let (days, second) = (seconds / SECONDS_PER_DAY, seconds % SECONDS_PER_DAY);
+        log::info!(
+            "Restored day {} second {} from the RTC (unsynced)",
+            days,
+            second
+        );
+        return Some(unsynced(days, second));
+    }
+
+    let days = backup::read(SLOT_DAYS);
+    let low = backup::read(SLOT_SECOND_LOW);
+    let high_check = backup::read(SLOT_SECOND_HIGH_CHECK);
+
+    if high_check & !1 != check(days, low) {
+        return None;
+    }
+
+    let second = (high_check as u32 & 1) << 16 | low as u32;
+    if second >= SECONDS_PER_DAY {
+        return None;
+    }
+
+    log::info!("Restored day {} second {} (unsynced)", days, second);
+    Some(unsynced(days as u32, second))
+}
+
+/// Saves the current time once a minute. Backup registers don't wear, but
+/// there is no point in writing more often than a resync could matter.
+#[embassy_executor::task]
+pub async fn persist_task() {
+    loop {
+        Timer::after_secs(60).await;
+
+        let Some(base) = timebase::load() else {
+            continue;
+        };
+
+        let now = monotonic::now_ms();
+        save(base.days_since_2000(now), base.second_of_day(now));
+    }
+}
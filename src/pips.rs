@@ -0,0 +1,66 @@
+//! Hourly time signal on the buzzer: a short pip at the top of each hour,
+//! or NHK style, three short pips at :59:57 to :59:59 and a long one
+//! starting on the hour. Only a fresh radio sync is trusted for it, and a
+//! ringing alarm takes the buzzer first.
+
+pub use ch32_jjy_clock::pips::mode::Mode;
+use embassy_time::Timer;
+
+use crate::{alarm, buzzer, log, monotonic, settings, state, timebase};
+
+pub fn mode() -> Mode {
+    settings::get().pips
+}
+
+pub fn set_mode(mode: Mode) {
+    settings::update(|s| s.pips = mode);
+}
+
+/// Every second boundary is waited for from this long before the first
+/// pip, so drift corrections in between are followed.
+const WINDOW_S: u32 = 5;
+
+/// Longest sleep between checks of the mode and the sync.
+const IDLE_S: u32 = 60;
+
+#[embassy_executor::task]
+pub async fn pips_task() {
+    loop {
+        let mode = mode();
+        let now = monotonic::now_ms();
+        let fresh = state::snapshot()
+            .last_sync_at()
+            .is_some_and(|at| now - at < timebase::STALE_AFTER_MS);
+        let base = timebase::load().filter(|b| b.synced && fresh);
+
+        let Some(base) = base.filter(|_| mode != Mode::Off) else {
+            Timer::after_secs(IDLE_S as u64).await;
+            continue;
+        };
+
+        // Sleep through most of the hour
+        let clock = base.wall_clock(now, settings::zone());
+        let until_hour = 3600 - (clock.minute as u32 * 60 + clock.second as u32);
+        let until_window = until_hour.saturating_sub(mode.lead_s() + WINDOW_S);
+        if until_window > 0 {
+            Timer::after_secs(until_window.min(IDLE_S) as u64).await;
+            continue;
+        }
+
+        let at = base.next_second_at(now);
+        Timer::at(monotonic::instant_at(at)).await;
+
+        let clock = base.wall_clock(at, settings::zone());
+        let Some(ms) = mode.pip_ms(clock.minute, clock.second) else {
+            continue;
+        };
+        if alarm::is_ringing() {
+            continue;
+        }
+
+        buzzer::set(true);
+        log::debug!("Pip at {} ms, due {} ms", monotonic::now_ms(), at);
+        Timer::after_millis(ms).await;
+        buzzer::set(false);
+    }
+}
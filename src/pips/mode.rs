@@ -0,0 +1,39 @@
+//! What the hourly time signal sounds like, apart from the buzzer.
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mode {
+    Off,
+    Hourly,
+    Nhk,
+}
+
+impl Mode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mode::Off => "off",
+            Mode::Hourly => "hourly",
+            Mode::Nhk => "nhk",
+        }
+    }
+
+    /// Seconds before the hour the first pip comes.
+    pub fn lead_s(self) -> u32 {
+        match self {
+            Mode::Nhk => 3,
+            Mode::Off | Mode::Hourly => 0,
+        }
+    }
+
+    /// Length of the pip starting at `minute:second`, if one does.
+    pub fn pip_ms(self, minute: u8, second: u8) -> Option<u64> {
+        match (self, minute, second) {
+            (Mode::Hourly, 0, 0) => Some(SHORT_PIP_MS),
+            (Mode::Nhk, 59, 57..=59) => Some(SHORT_PIP_MS),
+            (Mode::Nhk, 0, 0) => Some(LONG_PIP_MS),
+            _ => None,
+        }
+    }
+}
+
+const SHORT_PIP_MS: u64 = 100;
+const LONG_PIP_MS: u64 = 1000;
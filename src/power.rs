@@ -0,0 +1,34 @@
+//! Night blanking: between `BLANK_START_HOUR` and `BLANK_END_HOUR` local
+//! time, the display and its backlight are off while reception and
+//! timekeeping carry on. A button press brings them back for `WAKE_MS`.
+//!
+//! Between events the core sleeps in WFI from the executor's idle loop.
+//! The deeper Stop and Standby modes would halt TIM1 and TIM2 too, which
+//! time the pulses and drive embassy-time, so they are not used.
+
+use crate::settings;
+use crate::state::ClockState;
+use crate::timebase::TimeBase;
+
+const NIGHT_BLANK: bool = true;
+const BLANK_START_HOUR: u8 = 1;
+const BLANK_END_HOUR: u8 = 6;
+
+/// How long a press shows the display during the blank hours.
+const WAKE_MS: u64 = 10_000;
+
+/// Whether the display is blanked for the night at `now`. An unset clock
+/// never blanks.
+pub fn is_blanked(state: &ClockState, base: Option<TimeBase>, now: u64) -> bool {
+    let Some(base) = base.filter(|_| NIGHT_BLANK) else {
+        return false;
+    };
+
+    let hour = base.wall_clock(now, settings::zone()).hour;
+    let night = match BLANK_START_HOUR <= BLANK_END_HOUR {
+        true => (BLANK_START_HOUR..BLANK_END_HOUR).contains(&hour),
+        false => hour >= BLANK_START_HOUR || hour < BLANK_END_HOUR,
+    };
+
+    night && !state.woken_at.is_some_and(|at| now - at < WAKE_MS)
+}
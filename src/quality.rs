@@ -0,0 +1,68 @@
+//! Reception quality as a 0-8 score, for positioning the antenna.
+
+use crate::decoder::BitWidth;
+
+/// Pulses the score is taken over, about a minute.
+const WINDOW: usize = 60;
+
+/// Mean deviation from the nominal width at which the score bottoms out,
+/// in per-mille. About where the weak profile stops classifying.
+const DEVIATION_FLOOR_PERMILLE: u32 = 250;
+
+pub const MAX_SCORE: u8 = 8;
+
+/// Sliding window over the last `WINDOW` pulses, or seconds without one.
+pub struct SignalQuality {
+    // Deviation from the nearest nominal width of each pulse in per-mille,
+    // `None` for a pulse that didn't classify or a second without any
+    samples: [Option<u16>; WINDOW],
+    next: usize,
+}
+
+impl SignalQuality {
+    pub const fn new() -> Self {
+        SignalQuality {
+            samples: [None; WINDOW],
+            next: 0,
+        }
+    }
+
+    /// Adds a pulse `width_ms` long that classified as `bit`, against the
+    /// protocol's nominal `widths`.
+    pub fn push_pulse(&mut self, width_ms: u32, bit: BitWidth, widths: &[(BitWidth, u32)]) {
+        // Nearest rather than `bit`'s own, which merged symbols don't have
+        let deviation = widths
+            .iter()
+            .map(|(_, nominal)| width_ms.abs_diff(*nominal) * 1000 / nominal)
+            .min()
+            .filter(|_| bit != BitWidth::Unknown)
+            .map(|permille| permille.min(u16::MAX as u32) as u16);
+
+        self.push(deviation);
+    }
+
+    /// Counts a second that passed without a pulse.
+    pub fn push_silence(&mut self) {
+        self.push(None);
+    }
+
+    fn push(&mut self, sample: Option<u16>) {
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % WINDOW;
+    }
+
+    /// `MAX_SCORE` for every pulse classified and right on its nominal
+    /// width, down to 0 as pulses are lost or smeared.
+    pub fn score(&self) -> u8 {
+        let valid = self.samples.iter().flatten();
+        let count = valid.clone().count() as u32;
+        if count == 0 {
+            return 0;
+        }
+
+        let mean_deviation = valid.map(|d| *d as u32).sum::<u32>() / count;
+        let sharpness = DEVIATION_FLOOR_PERMILLE.saturating_sub(mean_deviation);
+
+        (MAX_SCORE as u32 * count * sharpness / (WINDOW as u32 * DEVIATION_FLOOR_PERMILLE)) as u8
+    }
+}
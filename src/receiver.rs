@@ -0,0 +1,300 @@
+//! Edges of the JJY receiver output, timestamped as close to the pin as
+//! the board allows: by a TIM1 input capture on PA9, or with the
+//! `exti-timing` feature, by the task that wakes up on the EXTI line. With
+//! `replay`, the edges come from `replay::replay_task` instead. With
+//! `cross-check`, the capture times them, and `cross_check_task` times the
+//! same edges from the EXTI line to compare.
+
+#[cfg(not(any(feature = "exti-timing", feature = "replay", feature = "cross-check")))]
+use ch32_hal::gpio::Input;
+#[cfg(not(any(feature = "exti-timing", feature = "replay")))]
+use ch32_hal::gpio::Pull;
+#[cfg(feature = "cross-check")]
+use ch32_hal::peripherals::EXTI9;
+#[cfg(not(any(feature = "exti-timing", feature = "replay")))]
+use ch32_hal::peripherals::{PA9, TIM1};
+#[cfg(not(any(feature = "exti-timing", feature = "replay")))]
+use ch32_hal::{Peri, pac, rcc};
+#[cfg(not(feature = "exti-timing"))]
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+#[cfg(not(feature = "exti-timing"))]
+use embassy_sync::channel::Channel;
+
+#[cfg(not(feature = "exti-timing"))]
+use core::sync::atomic::{AtomicU32, Ordering};
+
+#[cfg(any(feature = "exti-timing", feature = "cross-check"))]
+use ch32_hal::exti::ExtiInput;
+#[cfg(feature = "cross-check")]
+use ch32_jjy_clock::receiver::cross_check::CrossCheck;
+use embassy_futures::select::{Either, select};
+use embassy_time::Timer;
+
+#[cfg(not(feature = "exti-timing"))]
+use crate::fault::{self, Fault};
+#[cfg(not(feature = "exti-timing"))]
+use crate::log;
+use crate::{monotonic, watchdog};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Edge {
+    /// Level of the output after the edge.
+    pub high: bool,
+    /// `monotonic::now_ms` at the edge.
+    pub at_ms: u64,
+}
+
+pub struct JjyInput {
+    #[cfg(not(any(feature = "exti-timing", feature = "replay", feature = "cross-check")))]
+    _pin: Input<'static>,
+    #[cfg(feature = "exti-timing")]
+    exti: ExtiInput<'static>,
+    // `DROPPED` when it was last looked at
+    #[cfg(not(feature = "exti-timing"))]
+    dropped: u32,
+    unread: Option<Edge>,
+}
+
+impl JjyInput {
+    #[cfg(feature = "exti-timing")]
+    pub fn new(exti: ExtiInput<'static>) -> Self {
+        JjyInput { exti, unread: None }
+    }
+
+    /// The next edge. Beats the JJY heartbeat while it waits, since a quiet
+    /// receiver doesn't mean a stuck task.
+    pub async fn next_edge(&mut self) -> Edge {
+        if let Some(edge) = self.unread.take() {
+            return edge;
+        }
+
+        loop {
+            watchdog::beat(watchdog::Task::Jjy);
+
+            let heartbeat = Timer::after_millis(watchdog::HEARTBEAT_MS);
+            if let Either::First(edge) = select(self.wait_for_edge(), heartbeat).await {
+                return edge;
+            }
+        }
+    }
+
+    #[cfg(feature = "exti-timing")]
+    async fn wait_for_edge(&mut self) -> Edge {
+        self.exti.wait_for_any_edge().await;
+        Edge {
+            high: self.exti.is_high(),
+            at_ms: monotonic::now_ms(),
+        }
+    }
+
+    #[cfg(not(feature = "exti-timing"))]
+    async fn wait_for_edge(&mut self) -> Edge {
+        let edge = EDGES.receive().await;
+
+        let dropped = DROPPED.load(Ordering::Relaxed);
+        if dropped != self.dropped {
+            log::error!(
+                "Edge queue full, {} edges dropped",
+                dropped.wrapping_sub(self.dropped)
+            );
+            self.dropped = dropped;
+            fault::raise(Fault::EdgesDropped);
+        }
+
+        edge
+    }
+
+    /// Hands `edge` back to the next `next_edge` call.
+    pub fn unread(&mut self, edge: Edge) {
+        self.unread = Some(edge);
+    }
+}
+
+/// Capture counter rate; the 16-bit counter wraps after 6.5 s, far longer
+/// than ISR latency.
+#[cfg(not(any(feature = "exti-timing", feature = "replay")))]
+const CAPTURE_HZ: u32 = 10_000;
+
+#[cfg(not(feature = "exti-timing"))]
+static EDGES: Channel<CriticalSectionRawMutex, Edge, 16> = Channel::new();
+
+/// The captured edges again, for `cross_check_task`.
+#[cfg(feature = "cross-check")]
+static CAPTURED: Channel<CriticalSectionRawMutex, Edge, 16> = Channel::new();
+
+/// Edges that found `EDGES` full, ever.
+#[cfg(not(feature = "exti-timing"))]
+static DROPPED: AtomicU32 = AtomicU32::new(0);
+
+#[cfg(not(feature = "exti-timing"))]
+fn queue(edge: Edge) {
+    if EDGES.try_send(edge).is_err() {
+        // No read-modify-write atomics on this core, but whoever queues is
+        // the only writer: the capture ISR, or the replay task
+        DROPPED.store(
+            DROPPED.load(Ordering::Relaxed).wrapping_add(1),
+            Ordering::Relaxed,
+        );
+    }
+
+    // One lost here only shows up as unmatched in the cross-check summary
+    #[cfg(feature = "cross-check")]
+    let _ = CAPTURED.try_send(edge);
+}
+
+#[cfg(feature = "replay")]
+impl JjyInput {
+    /// Reads the edges `replay` queues.
+    pub fn replayed() -> Self {
+        JjyInput {
+            dropped: 0,
+            unread: None,
+        }
+    }
+}
+
+/// Queues `edge` as if PA9 had captured it.
+#[cfg(feature = "replay")]
+pub fn replay(edge: Edge) {
+    queue(edge);
+}
+
+#[cfg(not(any(feature = "exti-timing", feature = "replay")))]
+impl JjyInput {
+    /// Captures PA9 (TIM1_CH2) on alternating edges.
+    #[cfg(not(feature = "cross-check"))]
+    pub fn new(pin: Peri<'static, PA9>, tim: Peri<'static, TIM1>) -> Self {
+        let pin = Input::new(pin, Pull::None);
+        start_capture(tim, pin.is_high());
+
+        JjyInput {
+            _pin: pin,
+            dropped: 0,
+            unread: None,
+        }
+    }
+
+    /// Captures PA9 like `new`, and hands the pin back on its EXTI line
+    /// for `cross_check_task`.
+    #[cfg(feature = "cross-check")]
+    pub fn new(
+        pin: Peri<'static, PA9>,
+        tim: Peri<'static, TIM1>,
+        line: Peri<'static, EXTI9>,
+    ) -> (Self, ExtiInput<'static>) {
+        let exti = ExtiInput::new(pin, line, Pull::None);
+        start_capture(tim, exti.is_high());
+
+        let input = JjyInput {
+            dropped: 0,
+            unread: None,
+        };
+        (input, exti)
+    }
+}
+
+/// Starts TIM1 capturing CH2, on the edge that leaves the level PA9 is
+/// at, `high`.
+#[cfg(not(any(feature = "exti-timing", feature = "replay")))]
+fn start_capture(_tim: Peri<'static, TIM1>, high: bool) {
+    pac::RCC.apb2pcenr().modify(|w| w.set_tim1en(true));
+
+    let tim = pac::TIM1;
+    let pclk = rcc::clocks().pclk2.0;
+    tim.psc()
+        .write(|w| w.set_psc((pclk / CAPTURE_HZ - 1) as u16));
+    tim.atrlr().write(|w| w.set_atrlr(0xFFFF));
+
+    // CH2 captures TI2, on whichever edge leaves the current level
+    tim.chctlr1_input().modify(|w| w.set_ccs(1, 0b01));
+    tim.ccer().modify(|w| {
+        w.set_ccp(1, high);
+        w.set_cce(1, true);
+    });
+    tim.dmaintenr().modify(|w| w.set_ccie(1, true));
+    tim.ctlr1().modify(|w| w.set_cen(true));
+
+    unsafe { qingke::pfic::enable_interrupt(pac::Interrupt::TIM1_CC as u8) };
+}
+
+#[cfg(not(any(feature = "exti-timing", feature = "replay")))]
+#[qingke_rt::interrupt]
+fn TIM1_CC() {
+    let tim = pac::TIM1;
+
+    // Reading the capture clears the flag
+    let captured = tim.chcvr(1).read().chcvr();
+    let counter = tim.cnt().read().cnt();
+    let falling = tim.ccer().read().ccp(1);
+
+    // Capture next on whichever edge leaves the level PA9 is at now, rather
+    // than flipping blindly: one edge missed would invert every later one
+    let high = pac::GPIOA.indr().read().idr(9);
+    tim.ccer().modify(|w| w.set_ccp(1, high));
+
+    let now = monotonic::now_ms();
+    let latency_ms = counter.wrapping_sub(captured) as u64 * 1000 / CAPTURE_HZ as u64;
+    queue(Edge {
+        high: !falling,
+        at_ms: now.saturating_sub(latency_ms),
+    });
+
+    // The level went back before the polarity followed; the edge back
+    // came some time before now
+    if high == falling {
+        queue(Edge { high, at_ms: now });
+    }
+}
+
+/// Width errors up to this are the 1 ms timestamps rounding either way.
+#[cfg(feature = "cross-check")]
+const WIDTH_TOLERANCE_MS: i64 = 2;
+
+/// Pairs between summaries, about a minute of pulses.
+#[cfg(feature = "cross-check")]
+const SUMMARY_PAIRS: u32 = 120;
+
+/// Times each edge again when the EXTI line wakes it, pairs it with the
+/// capture of the same edge, and logs the widths the two disagree on and
+/// how late the wake-ups were.
+#[cfg(feature = "cross-check")]
+#[embassy_executor::task]
+pub async fn cross_check_task(mut exti: ExtiInput<'static>) {
+    let mut check = CrossCheck::new();
+    let mut pairs = 0;
+
+    loop {
+        // The wake-up first: the capture waits in the queue, while an edge
+        // that comes before the line is armed again wakes nothing
+        let pair = match select(exti.wait_for_any_edge(), CAPTURED.receive()).await {
+            Either::First(()) => check.woken(exti.is_high(), monotonic::now_ms()),
+            Either::Second(edge) => check.captured(edge.high, edge.at_ms),
+        };
+        let Some(pair) = pair else {
+            continue;
+        };
+
+        if let Some(error) = pair.width_error_ms
+            && error.abs() > WIDTH_TOLERANCE_MS
+        {
+            log::info!(
+                "Cross-check: level {} measured {} ms off by EXTI, {} ms late",
+                pair.high as u8,
+                error,
+                pair.lag_ms
+            );
+        }
+
+        pairs += 1;
+        if pairs % SUMMARY_PAIRS == 0 {
+            let summary = check.take_summary();
+            log::info!(
+                "Cross-check: {} pairs, {} unmatched, lag up to {} ms, width off by up to {} ms",
+                summary.pairs,
+                summary.unmatched,
+                summary.max_lag_ms,
+                summary.max_width_error_ms
+            );
+        }
+    }
+}
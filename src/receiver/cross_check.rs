@@ -0,0 +1,222 @@
+//! Pairs each receiver edge as the TIM1 capture timestamped it with the
+//! same edge as the EXTI wake-up did, for the `cross-check` build. The
+//! capture is taken in hardware and the wake-up waits on the executor, so
+//! the lag between the two is executor latency, and a lag that changes
+//! from one edge to the next is pulse width the EXTI path gets wrong.
+//! Hardware-free.
+
+/// Timestamps further apart than this are not the same edge; JJY and
+/// DCF77 hold each level for at least 100 ms.
+pub const MATCH_MS: u64 = 50;
+
+/// One edge, seen by both paths.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Pair {
+    /// Level of the output after the edge.
+    pub high: bool,
+    /// How much later the wake-up was timestamped than the capture.
+    pub lag_ms: i64,
+    /// The interval since the previous edge as the wake-ups measured it,
+    /// less the interval the captures did. `None` after an edge only one
+    /// path saw, when there is no interval both measured.
+    pub width_error_ms: Option<i64>,
+}
+
+/// What the pairs showed since the last `take_summary`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Summary {
+    pub pairs: u32,
+    /// Edges only one path saw.
+    pub unmatched: u32,
+    pub max_lag_ms: i64,
+    /// The largest `width_error_ms` either way.
+    pub max_width_error_ms: i64,
+}
+
+/// Level and timestamp of an edge still waiting for the other path.
+type Seen = (bool, u64);
+
+pub struct CrossCheck {
+    captured: Option<Seen>,
+    woken: Option<Seen>,
+    last_lag_ms: Option<i64>,
+    summary: Summary,
+}
+
+impl CrossCheck {
+    pub const fn new() -> Self {
+        CrossCheck {
+            captured: None,
+            woken: None,
+            last_lag_ms: None,
+            summary: Summary {
+                pairs: 0,
+                unmatched: 0,
+                max_lag_ms: 0,
+                max_width_error_ms: 0,
+            },
+        }
+    }
+
+    /// Takes an edge the capture timestamped at `at_ms`.
+    pub fn captured(&mut self, high: bool, at_ms: u64) -> Option<Pair> {
+        let woken = self.woken.take();
+        match self.counterpart(woken, high, at_ms) {
+            Some(woken_ms) => Some(self.pair(high, at_ms, woken_ms)),
+            None => {
+                let stale = self.captured.replace((high, at_ms));
+                self.unmatched(stale);
+                None
+            }
+        }
+    }
+
+    /// Takes an edge the EXTI wake-up timestamped at `at_ms`.
+    pub fn woken(&mut self, high: bool, at_ms: u64) -> Option<Pair> {
+        let captured = self.captured.take();
+        match self.counterpart(captured, high, at_ms) {
+            Some(captured_ms) => Some(self.pair(high, captured_ms, at_ms)),
+            None => {
+                let stale = self.woken.replace((high, at_ms));
+                self.unmatched(stale);
+                None
+            }
+        }
+    }
+
+    /// The summary so far, starting the next one.
+    pub fn take_summary(&mut self) -> Summary {
+        core::mem::take(&mut self.summary)
+    }
+
+    /// When `waiting` from the other path is the same edge, its timestamp.
+    /// Otherwise it never will be: the path that saw it missed the edge
+    /// this one saw since, or this one missed it.
+    fn counterpart(&mut self, waiting: Option<Seen>, high: bool, at_ms: u64) -> Option<u64> {
+        match waiting {
+            Some((level, seen_ms)) if level == high && seen_ms.abs_diff(at_ms) <= MATCH_MS => {
+                Some(seen_ms)
+            }
+            waiting => {
+                self.unmatched(waiting);
+                None
+            }
+        }
+    }
+
+    fn unmatched(&mut self, edge: Option<Seen>) {
+        if edge.is_some() {
+            self.summary.unmatched += 1;
+            self.last_lag_ms = None;
+        }
+    }
+
+    fn pair(&mut self, high: bool, captured_ms: u64, woken_ms: u64) -> Pair {
+        // Whatever this path still held waits for an edge long gone
+        let stale = [self.captured.take(), self.woken.take()];
+        for edge in stale {
+            self.unmatched(edge);
+        }
+
+        let lag_ms = woken_ms as i64 - captured_ms as i64;
+        let width_error_ms = self.last_lag_ms.map(|last| lag_ms - last);
+        self.last_lag_ms = Some(lag_ms);
+
+        let summary = &mut self.summary;
+        summary.pairs += 1;
+        summary.max_lag_ms = summary.max_lag_ms.max(lag_ms);
+        if let Some(error) = width_error_ms {
+            summary.max_width_error_ms = summary.max_width_error_ms.max(error.abs());
+        }
+
+        Pair {
+            high,
+            lag_ms,
+            width_error_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_an_edge_from_either_path_first() {
+        let mut check = CrossCheck::new();
+        assert_eq!(check.captured(true, 1000), None);
+        assert_eq!(
+            check.woken(true, 1003),
+            Some(Pair {
+                high: true,
+                lag_ms: 3,
+                width_error_ms: None,
+            })
+        );
+
+        assert_eq!(check.woken(false, 1505), None);
+        assert_eq!(
+            check.captured(false, 1500),
+            Some(Pair {
+                high: false,
+                lag_ms: 5,
+                width_error_ms: Some(2),
+            })
+        );
+    }
+
+    #[test]
+    fn a_changing_lag_is_width_error() {
+        let mut check = CrossCheck::new();
+        let mut errors = Vec::new();
+        for (i, lag) in [1, 1, 9, 2].into_iter().enumerate() {
+            let at = 1000 + 500 * i as u64;
+            let high = i % 2 == 0;
+            check.captured(high, at);
+            errors.push(check.woken(high, at + lag).unwrap().width_error_ms);
+        }
+        assert_eq!(errors, [None, Some(0), Some(8), Some(-7)]);
+
+        assert_eq!(
+            check.take_summary(),
+            Summary {
+                pairs: 4,
+                unmatched: 0,
+                max_lag_ms: 9,
+                max_width_error_ms: 8,
+            }
+        );
+        assert_eq!(check.take_summary(), Summary::default());
+    }
+
+    #[test]
+    fn an_edge_one_path_missed_is_unmatched() {
+        let mut check = CrossCheck::new();
+        check.captured(true, 1000);
+        check.woken(true, 1002);
+
+        // The wake-up for the falling edge never came
+        assert_eq!(check.captured(false, 1200), None);
+        assert_eq!(check.captured(true, 2000), None);
+        let pair = check.woken(true, 2002).unwrap();
+        assert_eq!(pair.width_error_ms, None);
+
+        // Nor the capture of this one
+        assert_eq!(check.woken(false, 2804), None);
+        assert_eq!(check.captured(true, 3000), None);
+        assert_eq!(check.woken(true, 3004).unwrap().width_error_ms, None);
+
+        let summary = check.take_summary();
+        assert_eq!((summary.pairs, summary.unmatched), (3, 2));
+    }
+
+    #[test]
+    fn edges_too_far_apart_or_at_other_levels_do_not_pair() {
+        let mut check = CrossCheck::new();
+        check.captured(true, 1000);
+        assert_eq!(check.woken(true, 1000 + MATCH_MS + 1), None);
+        assert_eq!(check.captured(false, 1100), None);
+        assert_eq!(check.woken(true, 1101), None);
+        assert_eq!(check.take_summary().unmatched, 3);
+    }
+}
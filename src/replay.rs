@@ -0,0 +1,120 @@
+//! Bench replay, with the `replay` feature: a task plays recorded JJY
+//! minutes into the receiver's edge queue in place of PA9, one pulse per
+//! second, so classification, frame assembly, the time base and the
+//! display all run as they would on air. The recordings are encoded at
+//! build time by `decoder::encode_jjy`.
+
+use ch32_hal::gpio::Level;
+use embassy_time::Timer;
+
+use crate::decoder::{self, Frame};
+use crate::receiver::{self, Edge};
+use crate::timebase::LeapSecond;
+use crate::{log, monotonic};
+
+#[allow(dead_code)] // only one of these is selected
+enum Recording {
+    /// Consecutive minutes as transmitted.
+    Clean,
+    /// The same with every pulse off by up to 8%, and one pulse in the
+    /// second minute that is neither a 1 nor a 0.
+    Noisy,
+    /// The same with the minute parity of the second minute wrong.
+    ParityError,
+}
+
+const RECORDING: Recording = Recording::Clean;
+
+/// Starts over after the last minute instead of going quiet.
+const LOOP: bool = true;
+
+const MINUTES: usize = 4;
+
+/// Minute `n` of the recording: 2025-05-03, a Saturday, from 12:34 JST.
+const fn minute(n: usize) -> [u16; 60] {
+    decoder::encode_jjy(&Frame {
+        minute: 34 + n as u32,
+        hour: 12,
+        day: 123,
+        year: 2025,
+        weekday: Some(6),
+        leap_second: LeapSecond::None,
+        summer_time: false,
+    })
+}
+
+const CLEAN: [[u16; 60]; MINUTES] = [minute(0), minute(1), minute(2), minute(3)];
+
+const NOISY: [[u16; 60]; MINUTES] = {
+    let mut minutes = CLEAN;
+
+    let mut m = 0;
+    while m < MINUTES {
+        let mut s = 0;
+        while s < 60 {
+            // Spread over -80‰ to +80‰, within even the strong tolerance
+            let permille = ((m * 60 + s) * 37 % 161) as i32 - 80;
+            let width = minutes[m][s] as i32;
+            minutes[m][s] = (width + width * permille / 1000) as u16;
+            s += 1;
+        }
+        m += 1;
+    }
+
+    // Between the marker and 1 windows at either tolerance
+    minutes[1][25] = 300;
+    minutes
+};
+
+const PARITY_ERROR: [[u16; 60]; MINUTES] = {
+    let mut minutes = CLEAN;
+    minutes[1][37] = match minutes[1][37] {
+        500 => 800,
+        _ => 500,
+    };
+    minutes
+};
+
+/// Waits for `at_ms` and queues the edge there.
+async fn edge(high: bool, at_ms: u64) {
+    Timer::at(monotonic::instant_at(at_ms)).await;
+    receiver::replay(Edge { high, at_ms });
+}
+
+/// Plays a pulse of `width_ms` at the second starting `at_ms`.
+async fn pulse(carrier: Level, at_ms: u64, width_ms: u16) {
+    let high = matches!(carrier, Level::High);
+    edge(high, at_ms).await;
+    edge(!high, at_ms + width_ms as u64).await;
+}
+
+/// Plays `RECORDING` with the receiver output at `carrier` during pulses.
+#[embassy_executor::task]
+pub async fn replay_task(carrier: Level) {
+    let minutes = match RECORDING {
+        Recording::Clean => &CLEAN,
+        Recording::Noisy => &NOISY,
+        Recording::ParityError => &PARITY_ERROR,
+    };
+
+    log::info!("Replaying {} recorded JJY minutes", MINUTES);
+
+    // The first minute opens on the P0 of the one before
+    let mut second_at = monotonic::now_ms() + 1000;
+    pulse(carrier, second_at, minutes[MINUTES - 1][59]).await;
+
+    loop {
+        for widths in minutes {
+            for width in widths {
+                second_at += 1000;
+                pulse(carrier, second_at, *width).await;
+            }
+        }
+
+        if !LOOP {
+            break;
+        }
+    }
+
+    log::info!("Replay finished");
+}
@@ -0,0 +1,100 @@
+use ch32_hal::pac;
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::timebase::SECONDS_PER_DAY;
+
+/// The counter holds seconds since 2000-01-01 00:00 UTC. Values
+/// before 2020 mean it was never set.
+const SET_AFTER: u32 = 20 * 365 * SECONDS_PER_DAY;
+
+/// The LSE crystal takes 1-2 s to start. Past this, the board has none
+/// (not every board fits one).
+const LSE_STARTUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+const LSE_POLL_MS: u64 = 10;
+
+fn wait_write_done() {
+    while !pac::RTC.ctlrl().read().rtoff() {}
+}
+
+fn configure(f: impl FnOnce()) {
+    wait_write_done();
+    pac::RTC.ctlrl().modify(|w| w.set_cnf(true));
+    f();
+    pac::RTC.ctlrl().modify(|w| w.set_cnf(false));
+    wait_write_done();
+}
+
+/// Starts the RTC from the 32.768 kHz LSE unless it kept running through
+/// the reset on VBAT. Needs `backup::init` first. Returns whether the RTC
+/// is usable at all.
+pub async fn init() -> bool {
+    if is_running() {
+        return true;
+    }
+
+    pac::RCC.bdctlr().modify(|w| w.set_lseon(true));
+    let started = Instant::now();
+    while !pac::RCC.bdctlr().read().lserdy() {
+        if started.elapsed() > LSE_STARTUP_TIMEOUT {
+            pac::RCC.bdctlr().modify(|w| w.set_lseon(false));
+            return false;
+        }
+        Timer::after_millis(LSE_POLL_MS).await;
+    }
+
+    pac::RCC.bdctlr().modify(|w| {
+        w.set_rtcsel(0b01);
+        w.set_rtcen(true);
+    });
+
+    // Sync the APB view of the registers before touching them
+    pac::RTC.ctlrl().modify(|w| w.set_rsf(false));
+    while !pac::RTC.ctlrl().read().rsf() {}
+
+    configure(|| {
+        pac::RTC.psch().write(|w| w.set_prl(0));
+        pac::RTC.pscl().write(|w| w.set_prl(32768 - 1));
+    });
+
+    true
+}
+
+fn counter() -> u32 {
+    loop {
+        let high = pac::RTC.cnth().read().cnt();
+        let low = pac::RTC.cntl().read().cnt();
+        if high == pac::RTC.cnth().read().cnt() {
+            return (high as u32) << 16 | low as u32;
+        }
+    }
+}
+
+fn is_running() -> bool {
+    pac::RCC.bdctlr().read().rtcen()
+}
+
+/// UTC seconds since 2000, if the counter is running and has ever been
+/// set.
+pub fn read() -> Option<u32> {
+    if !is_running() {
+        return None;
+    }
+
+    let seconds = counter();
+    (seconds >= SET_AFTER).then_some(seconds)
+}
+
+/// Sets the counter to `seconds`. Call right at a second boundary:
+/// reloading the prescaler restarts the sub-second phase there.
+pub fn set(seconds: u32) {
+    if !is_running() {
+        return;
+    }
+
+    configure(|| {
+        pac::RTC.pscl().write(|w| w.set_prl(32768 - 1));
+        pac::RTC.cnth().write(|w| w.set_cnt((seconds >> 16) as u16));
+        pac::RTC.cntl().write(|w| w.set_cnt(seconds as u16));
+    });
+}
@@ -0,0 +1,161 @@
+//! Six multiplexed 7-segment digits, HH.MM.SS, behind two daisy-chained
+//! 74HC595s. The register nearest the MCU drives segments a-g and the
+//! decimal point from Q0-Q7, high to light them (common cathode). The
+//! second drives the digit cathodes, left to right from Q0, through
+//! transistors that sink a digit while its output is high.
+
+use core::cell::Cell;
+
+use ch32_hal::gpio::{Level, Output};
+use embassy_futures::select::select;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_time::{Duration, Ticker, Timer};
+
+use crate::timebase::TimeBase;
+use crate::{monotonic, power, settings, state, timebase, watchdog};
+
+const DIGITS: usize = 6;
+
+const BLANK: u8 = 0;
+const DASH: u8 = 1 << 6;
+const DECIMAL_POINT: u8 = 1 << 7;
+
+/// 0-9, segment a in bit 0 to g in bit 6.
+const NUMERALS: [u8; 10] = [0x3F, 0x06, 0x5B, 0x4F, 0x66, 0x6D, 0x7D, 0x07, 0x7F, 0x6F];
+
+/// The decimal points after the hours and the minutes stand in for the
+/// colons.
+const COLONS: [usize; 2] = [1, 3];
+
+/// Each digit is lit for this long in turn, so the display as a whole
+/// refreshes at about 170 Hz.
+const DIGIT_PERIOD: Duration = Duration::from_micros(1000);
+
+/// Render this long after each second boundary, as on the LCD.
+const RENDER_AFTER_MS: u64 = 50;
+
+/// What `refresh_task` shows, leftmost digit first.
+static SEGMENTS: Mutex<CriticalSectionRawMutex, Cell<[u8; DIGITS]>> =
+    Mutex::new(Cell::new([DASH; DIGITS]));
+
+pub struct ShiftRegisters {
+    data: Output<'static>,
+    clock: Output<'static>,
+    latch: Output<'static>,
+}
+
+impl ShiftRegisters {
+    pub fn new(data: Output<'static>, clock: Output<'static>, latch: Output<'static>) -> Self {
+        ShiftRegisters { data, clock, latch }
+    }
+
+    fn shift(&mut self, byte: u8) {
+        for i in (0..8).rev() {
+            self.data.set_level(match byte & (1 << i) {
+                0 => Level::Low,
+                _ => Level::High,
+            });
+            self.clock.set_high();
+            self.clock.set_low();
+        }
+    }
+
+    /// Shifts both bytes in, then latches them together, so no other
+    /// digit ever shows these segments.
+    fn load(&mut self, digits: u8, segments: u8) {
+        self.shift(digits);
+        self.shift(segments);
+
+        self.latch.set_high();
+        self.latch.set_low();
+    }
+}
+
+/// Lights the digits one at a time from `SEGMENTS`.
+///
+/// Tasks share one cooperative executor, so a tick comes late by however
+/// long another task runs between awaits, at most a few hundred µs outside
+/// of console output. Lateness only stretches the on time of the digit
+/// already lit, giving at worst a momentary unevenness in brightness,
+/// never wrong segments. The ticker keeps to its own schedule, so late
+/// ticks don't add up to a slower refresh.
+#[embassy_executor::task]
+pub async fn refresh_task(mut registers: ShiftRegisters) {
+    let mut ticker = Ticker::every(DIGIT_PERIOD);
+    let mut digit = 0;
+
+    loop {
+        let segments = SEGMENTS.lock(|s| s.get());
+        registers.load(1 << digit, segments[digit]);
+
+        digit = (digit + 1) % DIGITS;
+        ticker.next().await;
+    }
+}
+
+/// "HH.MM.SS" with the leading zero of the hour blanked and the colons
+/// lit on even seconds, or dashes until the first sync.
+fn render(base: Option<TimeBase>, now: u64) -> [u8; DIGITS] {
+    let Some(base) = base else {
+        return [DASH; DIGITS];
+    };
+
+    let clock = base.wall_clock(now, settings::zone());
+    let mut segments = [
+        clock.hour / 10,
+        clock.hour % 10,
+        clock.minute / 10,
+        clock.minute % 10,
+        clock.second / 10,
+        clock.second % 10,
+    ]
+    .map(|digit| NUMERALS[digit as usize]);
+
+    if clock.hour < 10 {
+        segments[0] = BLANK;
+    }
+
+    if clock.second % 2 == 0 {
+        for colon in COLONS {
+            segments[colon] |= DECIMAL_POINT;
+        }
+    }
+
+    segments
+}
+
+/// Renders the time into `SEGMENTS` every second, and on each state
+/// change in place of the LCD.
+#[embassy_executor::task]
+pub async fn render_task() {
+    let mut changes = state::watch();
+
+    loop {
+        watchdog::beat(watchdog::Task::Display);
+
+        // At least once a heartbeat, even with nothing to show
+        let next_render_after = match timebase::load() {
+            Some(b) => {
+                let now = monotonic::now_ms();
+                let after = now.saturating_sub(RENDER_AFTER_MS);
+                b.next_second_at(after) + RENDER_AFTER_MS - now
+            }
+            None => watchdog::HEARTBEAT_MS,
+        };
+
+        let wait = next_render_after.min(watchdog::HEARTBEAT_MS);
+        select(Timer::after_millis(wait), changes.changed()).await;
+
+        let current = state::snapshot();
+        let base = timebase::load();
+        let now = monotonic::now_ms();
+
+        let segments = match current.standby || power::is_blanked(&current, base, now) {
+            true => [BLANK; DIGITS],
+            false => render(base, now),
+        };
+
+        SEGMENTS.lock(|s| s.set(segments));
+    }
+}
@@ -0,0 +1,262 @@
+//! What the buttons and the console set, kept over power cycles in the
+//! last flash page: the display zone, the alarm, the hourly pips, the
+//! station setting, the language and the hour format, the time page's
+//! bottom line template, and whether the first-boot setup has been through
+//! them. The record carries a magic, a
+//! version and a CRC; one that fails any of them is replaced by the
+//! defaults. Changes are written back once they settle, at most once a
+//! minute, to spare the flash.
+
+use core::cell::Cell;
+
+use ch32_jjy_clock::console::parse::CONFIG_WORDS;
+use ch32_jjy_clock::display::pages::Language;
+use ch32_jjy_clock::display::template::{self, Template};
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Timer, with_timeout};
+
+use crate::decoder::{Selection, Station};
+use crate::fault::{self, Fault};
+use crate::pips::Mode;
+use crate::{alarm, flash, log, monotonic, timebase};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Settings {
+    /// Minutes east of UTC.
+    pub zone_minutes: i32,
+    pub alarm: alarm::Setting,
+    pub pips: Mode,
+    pub selection: Selection,
+    pub language: Language,
+    pub twelve_hour: bool,
+    /// Lays out the time page's bottom line; empty for the built-in one.
+    pub status_line: Template,
+    /// The first-boot setup has run; until it has, it starts at boot.
+    pub set_up: bool,
+}
+
+impl Settings {
+    pub const DEFAULT: Settings = Settings {
+        zone_minutes: timebase::DEFAULT_ZONE_MINUTES,
+        alarm: alarm::Setting::DEFAULT,
+        pips: Mode::Off,
+        selection: Selection::Fixed(Station::Jjy40),
+        language: Language::En,
+        twelve_hour: false,
+        status_line: Template::NONE,
+        set_up: false,
+    };
+}
+
+// Flash record layout, in half-words
+const MAGIC: u16 = 0x5E77;
+/// Bumped whenever the layout changes; older records load as the defaults.
+const VERSION: u16 = 3;
+/// The same record `config dump` prints and `config load` takes.
+pub const WORDS: usize = CONFIG_WORDS;
+
+/// Where the template starts, two characters a half-word.
+const TEMPLATE_AT: usize = 5;
+
+const PIPS: [Mode; 3] = [Mode::Off, Mode::Hourly, Mode::Nhk];
+
+/// CRC-16/CCITT-FALSE.
+fn crc(words: &[u16]) -> u16 {
+    let mut crc = 0xFFFF;
+    for byte in words.iter().flat_map(|w| w.to_le_bytes()) {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = match crc & 0x8000 {
+                0 => crc << 1,
+                _ => crc << 1 ^ 0x1021,
+            };
+        }
+    }
+    crc
+}
+
+fn encode(settings: &Settings) -> [u16; WORDS] {
+    let alarm = settings.alarm;
+    let pips = PIPS.iter().position(|m| *m == settings.pips).unwrap() as u16;
+    let selection = Selection::ALL
+        .iter()
+        .position(|s| *s == settings.selection)
+        .unwrap() as u16;
+
+    let mut words = [0; WORDS];
+    words[..TEMPLATE_AT].copy_from_slice(&[
+        MAGIC,
+        VERSION,
+        settings.zone_minutes as i16 as u16,
+        (alarm.hour as u16) << 8 | alarm.minute as u16,
+        alarm.enabled as u16
+            | pips << 1
+            | selection << 3
+            | ((settings.language == Language::Ja) as u16) << 6
+            | (settings.twelve_hour as u16) << 7
+            | (settings.set_up as u16) << 8,
+    ]);
+    let template = settings.status_line.to_bytes();
+    for (word, pair) in words[TEMPLATE_AT..].iter_mut().zip(template.chunks(2)) {
+        *word = u16::from_le_bytes([pair[0], pair[1]]);
+    }
+    words[WORDS - 1] = crc(&words[..WORDS - 1]);
+    words
+}
+
+fn decode(words: &[u16; WORDS]) -> Option<Settings> {
+    if words[0] != MAGIC || words[1] != VERSION || words[WORDS - 1] != crc(&words[..WORDS - 1]) {
+        return None;
+    }
+
+    let (hour, minute) = ((words[3] >> 8) as u8, words[3] as u8);
+    if hour >= 24 || minute >= 60 {
+        return None;
+    }
+
+    let mut bytes = [0; template::LENGTH];
+    for (pair, word) in bytes.chunks_mut(2).zip(&words[TEMPLATE_AT..]) {
+        pair.copy_from_slice(&word.to_le_bytes());
+    }
+
+    Some(Settings {
+        zone_minutes: words[2] as i16 as i32,
+        alarm: alarm::Setting {
+            hour,
+            minute,
+            enabled: words[4] & 1 != 0,
+        },
+        pips: *PIPS.get((words[4] >> 1 & 0b11) as usize)?,
+        selection: *Selection::ALL.get((words[4] >> 3 & 0b111) as usize)?,
+        language: match words[4] >> 6 & 1 {
+            0 => Language::En,
+            _ => Language::Ja,
+        },
+        twelve_hour: words[4] >> 7 & 1 != 0,
+        status_line: Template::from_bytes(&bytes)?,
+        set_up: words[4] >> 8 & 1 != 0,
+    })
+}
+
+fn load() -> Option<Settings> {
+    decode(&core::array::from_fn(flash::read))
+}
+
+static SETTINGS: Mutex<CriticalSectionRawMutex, Cell<Settings>> =
+    Mutex::new(Cell::new(Settings::DEFAULT));
+
+/// Wakes the writer after each change.
+static CHANGED: Signal<CriticalSectionRawMutex, ()> = Signal::new();
+
+/// Restores what the last boot saved.
+pub fn init() {
+    match load() {
+        Some(settings) => {
+            log::info!("Restored settings");
+            SETTINGS.lock(|s| s.set(settings));
+        }
+        None => log::info!("No valid settings saved, using defaults"),
+    }
+}
+
+pub fn get() -> Settings {
+    SETTINGS.lock(|s| s.get())
+}
+
+/// The settings as their flash record.
+#[cfg(feature = "console")]
+pub fn record() -> [u16; WORDS] {
+    encode(&get())
+}
+
+/// Takes the settings in `words` if they pass the checks a saved record
+/// must, and returns them; `settings_task` saves them.
+#[cfg(feature = "console")]
+pub fn load_record(words: &[u16; WORDS]) -> Option<Settings> {
+    let settings = decode(words)?;
+    update(|s| *s = settings);
+    Some(settings)
+}
+
+/// The zone times are shown in, in minutes east of UTC.
+pub fn zone() -> i32 {
+    get().zone_minutes
+}
+
+pub fn set_zone(minutes: i32) {
+    update(|s| s.zone_minutes = minutes);
+}
+
+/// Changes the settings in place; `settings_task` saves them.
+pub fn update(f: impl FnOnce(&mut Settings)) {
+    SETTINGS.lock(|s| {
+        let mut settings = s.get();
+        f(&mut settings);
+        s.set(settings);
+    });
+    CHANGED.signal(());
+}
+
+/// Quiet this long after a change before writing, so a run of button
+/// presses is one write.
+const SETTLE: Duration = Duration::from_secs(5);
+
+const MIN_WRITE_INTERVAL_MS: u64 = 60 * 1000;
+
+/// How far past a second boundary a write goes. The edge starting the
+/// second has been timed by then, and no pulse ends before 200 ms.
+const WRITE_AFTER_MS: u64 = 100;
+
+/// Waits for a moment when stalling the core costs no edge. Without a
+/// radio-synced time, nobody knows when the edges come.
+async fn quiet_moment() {
+    let Some(base) = timebase::load().filter(|b| b.synced) else {
+        return;
+    };
+
+    let at = base.next_second_at(monotonic::now_ms()) + WRITE_AFTER_MS;
+    Timer::at(monotonic::instant_at(at)).await;
+}
+
+#[embassy_executor::task]
+pub async fn settings_task() {
+    let mut saved = get();
+    let mut written_at: Option<u64> = None;
+
+    loop {
+        CHANGED.wait().await;
+        while with_timeout(SETTLE, CHANGED.wait()).await.is_ok() {}
+
+        if let Some(at) = written_at {
+            Timer::at(monotonic::instant_at(at + MIN_WRITE_INTERVAL_MS)).await;
+        }
+
+        // Includes whatever changed while waiting
+        let settings = get();
+        if settings == saved {
+            continue;
+        }
+
+        quiet_moment().await;
+        let written = flash::write(&encode(&settings));
+        saved = settings;
+        written_at = Some(monotonic::now_ms());
+
+        match (written, load() == Some(settings)) {
+            (Ok(()), true) => {
+                log::info!("Settings saved");
+                fault::clear(Fault::FlashWrite);
+            }
+            (Err(e), _) => {
+                log::error!("Settings not saved: flash {}", e.as_str());
+                fault::raise(Fault::FlashWrite);
+            }
+            (Ok(()), false) => {
+                log::error!("Settings didn't read back as written");
+                fault::raise(Fault::FlashWrite);
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::watch::{Receiver, Watch};
+
+use crate::decoder::BitWidth;
+use crate::manual::ManualSet;
+use crate::stats::Stats;
+use crate::wizard::Wizard;
+use crate::{StatusUpdate, monotonic, settings, timebase};
+
+/// Reception state for the output interfaces; the time itself is read
+/// through `timebase::load`.
+#[derive(Clone, Copy, Debug)]
+pub struct ClockState {
+    pub carrier: bool,
+    pub cursor: u8,
+    pub latest_bitwidth: BitWidth,
+    /// Display and indicators are off; timekeeping continues.
+    pub standby: bool,
+    /// Name of the station setting or station being decoded, and when it
+    /// last changed.
+    pub protocol: &'static str,
+    pub protocol_at: u64,
+    /// When the last few radio-synced timebases were accepted, newest
+    /// first.
+    pub recent_syncs: [Option<u64>; RECENT_SYNCS],
+    /// 0 to `quality::MAX_SCORE`.
+    pub signal_quality: u8,
+    /// Since boot or the last `stats reset`, as of the last pulse posted.
+    pub stats: Stats,
+    /// The time being entered by hand, while it is.
+    pub setting: Option<ManualSet>,
+    /// The first-boot setup, while it runs.
+    pub setup: Option<Wizard>,
+    /// When the display zone was last changed.
+    pub zone_at: Option<u64>,
+    /// When a button last woke the display from night blanking.
+    pub woken_at: Option<u64>,
+    /// Page button presses since boot, wrapping; each one not seen yet is
+    /// a page to step.
+    pub page_presses: u8,
+    /// Width class and data bit of the latest pulses, newest last.
+    pub recent_pulses: [Option<(BitWidth, Option<bool>)>; RECENT_PULSES],
+    pub reception: Reception,
+    /// Seconds received of the frame in progress, if one has started.
+    pub progress: Option<u8>,
+    /// Die temperature in tenths of °C, once sampled.
+    pub temperature: Option<i16>,
+}
+
+/// Coarse reception state, for indicators read at a glance.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Reception {
+    /// Pulses coming in, no frame decoded yet.
+    Receiving,
+    /// Synced within `SYNCED_WITHIN_MS`.
+    Synced,
+    /// Synced, but not that recently.
+    Stale,
+    /// The receiver output has stopped changing, low or high.
+    NoSignal,
+}
+
+const SYNCED_WITHIN_MS: u64 = 60 * 60 * 1000;
+
+impl Reception {
+    /// The state at `now`, given the last sync and whether the receiver
+    /// has gone quiet.
+    pub fn at(last_sync_at: Option<u64>, silent: bool, now: u64) -> Self {
+        match last_sync_at {
+            _ if silent => Reception::NoSignal,
+            Some(at) if now - at < SYNCED_WITHIN_MS => Reception::Synced,
+            Some(_) => Reception::Stale,
+            None => Reception::Receiving,
+        }
+    }
+}
+
+/// Syncs remembered, one a minute at best.
+const RECENT_SYNCS: usize = 10;
+
+/// Pulses remembered, a line's worth.
+pub const RECENT_PULSES: usize = 16;
+
+impl ClockState {
+    const fn new() -> Self {
+        ClockState {
+            carrier: false,
+            cursor: 0,
+            latest_bitwidth: BitWidth::Unknown,
+            standby: false,
+            protocol: "",
+            protocol_at: 0,
+            recent_syncs: [None; RECENT_SYNCS],
+            signal_quality: 0,
+            stats: Stats::new(),
+            setting: None,
+            setup: None,
+            zone_at: None,
+            woken_at: None,
+            page_presses: 0,
+            recent_pulses: [None; RECENT_PULSES],
+            reception: Reception::Receiving,
+            progress: None,
+            temperature: None,
+        }
+    }
+
+    pub fn last_sync_at(&self) -> Option<u64> {
+        self.recent_syncs[0]
+    }
+
+    /// Syncs within the last `window_ms` before `now`.
+    pub fn syncs_within(&self, now: u64, window_ms: u64) -> usize {
+        self.recent_syncs
+            .iter()
+            .flatten()
+            .filter(|at| now - **at < window_ms)
+            .count()
+    }
+
+    /// Takes `update`, which `post` has already passed on to the time base
+    /// and settings it concerns, into the state as of `now`.
+    fn apply(&mut self, update: &StatusUpdate, now: u64) {
+        match *update {
+            StatusUpdate::JJYOn(cursor) => {
+                self.carrier = true;
+                self.cursor = cursor;
+            }
+            StatusUpdate::JJYOff(bit_width, value) => {
+                self.carrier = false;
+                self.latest_bitwidth = bit_width;
+                self.recent_pulses.rotate_left(1);
+                self.recent_pulses[RECENT_PULSES - 1] = Some((bit_width, value));
+            }
+            StatusUpdate::TimeBaseUpdate(base) => {
+                if base.synced {
+                    self.recent_syncs.rotate_right(1);
+                    self.recent_syncs[0] = Some(base.system_time);
+                }
+            }
+            StatusUpdate::Stats(stats) => {
+                self.stats = stats;
+            }
+            StatusUpdate::Standby(standby) => {
+                self.standby = standby;
+            }
+            StatusUpdate::SignalQuality(score) => {
+                self.signal_quality = score;
+            }
+            StatusUpdate::Protocol(name) => {
+                self.protocol = name;
+                self.protocol_at = now;
+            }
+            StatusUpdate::NextPage => {
+                self.page_presses = self.page_presses.wrapping_add(1);
+            }
+            StatusUpdate::Setting(setting) => {
+                self.setting = setting;
+            }
+            StatusUpdate::Setup(setup) => {
+                self.setup = setup;
+            }
+            StatusUpdate::Zone(_) => {
+                self.zone_at = Some(now);
+            }
+            StatusUpdate::Reception(reception) => {
+                self.reception = reception;
+                // Whatever pulse was on air when it went quiet has ended
+                if reception == Reception::NoSignal {
+                    self.carrier = false;
+                }
+            }
+            StatusUpdate::FrameProgress(progress) => {
+                self.progress = progress;
+            }
+            StatusUpdate::Temperature(tenths) => {
+                self.temperature = Some(tenths);
+            }
+            StatusUpdate::Wake => {
+                self.woken_at = Some(now);
+            }
+        }
+    }
+}
+
+/// Output tasks watching for changes: the display or the 7-segment
+/// renderer, and one to spare.
+const WATCHERS: usize = 2;
+
+// The latest state is all anyone needs, so updates never queue up or wait
+// for a slow reader, and a reader that falls behind only skips to the
+// newest
+static STATE: Watch<CriticalSectionRawMutex, ClockState, WATCHERS> =
+    Watch::new_with(ClockState::new());
+
+pub fn snapshot() -> ClockState {
+    STATE.try_get().unwrap_or(ClockState::new())
+}
+
+/// Wakes on each change of the state. One per output task.
+pub fn watch() -> Receiver<'static, CriticalSectionRawMutex, ClockState, WATCHERS> {
+    STATE.receiver().unwrap()
+}
+
+/// Applies `update` to the shared state and wakes the watchers. Never
+/// blocks.
+pub fn post(update: StatusUpdate) {
+    let now = monotonic::now_ms();
+
+    // Done before the critical section `send_modify` holds, which holds off
+    // the capture interrupt; the zone change also wakes the settings writer
+    match &update {
+        StatusUpdate::TimeBaseUpdate(base) => timebase::store(*base, now),
+        StatusUpdate::Zone(minutes) => settings::set_zone(*minutes),
+        _ => {}
+    }
+
+    STATE.sender().send_modify(&mut |state| {
+        if let Some(state) = state {
+            state.apply(&update, now);
+        }
+    });
+}
@@ -0,0 +1,115 @@
+//! Reception counters, kept by the decoder task and published through the
+//! shared state, so readers format their own copy. The counting is
+//! hardware-free.
+
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::decoder::{Abort, BitWidth, Event, FrameError};
+use crate::{log, state};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    /// Pulses by `BitWidth`.
+    pub pulses: [u32; 6],
+    pub frames_started: u32,
+    /// Frames received to their end and checked, whatever the outcome.
+    pub frames_completed: u32,
+    pub parity_errors: u32,
+    pub range_errors: u32,
+    /// A marker or fixed bit out of place, or a marker where data goes.
+    pub marker_errors: u32,
+    /// Unreadable seconds solved for from parity, in frames that decoded.
+    pub recovered: u32,
+    /// Confirmed times.
+    pub decodes: u32,
+    pub last_decode_at: Option<u64>,
+    /// Times the receiver went quiet.
+    pub signal_losses: u32,
+}
+
+impl Stats {
+    pub const fn new() -> Self {
+        Stats {
+            pulses: [0; 6],
+            frames_started: 0,
+            frames_completed: 0,
+            parity_errors: 0,
+            range_errors: 0,
+            marker_errors: 0,
+            recovered: 0,
+            decodes: 0,
+            last_decode_at: None,
+            signal_losses: 0,
+        }
+    }
+
+    pub fn total_pulses(&self) -> u32 {
+        self.pulses.iter().sum()
+    }
+
+    pub fn lose_signal(&mut self) {
+        self.signal_losses += 1;
+    }
+
+    /// Counts a pulse that classified as `bit` and did `event`, at `now`,
+    /// with `recovered` seconds of the frame it ended solved from parity.
+    pub fn record(&mut self, bit: BitWidth, event: Event, recovered: u8, now: u64) {
+        self.pulses[bit as usize] += 1;
+
+        match event {
+            Event::None => {}
+            Event::FrameStarted => self.frames_started += 1,
+            // A width that fits no class is a pulse count, not a frame one
+            Event::Aborted { reason, .. } => {
+                if let Abort::Layout(_) = reason {
+                    self.marker_errors += 1;
+                }
+            }
+            Event::Rejected(error) => {
+                self.frames_completed += 1;
+                match error {
+                    FrameError::Parity(_) => self.parity_errors += 1,
+                    FrameError::OutOfRange(_) => self.range_errors += 1,
+                    FrameError::Unreadable(_) => self.marker_errors += 1,
+                }
+            }
+            Event::Unconfirmed(_) => {
+                self.frames_completed += 1;
+                self.recovered += recovered as u32;
+            }
+            Event::Decoded(_) => {
+                self.frames_completed += 1;
+                self.recovered += recovered as u32;
+                self.decodes += 1;
+                self.last_decode_at = Some(now);
+            }
+        }
+    }
+}
+
+const SUMMARY_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Logs what the last hour added to the counters. A reset in between
+/// makes that hour count from the reset.
+#[embassy_executor::task]
+pub async fn summary_task() {
+    let mut summary_at = Instant::now() + SUMMARY_PERIOD;
+    let mut reported = state::snapshot().stats;
+
+    loop {
+        Timer::at(summary_at).await;
+        summary_at += SUMMARY_PERIOD;
+
+        let current = state::snapshot().stats;
+        let since = |now: u32, then: u32| now.checked_sub(then).unwrap_or(now);
+
+        log::info!(
+            "last 60 min: {} pulses, {} frames, {} decodes",
+            since(current.total_pulses(), reported.total_pulses()),
+            since(current.frames_started, reported.frames_started),
+            since(current.decodes, reported.decodes)
+        );
+
+        reported = current;
+    }
+}
@@ -0,0 +1,113 @@
+use core::fmt::{self, Write};
+
+use ch32_hal::mode::Blocking;
+#[cfg(not(feature = "i2c-display"))]
+use ch32_hal::peripherals::USART1;
+#[cfg(feature = "i2c-display")]
+use ch32_hal::peripherals::USART2;
+use ch32_hal::usart::UartTx;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::Channel;
+
+use crate::fault::Fault;
+use crate::{monotonic, settings, timebase};
+
+/// Syslog severities, numbered as in RFC 5424.
+#[derive(Clone, Copy, Debug)]
+pub enum Severity {
+    Error = 3,
+    Warning = 4,
+    Notice = 5,
+    Info = 6,
+}
+
+/// Events worth keeping in a long-term log.
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    Boot,
+    Synced,
+    SyncFailed(&'static str),
+    FaultRaised(Fault),
+    FaultCleared(Fault),
+    Alarm(&'static str),
+}
+
+impl Event {
+    fn severity(&self) -> Severity {
+        match self {
+            Event::Boot => Severity::Notice,
+            Event::Synced => Severity::Info,
+            Event::SyncFailed(_) => Severity::Warning,
+            Event::FaultRaised(_) => Severity::Error,
+            Event::FaultCleared(_) => Severity::Notice,
+            Event::Alarm(_) => Severity::Warning,
+        }
+    }
+
+    fn tag(&self) -> &'static str {
+        match self {
+            Event::Boot => "BOOT",
+            Event::Synced => "SYNC",
+            Event::SyncFailed(_) => "NOSYNC",
+            Event::FaultRaised(_) => "FAULT",
+            Event::FaultCleared(_) => "CLEAR",
+            Event::Alarm(_) => "ALARM",
+        }
+    }
+
+    fn detail(&self) -> &'static str {
+        match self {
+            Event::Boot | Event::Synced => "",
+            Event::SyncFailed(reason) | Event::Alarm(reason) => reason,
+            Event::FaultRaised(fault) | Event::FaultCleared(fault) => fault.as_str(),
+        }
+    }
+}
+
+static EVENTS: Channel<CriticalSectionRawMutex, (u64, Event), 8> = Channel::new();
+
+/// Queues `event` with the current time. Events are dropped rather than
+/// blocking the caller when the UART falls behind.
+pub fn emit(event: Event) {
+    let _ = EVENTS.try_send((monotonic::now_ms(), event));
+}
+
+/// USART1 on PB6, unless an I2C display needs PB6, then USART2 on PA2.
+#[cfg(not(feature = "i2c-display"))]
+pub type Port = USART1;
+#[cfg(feature = "i2c-display")]
+pub type Port = USART2;
+
+struct Line<'a>(&'a mut UartTx<'static, Port, Blocking>);
+
+impl Write for Line<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.blocking_write(s.as_bytes()).map_err(|_| fmt::Error)
+    }
+}
+
+/// Writes one line per event:
+///
+/// `<severity> <uptime ms> <HH:MM:SS or -> <TAG> <detail>`
+///
+/// Fields are space separated and never contain spaces themselves; the
+/// detail may be empty.
+#[embassy_executor::task]
+pub async fn syslog_task(mut uart: UartTx<'static, Port, Blocking>) {
+    let receiver = EVENTS.receiver();
+
+    loop {
+        let (at, event) = receiver.receive().await;
+        let mut line = Line(&mut uart);
+
+        let _ = write!(line, "<{}> {} ", event.severity() as u8, at);
+        let _ = match timebase::load() {
+            Some(base) => {
+                let c = base.wall_clock(at, settings::zone());
+                write!(line, "{:0>2}:{:0>2}:{:0>2} ", c.hour, c.minute, c.second)
+            }
+            None => write!(line, "- "),
+        };
+        let _ = write!(line, "{} {}\r\n", event.tag(), event.detail());
+    }
+}
@@ -0,0 +1,26 @@
+//! Die temperature from the ADC's internal sensor, sampled every few
+//! seconds. The crystal's drift follows it closely, so it is published
+//! next to the drift estimate. The conversion and smoothing are
+//! `temperature::conversion` of the lib.
+
+pub use ch32_jjy_clock::temperature::conversion::Celsius;
+use ch32_jjy_clock::temperature::conversion::{Smoothing, tenths_celsius};
+use embassy_time::Timer;
+
+use crate::{StatusUpdate, adc, log, state};
+
+const SAMPLE_PERIOD_S: u64 = 5;
+
+#[embassy_executor::task]
+pub async fn temperature_task() {
+    let mut smoothing = Smoothing::new();
+
+    loop {
+        let raw = adc::sample(adc::TEMPERATURE);
+        let tenths = smoothing.push(tenths_celsius(raw));
+        log::trace!("Temperature {} (raw {})", tenths, raw);
+        state::post(StatusUpdate::Temperature(tenths));
+
+        Timer::after_secs(SAMPLE_PERIOD_S).await;
+    }
+}
@@ -0,0 +1,104 @@
+//! Sensor readings to °C, apart from the ADC.
+
+use core::fmt;
+
+/// ADC reference, the supply.
+const VDDA_MV: i64 = 3300;
+const FULL_SCALE: i64 = 4095;
+
+/// Sensor voltage at 25 °C and how much it falls per °C, the datasheet's
+/// typical figures. A part can be a few °C off; the drift only cares about
+/// changes.
+const V25_UV: i64 = 1_400_000;
+const SLOPE_UV_PER_C: i64 = 4_300;
+
+/// Tenths of °C for a 12-bit reading of the sensor.
+pub fn tenths_celsius(raw: u16) -> i16 {
+    let microvolts = raw as i64 * VDDA_MV * 1000 / FULL_SCALE;
+    (250 + (V25_UV - microvolts) * 10 / SLOPE_UV_PER_C) as i16
+}
+
+/// Tenths of °C shown as "27.5" or "-3.5".
+pub struct Celsius(pub i16);
+
+impl fmt::Display for Celsius {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let tenths = self.0.unsigned_abs();
+        write!(f, "{sign}{}.{}", tenths / 10, tenths % 10)
+    }
+}
+
+/// Samples averaged, half a minute.
+const WINDOW: usize = 6;
+
+/// Mean of the last `WINDOW` readings, in tenths of °C.
+pub struct Smoothing {
+    samples: [Option<i16>; WINDOW],
+    next: usize,
+}
+
+impl Smoothing {
+    pub const fn new() -> Self {
+        Smoothing {
+            samples: [None; WINDOW],
+            next: 0,
+        }
+    }
+
+    pub fn push(&mut self, tenths: i16) -> i16 {
+        self.samples[self.next] = Some(tenths);
+        self.next = (self.next + 1) % WINDOW;
+
+        let (sum, count) = self
+            .samples
+            .iter()
+            .flatten()
+            .fold((0i32, 0i32), |(sum, count), t| (sum + *t as i32, count + 1));
+        (sum / count) as i16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The reading for `microvolts` at the sensor, rounded.
+    fn raw(microvolts: i64) -> u16 {
+        ((microvolts * FULL_SCALE + VDDA_MV * 500) / (VDDA_MV * 1000)) as u16
+    }
+
+    #[test]
+    fn reads_25_degrees_at_v25() {
+        assert_eq!(raw(V25_UV), 1737);
+        assert_eq!(tenths_celsius(1737), 250);
+    }
+
+    #[test]
+    fn follows_the_slope_off_nominal() {
+        // 100 mV under V25 is 23.3 °C warmer, over it as much colder
+        assert_eq!(raw(1_300_000), 1613);
+        assert_eq!(tenths_celsius(1613), 482);
+        assert_eq!(raw(1_500_000), 1861);
+        assert_eq!(tenths_celsius(1861), 19);
+    }
+
+    #[test]
+    fn shows_tenths_with_the_sign() {
+        for (tenths, shown) in [(275, "27.5"), (0, "0.0"), (-5, "-0.5"), (-35, "-3.5")] {
+            assert_eq!(format!("{}", Celsius(tenths)), shown);
+        }
+    }
+
+    #[test]
+    fn averages_the_last_window() {
+        let mut smoothing = Smoothing::new();
+        assert_eq!(smoothing.push(250), 250);
+        assert_eq!(smoothing.push(260), 255);
+
+        for _ in 0..WINDOW {
+            smoothing.push(300);
+        }
+        assert_eq!(smoothing.push(300), 300);
+    }
+}
@@ -0,0 +1,761 @@
+use core::cell::Cell;
+
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+
+mod slew;
+
+pub use slew::Slew;
+
+pub const SECONDS_PER_DAY: u32 = 60 * 60 * 24;
+
+/// Drift assumed for the free-running clock until it is actually measured.
+pub const ASSUMED_DRIFT_PPM: u64 = 100;
+
+/// What may be left of the drift once it is measured and taken out: the
+/// jitter of the two decodes it was measured between, and the oscillator
+/// wandering with temperature since.
+pub const RESIDUAL_DRIFT_PPM: u64 = 10;
+
+/// Drift is measured between decodes at least this far apart, so the
+/// millisecond jitter of each anchor stays below a ppm or so.
+const DRIFT_MIN_INTERVAL_S: u64 = 30 * 60;
+
+/// Bounds of the applied correction.
+const DRIFT_LIMIT_PPM: i32 = 200;
+
+/// A measurement this far off is a bad decode or a leap second, not drift.
+const DRIFT_OUTLIER_PPM: i64 = 1000;
+
+/// Beyond this estimated error the seconds digit is no longer trustworthy.
+pub const SECONDS_PRECISION_MS: u64 = 500;
+
+/// Without a sync for this long, the time is stale: marked on the display,
+/// and no longer trusted for time signals.
+pub const STALE_AFTER_MS: u64 = 24 * 60 * 60 * 1000;
+
+pub fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+pub fn days_in_year(year: u16) -> u16 {
+    if is_leap_year(year) { 366 } else { 365 }
+}
+
+const DAYS_IN_MONTH: [u16; 12] = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+
+/// Calendar month and day (both 1-based) of the 1-based `day` of `year`,
+/// or `None` if `day` doesn't exist in that year.
+pub fn month_day(year: u16, day: u16) -> Option<(u8, u8)> {
+    if day == 0 || day > days_in_year(year) {
+        return None;
+    }
+
+    let mut remaining = day;
+    for (month, days) in DAYS_IN_MONTH.into_iter().enumerate() {
+        let days = if month == 1 && is_leap_year(year) {
+            29
+        } else {
+            days
+        };
+        if remaining <= days {
+            return Some((month as u8 + 1, remaining as u8));
+        }
+
+        remaining -= days;
+    }
+
+    None
+}
+
+pub fn days_in_month(year: u16, month: u8) -> u16 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        m => DAYS_IN_MONTH[m as usize - 1],
+    }
+}
+
+/// Inverse of `month_day`: the 1-based day of year of a calendar date, or
+/// `None` if the date doesn't exist.
+pub fn day_of_year(year: u16, month: u8, day: u8) -> Option<u16> {
+    if !(1..=12).contains(&month) || day == 0 || day as u16 > days_in_month(year, month) {
+        return None;
+    }
+
+    Some((1..month).map(|m| days_in_month(year, m)).sum::<u16>() + day as u16)
+}
+
+/// Leap second announced for the end of the current UTC month.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LeapSecond {
+    None,
+    /// 23:59:60 UTC is inserted.
+    Insert,
+    /// 23:59:59 UTC is skipped.
+    Delete,
+}
+
+/// Days from 2000-01-01 to the 1-based `day` of `year`, for years >= 2000.
+pub fn days_since_2000(year: u16, day: u16) -> u32 {
+    let mut days = day as u32 - 1;
+    for y in 2000..year {
+        days += days_in_year(y) as u32;
+    }
+
+    days
+}
+
+/// Inverse of `days_since_2000`: the year and 1-based day of year.
+pub fn date_since_2000(mut days: u32) -> (u16, u16) {
+    let mut year = 2000;
+    while days >= days_in_year(year) as u32 {
+        days -= days_in_year(year) as u32;
+        year += 1;
+    }
+
+    (year, days as u16 + 1)
+}
+
+/// Weekday (0 = Sunday) of the 1-based `day` of `year`, for years >= 2000.
+pub fn weekday_of(year: u16, day: u16) -> u8 {
+    // 2000-01-01 was a Saturday
+    ((6 + days_since_2000(year, day)) % 7) as u8
+}
+
+/// Minutes since 2000-01-01 00:00, so consecutive frames differ by one
+/// across hour, day and year rollovers alike.
+pub fn minute_stamp(year: u16, day: u16, hour: u32, minute: u32) -> u32 {
+    days_since_2000(year, day) * 24 * 60 + hour * 60 + minute
+}
+
+/// Zone shown until one is chosen: JST, what JJY transmits.
+pub const DEFAULT_ZONE_MINUTES: i32 = 9 * 60;
+
+/// Zones are chosen in steps of this many minutes, between these offsets.
+const ZONE_STEP_MINUTES: i32 = 30;
+const ZONE_MIN_MINUTES: i32 = -12 * 60;
+const ZONE_MAX_MINUTES: i32 = 14 * 60;
+
+/// The zone a step east of `minutes`, wrapping around from the easternmost
+/// to the westernmost.
+pub fn next_zone(minutes: i32) -> i32 {
+    match minutes + ZONE_STEP_MINUTES {
+        m if m > ZONE_MAX_MINUTES => ZONE_MIN_MINUTES,
+        m => m,
+    }
+}
+
+/// Sign, hours and minutes of a zone offset, as in "UTC+09:30".
+pub fn zone_parts(minutes: i32) -> (u8, u8, u8) {
+    let sign = if minutes < 0 { b'-' } else { b'+' };
+    let minutes = minutes.unsigned_abs();
+    (sign, (minutes / 60) as u8, (minutes % 60) as u8)
+}
+
+// Copied in and out whole inside a critical section, so a reader never
+// sees the anchor of one decode paired with the clock or date of another.
+static CURRENT: Mutex<CriticalSectionRawMutex, Cell<Option<TimeBase>>> =
+    Mutex::new(Cell::new(None));
+
+/// The latest accepted timebase, as one consistent copy.
+pub fn load() -> Option<TimeBase> {
+    CURRENT.lock(|c| c.get())
+}
+
+/// Anchor of the running drift measurement and the estimate so far.
+#[derive(Clone, Copy)]
+struct DriftEstimator {
+    // System time (ms) and seconds since 2000 of an earlier decode
+    reference: Option<(u64, u64)>,
+    ppm: Option<i32>,
+}
+
+static DRIFT: Mutex<CriticalSectionRawMutex, Cell<DriftEstimator>> =
+    Mutex::new(Cell::new(DriftEstimator {
+        reference: None,
+        ppm: None,
+    }));
+
+/// Compares the local time elapsed since the reference decode with what
+/// JJY says elapsed, and folds the difference into the smoothed estimate.
+/// The first measurement is taken as it is.
+fn measure_drift(base: &TimeBase) -> Option<i32> {
+    let seconds =
+        days_since_2000(base.year, base.day) as u64 * SECONDS_PER_DAY as u64 + base.clock as u64;
+
+    DRIFT.lock(|d| {
+        let mut drift = d.get();
+
+        match drift.reference {
+            Some((at, reference)) if seconds >= reference + DRIFT_MIN_INTERVAL_S => {
+                let true_ms = ((seconds - reference) * 1000) as i64;
+                let local_ms = (base.system_time - at) as i64;
+                let measured = (local_ms - true_ms) * 1_000_000 / true_ms;
+
+                if measured.abs() <= DRIFT_OUTLIER_PPM {
+                    let measured = measured as i32;
+                    let ppm = drift.ppm.map_or(measured, |ppm| ppm + (measured - ppm) / 4);
+                    drift.ppm = Some(ppm.clamp(-DRIFT_LIMIT_PPM, DRIFT_LIMIT_PPM));
+                }
+                drift.reference = Some((base.system_time, seconds));
+            }
+            // Time went backwards: start over from this decode
+            Some((_, reference)) if seconds < reference => {
+                drift.reference = Some((base.system_time, seconds));
+            }
+            Some(_) => {}
+            None => drift.reference = Some((base.system_time, seconds)),
+        }
+
+        d.set(drift);
+        drift.ppm
+    })
+}
+
+/// Stores `base` with the current drift estimate applied to it, at `now`.
+/// A decode replacing a radio-synced time goes through `apply_fix`.
+pub fn store(mut base: TimeBase, now: u64) {
+    if base.synced {
+        base.drift_ppm = measure_drift(&base);
+    }
+
+    CURRENT.lock(|c| {
+        let next = match c.get() {
+            Some(mut current) if base.synced => {
+                current.apply_fix(base, now);
+                current
+            }
+            _ => base,
+        };
+        c.set(Some(next));
+    });
+}
+
+/// The UTC time at a moment of the system clock. Decoded and entered times
+/// are converted with `in_utc` before they are stored; `wall_clock` turns
+/// them into the chosen zone.
+#[derive(Clone, Copy, Debug)]
+pub struct TimeBase {
+    /// `monotonic::now_ms` at the boundary that started second `clock`.
+    pub system_time: u64,
+    /// Second of the UTC day.
+    pub clock: u32,
+    pub year: u16,
+    /// Day of year, 1-based.
+    pub day: u16,
+    /// 0 = Sunday .. 6 = Saturday, `None` if it could not be read.
+    pub weekday: Option<u8>,
+    pub leap_second: LeapSecond,
+    /// How fast the local oscillator runs, in ppm; filled in by `store`
+    /// once measured.
+    pub drift_ppm: Option<i32>,
+    /// False for a time restored after a reset, which is only a guess
+    /// until JJY confirms it.
+    pub synced: bool,
+    /// Entered by hand; never `synced`. The next decode replaces it.
+    pub manual: bool,
+    /// What is left of a small correction from the last fix; `Slew::NONE`
+    /// for a fresh timebase.
+    pub slew: Slew,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct WallClock {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub weekday: Option<u8>,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millisecond: u16,
+}
+
+impl TimeBase {
+    /// Milliseconds of true time since `system_time`, with the measured
+    /// oscillator drift taken out.
+    fn elapsed_ms(&self, now: u64) -> u64 {
+        let local = now.saturating_sub(self.system_time);
+        local * 1_000_000 / (1_000_000 + self.drift_ppm.unwrap_or(0) as i64) as u64
+    }
+
+    /// Milliseconds of the time shown since `system_time`: true time, less
+    /// whatever lag the slew has left.
+    fn shown_ms(&self, now: u64) -> i64 {
+        let elapsed = self.elapsed_ms(now) as i64;
+        elapsed - self.slew.lag_ms(elapsed)
+    }
+
+    /// Milliseconds of the UTC day shown at `now`, before leap seconds. A
+    /// slew working off a lag across midnight holds at 0.
+    fn ms_of_day(&self, now: u64) -> u64 {
+        (self.clock as i64 * 1000 + self.shown_ms(now)).max(0) as u64
+    }
+
+    /// `monotonic::now_ms` at the first second boundary after `now`, where
+    /// the time shown reaches the next whole second.
+    pub fn next_second_at(&self, now: u64) -> u64 {
+        let next = (self.shown_ms(now).div_euclid(1000) + 1) * 1000;
+        let elapsed = self.slew.elapsed_at(next).max(0) as u64;
+        let scale = (1_000_000 + self.drift_ppm.unwrap_or(0) as i64) as u64;
+
+        // Rounded up, so the second has begun by then
+        self.system_time + (elapsed * scale).div_ceil(1_000_000)
+    }
+
+    /// Milliseconds since 2000 shown at `now`, leap seconds aside.
+    fn ms_since_2000(&self, now: u64) -> i64 {
+        let days = days_since_2000(self.year, self.day) as i64;
+        days * SECONDS_PER_DAY as i64 * 1000 + self.clock as i64 * 1000 + self.shown_ms(now)
+    }
+
+    /// Takes over `fix`, a fresh decode, at `now`. When this is a radio
+    /// time that `fix` corrects by less than `slew::STEP_AT_MS`, the time
+    /// shown carries on from where it was and slews onto `fix`; otherwise
+    /// it steps.
+    pub fn apply_fix(&mut self, mut fix: TimeBase, now: u64) {
+        let error_ms = fix.ms_since_2000(now) - self.ms_since_2000(now);
+
+        if self.synced && error_ms.abs() < slew::STEP_AT_MS {
+            fix.slew = Slew {
+                from_ms: fix.elapsed_ms(now) as i64,
+                offset_ms: error_ms,
+            };
+        }
+
+        *self = fix;
+    }
+
+    /// How far the time shown may have drifted since the decode it is
+    /// anchored to: at `ASSUMED_DRIFT_PPM` until the drift is measured, and
+    /// at `RESIDUAL_DRIFT_PPM` once it is taken out.
+    pub fn estimated_error_ms(&self, now: u64) -> u64 {
+        let ppm = match self.drift_ppm {
+            Some(_) => RESIDUAL_DRIFT_PPM,
+            None => ASSUMED_DRIFT_PPM,
+        };
+        now.saturating_sub(self.system_time) * ppm / 1_000_000
+    }
+
+    /// Whether the seconds shown are radio time within
+    /// `SECONDS_PRECISION_MS` by `estimated_error_ms`: for about 83 minutes
+    /// after a decode until the drift is measured, and about 14 hours once
+    /// it is.
+    pub fn seconds_are_precise(&self, now: u64) -> bool {
+        self.synced && self.estimated_error_ms(now) <= SECONDS_PRECISION_MS
+    }
+
+    /// Converts a timebase whose clock and date are `utc_offset_minutes`
+    /// east of UTC, as decoded or entered, to UTC.
+    pub fn in_utc(self, utc_offset_minutes: i32) -> TimeBase {
+        let days = days_since_2000(self.year, self.day);
+        let local = days as i64 * SECONDS_PER_DAY as i64 + self.clock as i64;
+
+        // Times before 2000 don't occur; clamp rather than wrap
+        let utc = (local - utc_offset_minutes as i64 * 60).max(0) as u64;
+        let utc_days = (utc / SECONDS_PER_DAY as u64) as u32;
+        let (year, day) = date_since_2000(utc_days);
+
+        TimeBase {
+            clock: (utc % SECONDS_PER_DAY as u64) as u32,
+            year,
+            day,
+            weekday: self
+                .weekday
+                .map(|w| (w as i64 + utc_days as i64 - days as i64).rem_euclid(7) as u8),
+            ..self
+        }
+    }
+
+    /// Seconds from the midnight starting the day of the timebase to the
+    /// leap second instant announced for the end of its month.
+    fn leap_second_at(&self) -> u64 {
+        let (month, day) = month_day(self.year, self.day).unwrap_or((1, 1));
+        let days = (days_in_month(self.year, month) - day as u16 + 1) as u64;
+
+        days * SECONDS_PER_DAY as u64
+    }
+
+    /// Seconds since the midnight starting the day of the timebase, with any
+    /// announced leap second applied, plus whether `now` is inside the
+    /// inserted second itself.
+    fn elapsed_seconds(&self, now: u64) -> (u64, bool) {
+        let total = self.ms_of_day(now) / 1000;
+
+        match self.leap_second {
+            LeapSecond::None => (total, false),
+            LeapSecond::Insert => {
+                let at = self.leap_second_at();
+                match total {
+                    t if t < at => (t, false),
+                    t if t == at => (t - 1, true),
+                    t => (t - 1, false),
+                }
+            }
+            LeapSecond::Delete => {
+                let at = self.leap_second_at();
+                if total + 1 >= at {
+                    (total + 1, false)
+                } else {
+                    (total, false)
+                }
+            }
+        }
+    }
+
+    /// Days since 2000-01-01 of the UTC date at `now`.
+    pub fn days_since_2000(&self, now: u64) -> u32 {
+        let (total, _) = self.elapsed_seconds(now);
+
+        days_since_2000(self.year, self.day) + (total / SECONDS_PER_DAY as u64) as u32
+    }
+
+    pub fn second_of_day(&self, now: u64) -> u32 {
+        let (total, _) = self.elapsed_seconds(now);
+
+        (total % SECONDS_PER_DAY as u64) as u32
+    }
+
+    /// The time at `now`, `utc_offset_minutes` east of UTC.
+    pub fn wall_clock(&self, now: u64, utc_offset_minutes: i32) -> WallClock {
+        let shown = self.ms_of_day(now);
+        let (total, inserted) = self.elapsed_seconds(now);
+
+        // The offset may move the date either way across midnight
+        let base_days = days_since_2000(self.year, self.day);
+        let local = (base_days as i64 * SECONDS_PER_DAY as i64
+            + total as i64
+            + utc_offset_minutes as i64 * 60)
+            .max(0) as u64;
+        let days = (local / SECONDS_PER_DAY as u64) as u32;
+        let (year, day) = date_since_2000(days);
+
+        // `date_since_2000` only gives days that exist, so this can't fail
+        let (month, day) = month_day(year, day).unwrap_or((1, 1));
+
+        let remaining = (local % SECONDS_PER_DAY as u64) as u32;
+        let hour = remaining / (60 * 60);
+        let remaining = remaining % (60 * 60);
+        let minute = remaining / 60;
+        let second = if inserted { 60 } else { remaining % 60 };
+
+        WallClock {
+            year,
+            month,
+            day,
+            weekday: self
+                .weekday
+                .map(|w| (w as i64 + days as i64 - base_days as i64).rem_euclid(7) as u8),
+            hour: hour as u8,
+            minute: minute as u8,
+            second: second as u8,
+            millisecond: (shown % 1000) as u16,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A radio time anchored at `system_time`, on second `clock` of day 100
+    /// of 2025.
+    fn base(system_time: u64, clock: u32) -> TimeBase {
+        TimeBase {
+            system_time,
+            clock,
+            year: 2025,
+            day: 100,
+            weekday: Some(4),
+            leap_second: LeapSecond::None,
+            drift_ppm: None,
+            synced: true,
+            manual: false,
+            slew: Slew::NONE,
+        }
+    }
+
+    /// A fresh decode, anchored 1-2 s before `now`, that puts the time at
+    /// `now` `error_ms` ahead of what `current` shows.
+    fn fix(current: &TimeBase, now: u64, error_ms: i64) -> TimeBase {
+        let true_ms = current.ms_of_day(now) as i64 + error_ms;
+        let clock = true_ms.div_euclid(1000) - 1;
+        base(now - (true_ms - clock * 1000) as u64, clock as u32)
+    }
+
+    const NOW: u64 = 50_000;
+
+    const HOUR_MS: u64 = 60 * 60 * 1000;
+    const DAY_MS: u64 = 24 * HOUR_MS;
+
+    /// A radio time anchored at 0 on `hour:minute:second` UTC of a date.
+    fn utc(year: u16, month: u8, day: u8, hour: u32, minute: u32, second: u32) -> TimeBase {
+        let day = day_of_year(year, month, day).unwrap();
+        TimeBase {
+            year,
+            day,
+            weekday: Some(weekday_of(year, day)),
+            ..base(0, hour * 3600 + minute * 60 + second)
+        }
+    }
+
+    /// Date, weekday and time shown at `now`, `zone` minutes east of UTC.
+    fn shown(base: &TimeBase, now: u64, zone: i32) -> (u16, u8, u8, Option<u8>, u8, u8, u8) {
+        let c = base.wall_clock(now, zone);
+        (
+            c.year, c.month, c.day, c.weekday, c.hour, c.minute, c.second,
+        )
+    }
+
+    #[test]
+    fn new_year_rolls_the_date_over() {
+        let base = utc(2024, 12, 31, 23, 59, 59);
+        assert_eq!(shown(&base, 999, 0), (2024, 12, 31, Some(2), 23, 59, 59));
+        assert_eq!(shown(&base, 1000, 0), (2025, 1, 1, Some(3), 0, 0, 0));
+    }
+
+    #[test]
+    fn february_ends_on_the_29th_in_leap_years_only() {
+        let leap = utc(2024, 2, 28, 23, 59, 59);
+        assert_eq!(shown(&leap, 1000, 0), (2024, 2, 29, Some(4), 0, 0, 0));
+        assert_eq!(
+            shown(&leap, 1000 + DAY_MS, 0),
+            (2024, 3, 1, Some(5), 0, 0, 0)
+        );
+
+        let common = utc(2025, 2, 28, 23, 59, 59);
+        assert_eq!(shown(&common, 1000, 0), (2025, 3, 1, Some(6), 0, 0, 0));
+
+        // Centuries are leap years only every 400 years
+        let century = utc(2100, 2, 28, 23, 59, 59);
+        assert_eq!(shown(&century, 1000, 0), (2100, 3, 1, Some(1), 0, 0, 0));
+    }
+
+    #[test]
+    fn month_ends_roll_over_to_the_first() {
+        for (year, month) in [(2025, 1), (2025, 4), (2025, 6), (2025, 9), (2025, 11)] {
+            let last = days_in_month(year, month) as u8;
+            let base = utc(year, month, last, 23, 59, 59);
+            let (y, m, d, _, h, _, _) = shown(&base, 1000, 0);
+            assert_eq!((y, m, d, h), (year, month + 1, 1, 0), "{year}-{month}");
+        }
+    }
+
+    #[test]
+    fn calendar_conversions_round_trip() {
+        for year in [2000, 2023, 2024, 2100] {
+            let mut expected = (1, 1);
+            for day in 1..=days_in_year(year) {
+                let (month, month_day) = month_day(year, day).unwrap();
+                assert_eq!((month, month_day), expected, "{year} day {day}");
+                assert_eq!(day_of_year(year, month, month_day), Some(day));
+
+                expected = match month_day as u16 == days_in_month(year, month) {
+                    true => (month + 1, 1),
+                    false => (month, month_day + 1),
+                };
+            }
+            assert_eq!(expected, (13, 1));
+            assert_eq!(month_day(year, days_in_year(year) + 1), None);
+        }
+
+        for days in 0..40_000 {
+            let (year, day) = date_since_2000(days);
+            assert_eq!(days_since_2000(year, day), days);
+        }
+    }
+
+    const JST: i32 = 9 * 60;
+    const EST: i32 = -5 * 60;
+
+    #[test]
+    fn zones_move_the_date_across_midnight() {
+        let base = utc(2025, 5, 3, 14, 59, 59);
+        assert_eq!(shown(&base, 999, JST), (2025, 5, 3, Some(6), 23, 59, 59));
+        assert_eq!(shown(&base, 1000, JST), (2025, 5, 4, Some(0), 0, 0, 0));
+
+        let base = utc(2025, 5, 4, 4, 59, 59);
+        assert_eq!(shown(&base, 999, EST), (2025, 5, 3, Some(6), 23, 59, 59));
+        assert_eq!(shown(&base, 1000, EST), (2025, 5, 4, Some(0), 0, 0, 0));
+
+        // Half-hour zones cross midnight on the half hour of UTC
+        let base = utc(2025, 5, 3, 18, 29, 59);
+        assert_eq!(shown(&base, 999, 330), (2025, 5, 3, Some(6), 23, 59, 59));
+        assert_eq!(shown(&base, 1000, 330), (2025, 5, 4, Some(0), 0, 0, 0));
+    }
+
+    #[test]
+    fn zones_move_the_date_across_months_and_years() {
+        let base = utc(2025, 3, 31, 15, 0, 0);
+        assert_eq!(shown(&base, 0, JST), (2025, 4, 1, Some(2), 0, 0, 0));
+
+        let base = utc(2025, 3, 1, 4, 0, 0);
+        assert_eq!(shown(&base, 0, EST), (2025, 2, 28, Some(5), 23, 0, 0));
+
+        let base = utc(2024, 3, 1, 4, 0, 0);
+        assert_eq!(shown(&base, 0, EST), (2024, 2, 29, Some(4), 23, 0, 0));
+
+        let base = utc(2025, 1, 1, 0, 30, 0);
+        assert_eq!(shown(&base, 0, -60), (2024, 12, 31, Some(2), 23, 30, 0));
+    }
+
+    #[test]
+    fn local_times_convert_back_to_utc() {
+        for (zone, local, expected) in [
+            (JST, utc(2025, 4, 1, 8, 0, 0), utc(2025, 3, 31, 23, 0, 0)),
+            (EST, utc(2024, 12, 31, 20, 0, 0), utc(2025, 1, 1, 1, 0, 0)),
+            (330, utc(2024, 3, 1, 5, 0, 0), utc(2024, 2, 29, 23, 30, 0)),
+        ] {
+            let converted = local.in_utc(zone);
+            assert_eq!(
+                (
+                    converted.year,
+                    converted.day,
+                    converted.weekday,
+                    converted.clock
+                ),
+                (
+                    expected.year,
+                    expected.day,
+                    expected.weekday,
+                    expected.clock
+                ),
+                "{zone}"
+            );
+            assert_eq!(shown(&converted, 0, zone), shown(&local, 0, 0), "{zone}");
+        }
+    }
+
+    #[test]
+    fn free_running_for_days_crosses_every_midnight() {
+        // Ten days and three hours from 2025-12-26, a Friday
+        let base = utc(2025, 12, 26, 22, 0, 0);
+        let now = 10 * DAY_MS + 3 * HOUR_MS;
+        assert_eq!(shown(&base, now, 0), (2026, 1, 6, Some(2), 1, 0, 0));
+
+        for days in 0..400 {
+            let now = days * DAY_MS + 2 * HOUR_MS;
+            let c = base.wall_clock(now, 0);
+            let day = day_of_year(c.year, c.month, c.day).unwrap();
+            assert_eq!(
+                days_since_2000(c.year, day),
+                base.days_since_2000(0) + days as u32 + 1
+            );
+            assert_eq!(c.weekday, Some(weekday_of(c.year, day)));
+            assert_eq!((c.hour, c.minute), (0, 0));
+        }
+    }
+
+    #[test]
+    fn small_error_slews_without_a_jump() {
+        for error_ms in [1, -1, 7, 300, -300, 499, -499] {
+            let mut current = base(1000, 3600);
+            let before = current.ms_of_day(NOW);
+            let fix = fix(&current, NOW, error_ms);
+            current.apply_fix(fix, NOW);
+
+            assert!(current.ms_of_day(NOW).abs_diff(before) <= 1, "{error_ms}");
+
+            // Never backwards, never faster than 2 ms per ms, and onto the
+            // fix in the end
+            let mut previous = current.ms_of_day(NOW);
+            for now in NOW + 1..NOW + 100_000 {
+                let shown = current.ms_of_day(now);
+                assert!(shown >= previous, "{error_ms} at {now}");
+                assert!(shown - previous <= 2, "{error_ms} at {now}");
+                previous = shown;
+            }
+            assert_eq!(
+                current.ms_of_day(NOW + 100_000),
+                fix.ms_of_day(NOW + 100_000)
+            );
+        }
+    }
+
+    #[test]
+    fn second_boundaries_follow_the_slew() {
+        for error_ms in [300, -300] {
+            let mut current = base(1000, 3600);
+            current.apply_fix(fix(&current, NOW, error_ms), NOW);
+
+            for now in (NOW..NOW + 60_000).step_by(7) {
+                let at = current.next_second_at(now);
+                assert!(at > now);
+                assert_eq!(
+                    current.ms_of_day(at) / 1000,
+                    current.ms_of_day(at - 1) / 1000 + 1
+                );
+                assert!(current.ms_of_day(at) % 1000 <= 1, "{error_ms} at {now}");
+            }
+        }
+    }
+
+    #[test]
+    fn large_error_steps() {
+        for error_ms in [slew::STEP_AT_MS, -slew::STEP_AT_MS, 700, -3000] {
+            let mut current = base(1000, 3600);
+            let fix = fix(&current, NOW, error_ms);
+            current.apply_fix(fix, NOW);
+
+            assert_eq!(current.slew, Slew::NONE);
+            assert_eq!(current.ms_of_day(NOW), fix.ms_of_day(NOW), "{error_ms}");
+        }
+    }
+
+    #[test]
+    fn error_estimate_follows_the_drift_measurement() {
+        // 100 ppm until measured, then only what correcting it leaves
+        let unmeasured = base(1000, 3600);
+        assert_eq!(unmeasured.estimated_error_ms(1000 + HOUR_MS), 360);
+        assert_eq!(unmeasured.estimated_error_ms(1000 + DAY_MS), 8640);
+
+        for ppm in [0, 37, -150] {
+            let measured = TimeBase {
+                drift_ppm: Some(ppm),
+                ..unmeasured
+            };
+            assert_eq!(measured.estimated_error_ms(1000 + HOUR_MS), 36, "{ppm}");
+            assert_eq!(measured.estimated_error_ms(1000 + DAY_MS), 864, "{ppm}");
+        }
+
+        // Before the anchor there is nothing to drift over
+        assert_eq!(unmeasured.estimated_error_ms(0), 0);
+    }
+
+    #[test]
+    fn seconds_stay_precise_longer_once_the_drift_is_measured() {
+        const S: u64 = 1000;
+        let unmeasured = base(1000, 3600);
+        let measured = TimeBase {
+            drift_ppm: Some(20),
+            ..unmeasured
+        };
+
+        // Half a second at 100 ppm, and at the 10 ppm left once measured
+        assert!(unmeasured.seconds_are_precise(1000 + 5000 * S));
+        assert!(!unmeasured.seconds_are_precise(1000 + 5010 * S));
+        assert!(measured.seconds_are_precise(1000 + 50_000 * S));
+        assert!(!measured.seconds_are_precise(1000 + 50_100 * S));
+
+        // A guess from before a reset never is
+        let restored = TimeBase {
+            synced: false,
+            ..measured
+        };
+        assert!(!restored.seconds_are_precise(1000));
+    }
+
+    #[test]
+    fn unsynced_time_steps() {
+        let mut current = TimeBase {
+            synced: false,
+            ..base(1000, 3600)
+        };
+        let fix = fix(&current, NOW, 100);
+        current.apply_fix(fix, NOW);
+
+        assert_eq!(current.slew, Slew::NONE);
+        assert_eq!(current.ms_of_day(NOW), fix.ms_of_day(NOW));
+    }
+}
@@ -0,0 +1,73 @@
+//! Small corrections worked off gradually instead of stepped. After a fix
+//! that disagrees with the running time by less than `STEP_AT_MS`, the
+//! time shown starts out where the old prediction was and catches up at
+//! `RATE_PPM`, so displayed seconds and PPS edges neither repeat nor skip.
+
+/// Errors this large are stepped at once, as are restored or hand-set
+/// times being replaced.
+pub const STEP_AT_MS: i64 = 500;
+
+/// How much faster or slower than true time the time shown runs while it
+/// catches up: a 500 ms error is gone in 50 s.
+const RATE_PPM: i64 = 10_000;
+
+/// A correction being worked off, in ms of true time past the anchor of
+/// the timebase it belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Slew {
+    /// When the correction started.
+    pub from_ms: i64,
+    /// How far the time shown was behind true time then; negative when
+    /// ahead.
+    pub offset_ms: i64,
+}
+
+impl Slew {
+    pub const NONE: Slew = Slew {
+        from_ms: 0,
+        offset_ms: 0,
+    };
+
+    fn duration_ms(&self) -> i64 {
+        self.offset_ms.abs() * 1_000_000 / RATE_PPM
+    }
+
+    /// How far the time shown is behind true time at `elapsed_ms`. Changes
+    /// by at most 1 ms per ms, so the time shown never runs backwards.
+    pub fn lag_ms(&self, elapsed_ms: i64) -> i64 {
+        let duration = self.duration_ms();
+        if duration == 0 {
+            return 0;
+        }
+
+        let left = duration - (elapsed_ms - self.from_ms).clamp(0, duration);
+        self.offset_ms * left / duration
+    }
+
+    /// The first `elapsed_ms` at which the time shown reaches `shown_ms`.
+    pub fn elapsed_at(&self, shown_ms: i64) -> i64 {
+        let duration = self.duration_ms();
+        let end = self.from_ms + duration;
+
+        // With the lag constant before the correction and gone after it
+        if duration == 0 || shown_ms >= end {
+            return shown_ms;
+        }
+        if shown_ms <= self.from_ms - self.offset_ms {
+            return shown_ms + self.offset_ms;
+        }
+
+        // shown = elapsed - offset * (end - elapsed) / duration, solved for
+        // elapsed; the lag's rounding may leave it a ms either way
+        let shown = |elapsed: i64| elapsed - self.lag_ms(elapsed);
+        let scaled = shown_ms * duration + self.offset_ms * end;
+        let mut elapsed = scaled.div_euclid(duration + self.offset_ms);
+        while shown(elapsed) < shown_ms {
+            elapsed += 1;
+        }
+        while shown(elapsed - 1) >= shown_ms {
+            elapsed -= 1;
+        }
+        elapsed
+    }
+}
@@ -0,0 +1,133 @@
+//! Independent watchdog, fed only while the display and JJY tasks both
+//! check in. Each beats its heartbeat at least every `HEARTBEAT_MS`, busy
+//! or waiting; once either has been quiet for `STALL_MS`, feeding stops and
+//! the IWDG resets the chip `TIMEOUT_MS` later.
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use ch32_hal::pac;
+use embassy_time::Timer;
+
+use crate::{log, monotonic};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Task {
+    /// The display, or whichever output task replaces it.
+    Display,
+    Jjy,
+}
+
+impl Task {
+    const ALL: [Task; 2] = [Task::Display, Task::Jjy];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Task::Display => "display",
+            Task::Jjy => "JJY",
+        }
+    }
+
+    /// The headless build has no display task to wait for.
+    fn watched(self) -> bool {
+        !(cfg!(feature = "monitor") && self == Task::Display)
+    }
+}
+
+static HEARTBEATS: [AtomicU32; Task::ALL.len()] = [AtomicU32::new(0), AtomicU32::new(0)];
+
+static RESET_BY_WATCHDOG: AtomicBool = AtomicBool::new(false);
+
+/// Longest a watched task may go between beats.
+pub const HEARTBEAT_MS: u64 = 1000;
+
+const STALL_MS: u64 = 3 * HEARTBEAT_MS;
+
+/// The LSI's 40 kHz divided by 64, at `PRESCALER` 4.
+const PRESCALER: u8 = 4;
+const TICK_HZ: u64 = 40_000 / 64;
+const TIMEOUT_MS: u64 = 4000;
+
+const KEY_UNLOCK: u16 = 0x5555;
+const KEY_FEED: u16 = 0xAAAA;
+const KEY_START: u16 = 0xCCCC;
+
+/// Called by `task` whenever it comes round its loop.
+pub fn beat(task: Task) {
+    // No read-modify-write atomics on this core, but every counter has a
+    // single writer
+    let beats = &HEARTBEATS[task as usize];
+    beats.store(
+        beats.load(Ordering::Relaxed).wrapping_add(1),
+        Ordering::Relaxed,
+    );
+}
+
+/// Reads and clears the reset flags. Call once at boot, before `was_reset`.
+pub fn init() -> bool {
+    let by_watchdog = pac::RCC.rstsckr().read().iwdgrstf();
+    pac::RCC.rstsckr().modify(|w| w.set_rmvf(true));
+
+    RESET_BY_WATCHDOG.store(by_watchdog, Ordering::Relaxed);
+    by_watchdog
+}
+
+/// Whether the watchdog caused the last reset.
+pub fn was_reset() -> bool {
+    RESET_BY_WATCHDOG.load(Ordering::Relaxed)
+}
+
+fn start() {
+    let iwdg = pac::IWDG;
+
+    iwdg.ctlr().write(|w| w.set_key(KEY_UNLOCK));
+    while iwdg.statr().read().pvu() {}
+    iwdg.pscr().write(|w| w.set_pr(PRESCALER));
+    while iwdg.statr().read().rvu() {}
+    iwdg.rldr()
+        .write(|w| w.set_rl((TIMEOUT_MS * TICK_HZ / 1000) as u16));
+
+    feed();
+    iwdg.ctlr().write(|w| w.set_key(KEY_START));
+}
+
+fn feed() {
+    pac::IWDG.ctlr().write(|w| w.set_key(KEY_FEED));
+}
+
+/// Starts the watchdog and keeps feeding it for as long as every watched
+/// task beats.
+#[embassy_executor::task]
+pub async fn watchdog_task() {
+    start();
+
+    let mut seen = [0; Task::ALL.len()];
+    let mut seen_at = [monotonic::now_ms(); Task::ALL.len()];
+    let mut stalled = false;
+
+    loop {
+        Timer::after_millis(HEARTBEAT_MS).await;
+        let now = monotonic::now_ms();
+
+        for task in Task::ALL {
+            let i = task as usize;
+            let beats = HEARTBEATS[i].load(Ordering::Relaxed);
+            if beats != seen[i] {
+                seen[i] = beats;
+                seen_at[i] = now;
+            }
+        }
+
+        let stall = Task::ALL
+            .into_iter()
+            .find(|t| t.watched() && now - seen_at[*t as usize] >= STALL_MS);
+
+        match stall {
+            None => feed(),
+            Some(task) if !stalled => {
+                log::error!("{} task stalled, letting the watchdog reset", task.as_str());
+                stalled = true;
+            }
+            Some(_) => {}
+        }
+    }
+}
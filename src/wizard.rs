@@ -0,0 +1,150 @@
+//! The walk through the settings on first boot: the language, the zone
+//! and the hour format, then the antenna, aimed by the pulses coming in,
+//! and the first sync. The page button drives it as it does setting the
+//! time: a tap changes the choice, and a hold takes it and moves on.
+//! Hardware-free; the firmware saves the choices once it ends.
+
+use crate::display::pages::Language;
+use crate::timebase;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Step {
+    Language,
+    Zone,
+    /// 12 or 24 hours.
+    HourFormat,
+    /// Turning the antenna while the pulses show how it comes in.
+    Antenna,
+    /// Waiting for the first decode, or leaving it to come on its own.
+    FirstSync,
+}
+
+const STEPS: [Step; 5] = [
+    Step::Language,
+    Step::Zone,
+    Step::HourFormat,
+    Step::Antenna,
+    Step::FirstSync,
+];
+
+/// The first-boot setup, at `step`, with what has been chosen so far.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Wizard {
+    pub step: Step,
+    pub language: Language,
+    /// Minutes east of UTC.
+    pub zone_minutes: i32,
+    pub twelve_hour: bool,
+    /// Steps with nothing to choose on this build, passed over.
+    skipped: &'static [Step],
+}
+
+impl Wizard {
+    /// Starts at the first step not `skipped`, with the choices as the
+    /// settings have them.
+    pub fn start(
+        language: Language,
+        zone_minutes: i32,
+        twelve_hour: bool,
+        skipped: &'static [Step],
+    ) -> Self {
+        let first = STEPS.iter().find(|step| !skipped.contains(step));
+        Wizard {
+            step: *first.unwrap_or(&Step::FirstSync),
+            language,
+            zone_minutes,
+            twelve_hour,
+            skipped,
+        }
+    }
+
+    /// Changes the choice of the step, wrapping around; zones step east.
+    /// The antenna and the sync have nothing to choose.
+    pub fn increment(self) -> Self {
+        match self.step {
+            Step::Language => Wizard {
+                language: self.language.next(),
+                ..self
+            },
+            Step::Zone => Wizard {
+                zone_minutes: timebase::next_zone(self.zone_minutes),
+                ..self
+            },
+            Step::HourFormat => Wizard {
+                twelve_hour: !self.twelve_hour,
+                ..self
+            },
+            Step::Antenna | Step::FirstSync => self,
+        }
+    }
+
+    /// Moves on to the next step not skipped, or `None` once the first
+    /// sync is done with.
+    pub fn advance(self) -> Option<Self> {
+        let at = STEPS.iter().position(|step| *step == self.step)?;
+        let step = STEPS[at + 1..]
+            .iter()
+            .find(|step| !self.skipped.contains(step))?;
+
+        Some(Wizard {
+            step: *step,
+            ..self
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The steps from `wizard` to the end, a hold each.
+    fn steps(mut wizard: Wizard) -> Vec<Step> {
+        let mut steps = vec![wizard.step];
+        while let Some(next) = wizard.advance() {
+            steps.push(next.step);
+            wizard = next;
+        }
+        steps
+    }
+
+    #[test]
+    fn every_step_comes_in_turn() {
+        let wizard = Wizard::start(Language::En, 9 * 60, false, &[]);
+        assert_eq!(steps(wizard), STEPS);
+    }
+
+    #[test]
+    fn skipped_steps_are_passed_over() {
+        let wizard = Wizard::start(Language::En, 9 * 60, false, &[Step::Language]);
+        assert_eq!(wizard.step, Step::Zone);
+
+        let wizard = Wizard::start(
+            Language::En,
+            9 * 60,
+            false,
+            &[Step::Language, Step::HourFormat],
+        );
+        assert_eq!(steps(wizard), [Step::Zone, Step::Antenna, Step::FirstSync]);
+    }
+
+    #[test]
+    fn taps_change_the_choice_of_the_step() {
+        let wizard = Wizard::start(Language::En, 14 * 60, false, &[]);
+        assert_eq!(wizard.increment().language, Language::Ja);
+        assert_eq!(wizard.increment().increment().language, Language::En);
+
+        // Around from the easternmost zone
+        let wizard = wizard.advance().unwrap();
+        assert_eq!(wizard.increment().zone_minutes, -12 * 60);
+        assert_eq!(wizard.increment().increment().zone_minutes, -12 * 60 + 30);
+
+        let wizard = wizard.advance().unwrap();
+        assert!(wizard.increment().twelve_hour);
+
+        // Nothing to choose from here on
+        let wizard = wizard.advance().unwrap();
+        assert_eq!(wizard.increment(), wizard);
+        let wizard = wizard.advance().unwrap();
+        assert_eq!(wizard.increment(), wizard);
+    }
+}